@@ -0,0 +1,284 @@
+//! Baselines for the hot path of the simulation: building the spatial index, querying it and a
+//! full step of forces. Every scenario is seeded, so runs before and after a change see exactly
+//! the same particles.
+//!
+//! Run with `cargo bench --no-default-features`, hot reloading makes the forces run on one thread.
+//! Narrow it down with a filter, like `cargo bench --no-default-features -- force_step/jormungandr`.
+
+use std::{
+    hint::black_box,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use abiogenesis::bench::{
+    BiomeMap, Dimensions, GRID_CELLS, Model, Particle, ParticleColour, ParticleIndex, Preset,
+    SimulationParams, SpatialHashGrid, SpatialIndex, TorodialMath, Velocity, WORLD_SIZE,
+    compute_forces, rebuild_spatial_index,
+};
+use bevy::{
+    ecs::schedule::ExecutorKind,
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution as _, Normal};
+
+const SEED: u64 = 0;
+
+const PARTICLES: [usize; 3] = [1_000, 3_000, 10_000];
+
+/// How the particles are laid out, from the easy case for the spatial hash to the worst.
+#[derive(Debug, Clone, Copy)]
+enum Distribution {
+    /// Evenly spread, like a fresh respawn.
+    Uniform,
+    /// Gathered into a few dozen organism sized clumps, like a world that has settled.
+    Clumpy,
+    /// Everything in one lump, so most particles share a handful of cells.
+    Blob,
+}
+
+impl Distribution {
+    const ALL: [Distribution; 3] = [
+        Distribution::Uniform,
+        Distribution::Clumpy,
+        Distribution::Blob,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Distribution::Uniform => "uniform",
+            Distribution::Clumpy => "clumpy",
+            Distribution::Blob => "blob",
+        }
+    }
+
+    fn positions(self, count: usize, bounds: Rect, rng: &mut StdRng) -> Vec<Vec2> {
+        let scatter = |rng: &mut StdRng, centre: Vec2, spread: f32| {
+            let normal = Normal::new(0.0, spread).unwrap();
+            bounds.toroidal_wrap(centre + Vec2::new(normal.sample(rng), normal.sample(rng)))
+        };
+
+        match self {
+            Distribution::Uniform => (0..count).map(|_| random_position(bounds, rng)).collect(),
+            Distribution::Clumpy => {
+                let centres = (0..24)
+                    .map(|_| random_position(bounds, rng))
+                    .collect::<Vec<_>>();
+
+                (0..count)
+                    .map(|index| scatter(rng, centres[index % centres.len()], 30.0))
+                    .collect()
+            }
+            Distribution::Blob => (0..count).map(|_| scatter(rng, Vec2::ZERO, 60.0)).collect(),
+        }
+    }
+}
+
+fn random_position(bounds: Rect, rng: &mut impl Rng) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(bounds.min.x..bounds.max.x),
+        rng.gen_range(bounds.min.y..bounds.max.y),
+    )
+}
+
+/// A preset's laws over a seeded layout of particles.
+struct Scenario {
+    model: Model,
+    params: SimulationParams,
+    particles: Vec<(Vec2, ParticleColour)>,
+}
+
+impl Scenario {
+    fn new(preset: &Preset, distribution: Distribution, count: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let positions = distribution.positions(count, bounds(), &mut rng);
+
+        // Coloured in turn like a respawn, so every colour is as common as the others
+        let particles = positions
+            .into_iter()
+            .enumerate()
+            .map(|(index, position)| {
+                let colour = ParticleColour::from_index(index % preset.params.num_colours.max(1));
+                (position, colour)
+            })
+            .collect();
+
+        Scenario {
+            model: preset.model.clone(),
+            params: preset.params,
+            particles,
+        }
+    }
+
+    /// A world holding just what the force step reads, with a default window so the simulation is
+    /// [`WORLD_SIZE`].
+    fn world(&self) -> World {
+        let mut world = World::new();
+
+        world.insert_resource(ParticleIndex(Vec::with_capacity(self.particles.len())));
+        world.insert_resource(Dimensions::Two);
+        world.insert_resource(SpatialIndex::new(bounds()));
+        world.insert_resource(BiomeMap::default());
+        world.insert_resource(self.model.clone());
+        world.insert_resource(self.params);
+        world.spawn(Window::default());
+
+        world.spawn_batch(
+            self.particles
+                .iter()
+                .map(|(position, colour)| {
+                    (
+                        Particle,
+                        Transform::from_translation(position.extend(0.0)),
+                        *colour,
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        world
+    }
+}
+
+fn bounds() -> Rect {
+    Rect::from_center_size(Vec2::ZERO, WORLD_SIZE)
+}
+
+/// The presets in the Book of Fates, by file name.
+fn presets() -> Vec<(String, Preset)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/presets");
+    let book = std::fs::read(directory.join("fates.book.json")).unwrap();
+
+    serde_json::from_slice::<Vec<String>>(&book)
+        .unwrap()
+        .into_iter()
+        .map(|path| {
+            let preset = Preset::from_slice(&std::fs::read(directory.join(&path)).unwrap());
+            let name = path.trim_end_matches(".preset.json").to_string();
+            (name, preset.unwrap())
+        })
+        .collect()
+}
+
+/// Filling the spatial index from scratch, as happens before every step. It doesn't depend on the
+/// laws, so there's no need to go through every preset.
+fn index_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index_build");
+    let (_, preset) = &presets()[0];
+
+    for distribution in Distribution::ALL {
+        for count in PARTICLES {
+            let scenario = Scenario::new(preset, distribution, count);
+            let mut grid = SpatialHashGrid::new(bounds(), GRID_CELLS);
+
+            group.bench_function(BenchmarkId::new(distribution.name(), count), |b| {
+                b.iter(|| {
+                    grid.clear();
+                    for (index, (position, colour)) in scenario.particles.iter().enumerate() {
+                        grid.insert(*position, (Entity::from_raw(index as u32), *colour));
+                    }
+                    black_box(&grid);
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+/// Every particle finding its neighbours within the preset's attraction radius, like the force
+/// step does, along with the toroidal distance to each.
+fn index_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index_query");
+    let torus = bounds();
+
+    for (name, preset) in presets() {
+        for distribution in Distribution::ALL {
+            for count in PARTICLES {
+                let scenario = Scenario::new(&preset, distribution, count);
+                let radius = scenario.params.attraction_radius;
+
+                let mut grid = SpatialHashGrid::new(bounds(), GRID_CELLS);
+                for (index, (position, _)) in scenario.particles.iter().enumerate() {
+                    grid.insert(*position, index);
+                }
+
+                let id = BenchmarkId::new(format!("{name}/{}", distribution.name()), count);
+                group.bench_function(id, |b| {
+                    b.iter(|| {
+                        scenario
+                            .particles
+                            .iter()
+                            .map(|(position, _)| {
+                                grid.query(*position, radius)
+                                    .map(|(other, _)| {
+                                        torus.toroidal_displacement(*position, other).length()
+                                    })
+                                    .sum::<f32>()
+                            })
+                            .sum::<f32>()
+                    });
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+/// One fixed update of the real systems: rebuilding the spatial index then computing the forces
+/// and moving every particle. The particles are put back before each step so every iteration
+/// starts from the same place.
+fn force_step(c: &mut Criterion) {
+    ComputeTaskPool::get_or_init(TaskPool::default);
+
+    let mut group = c.benchmark_group("force_step");
+    group.sample_size(10);
+
+    for (name, preset) in presets() {
+        for distribution in Distribution::ALL {
+            for count in PARTICLES {
+                let scenario = Scenario::new(&preset, distribution, count);
+                let mut world = scenario.world();
+
+                let mut schedule = Schedule::default();
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+                schedule.add_systems((rebuild_spatial_index, compute_forces).chain());
+
+                let mut particles =
+                    world.query_filtered::<(&mut Transform, &mut Velocity), With<Particle>>();
+
+                let id = BenchmarkId::new(format!("{name}/{}", distribution.name()), count);
+                group.bench_function(id, |b| {
+                    b.iter_custom(|iterations| {
+                        let mut elapsed = Duration::ZERO;
+
+                        for _ in 0..iterations {
+                            particles
+                                .iter_mut(&mut world)
+                                .zip(&scenario.particles)
+                                .for_each(|((mut transform, mut velocity), (position, _))| {
+                                    transform.translation = position.extend(0.0);
+                                    **velocity = Vec3::ZERO;
+                                });
+
+                            let start = Instant::now();
+                            schedule.run(&mut world);
+                            elapsed += start.elapsed();
+                        }
+
+                        elapsed
+                    });
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, index_build, index_query, force_step);
+criterion_main!(benches);
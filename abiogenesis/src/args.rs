@@ -0,0 +1,23 @@
+//! Reading flag values off the command line, shared by the app's flags and the windowless tools.
+
+use std::str::FromStr;
+
+use bevy::math::UVec2;
+
+/// Parses the value following `flag` on the command line.
+pub fn value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag} can't be {value:?}"))
+}
+
+/// Parses a window size like `1280x720`.
+pub fn window_size(flag: &str, size: Option<String>) -> Result<UVec2, String> {
+    let size = size.ok_or_else(|| format!("{flag} needs a value"))?;
+
+    size.split_once(['x', 'X'])
+        .and_then(|(width, height)| Some(UVec2::new(width.parse().ok()?, height.parse().ok()?)))
+        .filter(|size| size.cmpgt(UVec2::ZERO).all())
+        .ok_or_else(|| format!("{flag} can't be {size:?}, it should look like 1280x720"))
+}
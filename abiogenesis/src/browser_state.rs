@@ -1,15 +1,199 @@
+use std::str::FromStr;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize, de::Error};
+use serde_json::{Map, Value};
+
+use crate::particles::{
+    automation::Automations,
+    colour::NUM_COLOURS,
+    model::Model,
+    morph::{Morph, MorphSettings},
+    simulation::SimulationParams,
+    spawner::{Respawn, SpawnerConfig},
+};
+
+/// The version of the [`State`] format written by this build. Whenever the format changes this
+/// is bumped and a migration from the previous version is added to [`MIGRATIONS`], so links and
+/// files shared by older builds keep working.
+const STATE_VERSION: u64 = 3;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), serde_json::Error>;
+
+/// Each migration upgrades the state from the version at its index to the next.
+const MIGRATIONS: [Migration; STATE_VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// Everything that is shared between instances, on the web through the url and natively through
+/// files, the clipboard or stdin/stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct State {
+    version: u64,
+
+    /// The side length of the force matrix, so it can be fit to however many colours the
+    /// importing build has.
+    species: usize,
+
+    #[serde(flatten)]
+    params: SimulationParams,
+
+    #[serde(flatten)]
+    model: Model,
+
+    /// How the particles are laid out, with [`SpawnerConfig::None`] leaving them be on import.
+    spawner: SpawnerConfig,
+
+    /// How the laws change over time.
+    automations: Automations,
+}
+
+impl State {
+    pub fn new(
+        params: SimulationParams,
+        model: Model,
+        spawner: SpawnerConfig,
+        automations: Automations,
+    ) -> Self {
+        Self {
+            version: STATE_VERSION,
+            species: NUM_COLOURS,
+            params,
+            model,
+            spawner,
+            automations,
+        }
+    }
+
+    pub fn params(&self) -> &SimulationParams {
+        &self.params
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn apply(self, commands: &mut Commands) {
+        commands.insert_resource(self.params);
+        commands.insert_resource(self.model);
+        commands.insert_resource(self.automations);
+
+        if self.spawner != SpawnerConfig::None {
+            commands.insert_resource(self.spawner);
+            commands.trigger(Respawn);
+        }
+    }
+
+    /// Eases into the state when morphing, unless it comes with its own layout of particles.
+    pub fn morph_or_apply(self, morph: &MorphSettings, commands: &mut Commands) {
+        if morph.enabled() && self.spawner == SpawnerConfig::None {
+            commands.insert_resource(self.automations);
+            commands.trigger(Morph {
+                model: self.model,
+                params: self.params,
+            });
+        } else {
+            self.apply(commands);
+        }
+    }
+
+    /// Parses a state of any version, migrating it to the current one.
+    pub fn from_json(state: Value) -> Result<Self, serde_json::Error> {
+        let Value::Object(mut state) = state else {
+            return Err(serde_json::Error::custom("state is not an object"));
+        };
+
+        // The first version had no version tag at all
+        let version = match state.get("version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| serde_json::Error::custom("version is not an integer"))?,
+        };
+
+        if version > STATE_VERSION {
+            return Err(serde_json::Error::custom(format!(
+                "state version {version} is newer than the supported version {STATE_VERSION}"
+            )));
+        }
+
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut state)?;
+        }
+
+        let mut state = serde_json::from_value::<State>(Value::Object(state))?;
+
+        if state.model.len() != state.species * state.species {
+            return Err(serde_json::Error::custom(format!(
+                "expected {} weights for {} species, found {}",
+                state.species * state.species,
+                state.species,
+                state.model.len()
+            )));
+        }
+
+        state.model = state.model.resized(state.species);
+        state.params.num_colours = state.params.num_colours.min(NUM_COLOURS);
+        state.species = NUM_COLOURS;
+
+        Ok(state)
+    }
+}
+
+impl FromStr for State {
+    type Err = serde_json::Error;
+
+    fn from_str(state: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(state).and_then(Self::from_json)
+    }
+}
+
+/// Applies a state shared from elsewhere, letting anything interested know it arrived.
+fn receive(state: State, commands: &mut Commands) {
+    commands.trigger(Received(state.clone()));
+    state.apply(commands);
+}
+
+/// Version 0 was the flattened params and weights, always for six colours, with no version tag.
+fn v0_to_v1(state: &mut Map<String, Value>) -> Result<(), serde_json::Error> {
+    let weights = state
+        .get("weights")
+        .and_then(Value::as_array)
+        .ok_or_else(|| serde_json::Error::custom("missing weights"))?
+        .len();
+
+    state.insert("version".into(), 1.into());
+    state.insert("species".into(), weights.isqrt().into());
+
+    Ok(())
+}
+
+/// Version 1 didn't carry the spawner, so importing it left the particles as they were.
+fn v1_to_v2(state: &mut Map<String, Value>) -> Result<(), serde_json::Error> {
+    state.insert("version".into(), 2.into());
+    state.insert("spawner".into(), serde_json::to_value(SpawnerConfig::None)?);
+
+    Ok(())
+}
+
+/// Version 2 had no automations, the laws only changed when someone changed them.
+fn v2_to_v3(state: &mut Map<String, Value>) -> Result<(), serde_json::Error> {
+    state.insert("version".into(), 3.into());
+    state.insert("automations".into(), Value::Array(Vec::new()));
+
+    Ok(())
+}
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use std::cell::RefCell;
 
     use crate::{
-        browser_state::{Export, Import},
-        particles::{model::Model, simulation::SimulationParams},
+        browser_state::{Export, Import, State, receive},
+        particles::{
+            automation::Automations, model::Model, simulation::SimulationParams,
+            spawner::SpawnerConfig,
+        },
     };
     use bevy::prelude::*;
-    use serde::{Deserialize, Serialize};
     use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
 
     #[wasm_bindgen]
@@ -18,15 +202,6 @@ mod wasm {
         fn wasm_set_state(state: JsValue);
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
-    struct State {
-        #[serde(flatten)]
-        params: SimulationParams,
-
-        #[serde(flatten)]
-        model: Model,
-    }
-
     pub fn import(_trigger: Trigger<Import>) {
         wasm_get_state();
     }
@@ -37,7 +212,10 @@ mod wasm {
 
     #[wasm_bindgen]
     pub fn import_settings_from_js(value: JsValue) {
-        match serde_wasm_bindgen::from_value::<State>(value) {
+        match serde_wasm_bindgen::from_value::<serde_json::Value>(value)
+            .map_err(|err| err.to_string())
+            .and_then(|state| State::from_json(state).map_err(|err| err.to_string()))
+        {
             Ok(state) => {
                 PENDING_IMPORT.with_borrow_mut(|cell| {
                     *cell = Some(state);
@@ -53,31 +231,248 @@ mod wasm {
         let state = PENDING_IMPORT.with_borrow_mut(|cell| cell.take());
 
         if let Some(state) = state {
-            commands.insert_resource(state.params);
-            commands.insert_resource(state.model);
+            receive(state, &mut commands);
         }
     }
 
-    pub fn export(trigger: Trigger<Export>, params: Res<SimulationParams>, model: Res<Model>) {
-        let state = State {
-            params: *params,
-            model: model.clone(),
-        };
+    pub fn export(
+        trigger: Trigger<Export>,
+        params: Res<SimulationParams>,
+        model: Res<Model>,
+        spawner: Res<SpawnerConfig>,
+        automations: Res<Automations>,
+    ) {
+        let state = State::new(*params, model.clone(), spawner.clone(), automations.clone());
 
         wasm_set_state(serde_wasm_bindgen::to_value(&state).unwrap());
     }
 }
 
-mod native {
-    use crate::browser_state::{Export, Import};
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native {
+    use std::{
+        io::{BufRead, Write},
+        path::PathBuf,
+        str::FromStr,
+        sync::{
+            Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    use crate::{
+        browser_state::{Export, Import, State, receive},
+        particles::{
+            automation::Automations, model::Model, simulation::SimulationParams,
+            spawner::SpawnerConfig,
+        },
+    };
     use bevy::prelude::*;
 
-    pub fn import(_: Trigger<Import>) {
-        tracing::warn!("import not supported on native");
+    /// Where the state is read from and written to.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum Exchange {
+        /// A json file in the save directory.
+        #[default]
+        File,
+        /// The system clipboard, for pasting between instances or into a chat.
+        Clipboard,
+        /// A line of json on stdin, and on stdout, for driving the simulation from scripts.
+        Stdio,
+    }
+
+    impl FromStr for Exchange {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "file" => Ok(Exchange::File),
+                "clipboard" => Ok(Exchange::Clipboard),
+                "stdio" => Ok(Exchange::Stdio),
+                other => Err(format!("unknown exchange {other:?}")),
+            }
+        }
+    }
+
+    /// How "Receive" and "Bestow" behave natively, configured with the `ABIOGENESIS_EXCHANGE` and
+    /// `ABIOGENESIS_SAVE_DIR` environment variables.
+    #[derive(Debug, Resource, Clone)]
+    pub struct NativeExchange {
+        pub exchange: Exchange,
+        pub save_dir: PathBuf,
+    }
+
+    impl NativeExchange {
+        const STATE_FILE: &str = "abiogenesis_state.json";
+
+        pub fn state_path(&self) -> PathBuf {
+            self.save_dir.join(Self::STATE_FILE)
+        }
+    }
+
+    impl Default for NativeExchange {
+        fn default() -> Self {
+            let exchange = std::env::var("ABIOGENESIS_EXCHANGE")
+                .ok()
+                .and_then(|exchange| {
+                    exchange
+                        .parse()
+                        .inspect_err(|err| tracing::warn!(?err, "ignoring ABIOGENESIS_EXCHANGE"))
+                        .ok()
+                })
+                .unwrap_or_default();
+
+            NativeExchange {
+                exchange,
+                save_dir: save_dir(),
+            }
+        }
+    }
+
+    /// Where native builds keep everything they save, set with `ABIOGENESIS_SAVE_DIR`.
+    pub fn save_dir() -> PathBuf {
+        std::env::var_os("ABIOGENESIS_SAVE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Stdin blocks, so it's read on another thread and picked up by [`async_import`], just like
+    /// the wasm import waits on javascript.
+    static PENDING_IMPORT: Mutex<Option<State>> = Mutex::new(None);
+    static READING_STDIN: AtomicBool = AtomicBool::new(false);
+
+    fn parse(state: &str) -> Option<State> {
+        state
+            .parse::<State>()
+            .inspect_err(|err| tracing::warn!(?err, "failed to parse state"))
+            .ok()
+    }
+
+    pub fn import(
+        _: Trigger<Import>,
+        exchange: Res<NativeExchange>,
+        mut clipboard: NonSendMut<Clipboard>,
+        mut commands: Commands,
+    ) {
+        let state = match exchange.exchange {
+            Exchange::File => {
+                let path = exchange.state_path();
+                std::fs::read_to_string(&path)
+                    .inspect_err(|err| tracing::warn!(?err, ?path, "failed to read state"))
+                    .ok()
+            }
+            Exchange::Clipboard => clipboard.get_text(),
+            Exchange::Stdio => {
+                read_stdin();
+                None
+            }
+        };
+
+        if let Some(state) = state.as_deref().and_then(parse) {
+            receive(state, &mut commands);
+        }
+    }
+
+    fn read_stdin() {
+        if READING_STDIN.swap(true, Ordering::SeqCst) {
+            // Still waiting on the last import
+            return;
+        }
+
+        std::thread::spawn(|| {
+            let mut line = String::new();
+            match std::io::stdin().lock().read_line(&mut line) {
+                Ok(0) => tracing::warn!("stdin closed, nothing to import"),
+                Ok(_) => {
+                    if let Some(state) = parse(&line) {
+                        *PENDING_IMPORT.lock().unwrap() = Some(state);
+                    }
+                }
+                Err(err) => tracing::warn!(?err, "failed to read state from stdin"),
+            }
+
+            READING_STDIN.store(false, Ordering::SeqCst);
+        });
     }
 
-    pub fn export(_: Trigger<Export>) {
-        tracing::warn!("export not supported on native");
+    pub fn async_import(mut commands: Commands) {
+        let state = PENDING_IMPORT.lock().unwrap().take();
+
+        if let Some(state) = state {
+            receive(state, &mut commands);
+        }
+    }
+
+    pub fn export(
+        _: Trigger<Export>,
+        exchange: Res<NativeExchange>,
+        mut clipboard: NonSendMut<Clipboard>,
+        params: Res<SimulationParams>,
+        model: Res<Model>,
+        spawner: Res<SpawnerConfig>,
+        automations: Res<Automations>,
+    ) {
+        let state = State::new(*params, model.clone(), spawner.clone(), automations.clone());
+
+        let state = match serde_json::to_string(&state) {
+            Ok(state) => state,
+            Err(err) => {
+                tracing::warn!(?err, "failed to serialize state");
+                return;
+            }
+        };
+
+        match exchange.exchange {
+            Exchange::File => {
+                let path = exchange.state_path();
+                if let Err(err) = std::fs::create_dir_all(&exchange.save_dir)
+                    .and_then(|_| std::fs::write(&path, state))
+                {
+                    tracing::warn!(?err, ?path, "failed to write state");
+                } else {
+                    tracing::info!(?path, "exported state");
+                }
+            }
+            Exchange::Clipboard => clipboard.set_text(state),
+            Exchange::Stdio => {
+                let mut stdout = std::io::stdout().lock();
+                if let Err(err) = writeln!(stdout, "{state}").and_then(|_| stdout.flush()) {
+                    tracing::warn!(?err, "failed to write state to stdout");
+                }
+            }
+        }
+    }
+
+    /// The system clipboard, opened on first use. It's kept around because on some platforms the
+    /// copied text disappears along with the clipboard handle that set it.
+    #[derive(Default)]
+    pub struct Clipboard(Option<arboard::Clipboard>);
+
+    impl Clipboard {
+        fn handle(&mut self) -> Option<&mut arboard::Clipboard> {
+            if self.0.is_none() {
+                self.0 = arboard::Clipboard::new()
+                    .inspect_err(|err| tracing::warn!(?err, "failed to open clipboard"))
+                    .ok();
+            }
+
+            self.0.as_mut()
+        }
+
+        fn get_text(&mut self) -> Option<String> {
+            self.handle()?
+                .get_text()
+                .inspect_err(|err| tracing::warn!(?err, "failed to read clipboard"))
+                .ok()
+        }
+
+        fn set_text(&mut self, text: String) {
+            if let Some(clipboard) = self.handle()
+                && let Err(err) = clipboard.set_text(text)
+            {
+                tracing::warn!(?err, "failed to write clipboard");
+            }
+        }
     }
 }
 
@@ -91,7 +486,10 @@ impl Plugin for BrowserStatePlugin {
             .add_observer(wasm::export);
 
         #[cfg(not(target_arch = "wasm32"))]
-        app.add_observer(native::import)
+        app.init_resource::<native::NativeExchange>()
+            .init_non_send_resource::<native::Clipboard>()
+            .add_systems(PreUpdate, native::async_import)
+            .add_observer(native::import)
             .add_observer(native::export);
     }
 }
@@ -101,3 +499,220 @@ pub struct Export;
 
 #[derive(Debug, Default, Event, Copy, Clone)]
 pub struct Import;
+
+/// Triggered whenever a state arrives through [`Import`].
+#[derive(Debug, Event, Clone)]
+pub struct Received(pub State);
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::State;
+    use bevy::math::{Rect, Vec2};
+
+    use crate::particles::{
+        automation::{Automated, Automations, Curve, Wave},
+        colour::{NUM_COLOURS, ParticleColour},
+        presets::load_preset_file,
+        spawner::{SpawnShape, SpawnerConfig},
+    };
+
+    const V0_FIRST_GARDEN: &str = include_str!("../fixtures/state/v0_first_garden.json");
+    const V0_RAINBOW_SERPENT: &str = include_str!("../fixtures/state/v0_rainbow_serpent.json");
+    const V1_FIRST_GARDEN: &str = include_str!("../fixtures/state/v1_first_garden.json");
+    const V2_FIRST_GARDEN: &str = include_str!("../fixtures/state/v2_first_garden.json");
+    const V2_CUSTOM_SPAWNER: &str = include_str!("../fixtures/state/v2_custom_spawner.json");
+    const V3_FIRST_GARDEN: &str = include_str!("../fixtures/state/v3_first_garden.json");
+    const V3_CUSTOM_SPAWNER: &str = include_str!("../fixtures/state/v3_custom_spawner.json");
+    const V3_SEASONS: &str = include_str!("../fixtures/state/v3_seasons.json");
+
+    fn to_value(state: &State) -> Value {
+        serde_json::to_value(state).unwrap()
+    }
+
+    fn preset(name: &str) -> State {
+        let preset = load_preset_file(name);
+        State::new(
+            preset.params,
+            preset.model,
+            preset.spawner,
+            Automations::default(),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let state = preset("rainbow_serpent");
+        let parsed = serde_json::to_string(&state)
+            .unwrap()
+            .parse::<State>()
+            .unwrap();
+
+        assert_eq!(to_value(&parsed), to_value(&state));
+    }
+
+    #[test]
+    fn exports_current_version() {
+        let fixture = serde_json::from_str::<Value>(V3_FIRST_GARDEN).unwrap();
+        assert_eq!(to_value(&preset("the_first_garden")), fixture);
+    }
+
+    #[test]
+    fn current_version_fixture() {
+        let state = V3_FIRST_GARDEN.parse::<State>().unwrap();
+        assert_eq!(to_value(&state), to_value(&preset("the_first_garden")));
+    }
+
+    #[test]
+    fn version_2_fixture() {
+        let state = V2_FIRST_GARDEN.parse::<State>().unwrap();
+        assert_eq!(to_value(&state), to_value(&preset("the_first_garden")));
+    }
+
+    #[test]
+    fn version_1_fixture() {
+        let state = V1_FIRST_GARDEN.parse::<State>().unwrap();
+        assert_eq!(to_value(&state), to_value(&preset("the_first_garden")));
+    }
+
+    #[test]
+    fn custom_spawner() {
+        let state = V2_CUSTOM_SPAWNER.parse::<State>().unwrap();
+        assert_eq!(
+            state.spawner,
+            SpawnerConfig::Custom(vec![
+                (
+                    ParticleColour::Aqua,
+                    SpawnShape::HollowCircle {
+                        position: Vec2::ZERO,
+                        inner_radius: 200.0,
+                        outer_radius: 250.0,
+                    },
+                ),
+                (
+                    ParticleColour::Red,
+                    SpawnShape::Rect(Rect::new(-100.0, -50.0, 100.0, 50.0)),
+                ),
+                (
+                    ParticleColour::Green,
+                    SpawnShape::Circle {
+                        position: Vec2::new(300.0, 0.0),
+                        radius: 40.0,
+                    },
+                ),
+            ])
+        );
+
+        let fixture = serde_json::from_str::<Value>(V3_CUSTOM_SPAWNER).unwrap();
+        assert_eq!(to_value(&state), fixture);
+        assert_eq!(
+            to_value(&V3_CUSTOM_SPAWNER.parse::<State>().unwrap()),
+            fixture
+        );
+    }
+
+    #[test]
+    fn automations() {
+        let state = V3_SEASONS.parse::<State>().unwrap();
+        assert_eq!(
+            state.automations.get(Automated::Friction),
+            Some(&Curve::Oscillator {
+                wave: Wave::Sine,
+                centre: 2.0,
+                depth: 1.25,
+                period: 30.0,
+            })
+        );
+
+        let weight = Automated::Weight {
+            source: ParticleColour::Red,
+            target: ParticleColour::Green,
+        };
+        assert_eq!(
+            state.automations.get(weight).unwrap().value(30.0),
+            Some(0.0)
+        );
+
+        let fixture = serde_json::from_str::<Value>(V3_SEASONS).unwrap();
+        assert_eq!(to_value(&state), fixture);
+    }
+
+    #[test]
+    fn unversioned_fixtures() {
+        for (fixture, name) in [
+            (V0_FIRST_GARDEN, "the_first_garden"),
+            (V0_RAINBOW_SERPENT, "rainbow_serpent"),
+        ] {
+            let state = fixture.parse::<State>().unwrap();
+            assert_eq!(to_value(&state), to_value(&preset(name)));
+        }
+    }
+
+    #[test]
+    fn fewer_species() {
+        let state = r#"{
+            "version": 1, "species": 2, "friction": 2.0, "force_strength": 100.0,
+            "peak_attraction_radius": 50.0, "repulsion_radius": 25.0, "attraction_radius": 75.0,
+            "decay_rate": 100.0, "num_colours": 2, "weights": [10, 20, 30, 40]
+        }"#
+        .parse::<State>()
+        .unwrap();
+
+        assert_eq!(state.model.len(), NUM_COLOURS * NUM_COLOURS);
+        assert_eq!(
+            state
+                .model
+                .weight(ParticleColour::from_index(1), ParticleColour::from_index(0)),
+            0.3
+        );
+        assert_eq!(
+            state
+                .model
+                .weight(ParticleColour::from_index(2), ParticleColour::from_index(2)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn more_species() {
+        let weights = (0..64).map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+        let state = format!(
+            r#"{{
+                "version": 1, "species": 8, "friction": 2.0, "force_strength": 100.0,
+                "peak_attraction_radius": 50.0, "repulsion_radius": 25.0,
+                "attraction_radius": 75.0, "decay_rate": 100.0, "num_colours": 8,
+                "weights": [{weights}]
+            }}"#
+        )
+        .parse::<State>()
+        .unwrap();
+
+        assert_eq!(state.model.len(), NUM_COLOURS * NUM_COLOURS);
+        assert_eq!(state.params.num_colours, NUM_COLOURS);
+        assert_eq!(
+            state
+                .model
+                .weight(ParticleColour::from_index(1), ParticleColour::from_index(1)),
+            0.09
+        );
+    }
+
+    #[test]
+    fn mismatched_weights() {
+        let mut state = serde_json::from_str::<Value>(V3_FIRST_GARDEN).unwrap();
+        state["species"] = 5.into();
+        assert!(State::from_json(state).is_err());
+
+        let mut state = serde_json::from_str::<Value>(V0_FIRST_GARDEN).unwrap();
+        state["weights"].as_array_mut().unwrap().pop();
+        assert!(State::from_json(state).is_err());
+    }
+
+    #[test]
+    fn future_version() {
+        let mut state = serde_json::from_str::<Value>(V3_FIRST_GARDEN).unwrap();
+        state["version"] = 4.into();
+        assert!(State::from_json(state).is_err());
+    }
+}
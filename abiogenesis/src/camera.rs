@@ -1,5 +1,7 @@
 use crate::{
-    particles::{particle::Particle, size::SimulationSize},
+    particles::{
+        biomes::BiomeMap, dimensions::Dimensions, particle::Particle, size::SimulationSize,
+    },
     systems::AppSystems,
 };
 use bevy::{ecs::system::SystemParam, prelude::*};
@@ -8,8 +10,15 @@ pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera)
+        app.register_type::<OrbitCamera>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                swap_camera
+                    .run_if(resource_changed::<Dimensions>.and(not(resource_added::<Dimensions>))),
+            )
             .add_systems(Update, clamp_camera_zoom.in_set(AppSystems::Camera))
+            .add_systems(Update, update_orbit_camera.in_set(AppSystems::Camera))
             .add_systems(
                 Update,
                 camera_follow_particle
@@ -35,6 +44,61 @@ fn spawn_camera(
     commands.spawn((Name::from("Camera"), Camera2d));
 }
 
+pub const MIN_ORBIT_DISTANCE: f32 = 200.0;
+pub const MAX_ORBIT_DISTANCE: f32 = 5000.0;
+
+/// Spins the 3D camera around the center of the simulation volume.
+#[derive(Debug, Component, Reflect, Clone, Copy)]
+pub struct OrbitCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: -0.3,
+            distance: 2500.0,
+        }
+    }
+}
+
+fn swap_camera(
+    dimensions: Res<Dimensions>,
+    cameras: Query<Entity, With<Camera>>,
+    mut commands: Commands,
+) {
+    cameras
+        .iter()
+        .for_each(|camera| commands.entity(camera).despawn());
+
+    match *dimensions {
+        Dimensions::Two => commands.spawn((Name::from("Camera"), Camera2d)),
+        Dimensions::Three => commands.spawn((
+            Name::from("Camera"),
+            Camera3d::default(),
+            Projection::Perspective(PerspectiveProjection {
+                far: 10_000.0,
+                ..default()
+            }),
+            OrbitCamera::default(),
+            // Parenting the light to the camera keeps the side facing the viewer lit
+            children![DirectionalLight::default()],
+        )),
+    };
+}
+
+fn update_orbit_camera(mut cameras: Query<(&OrbitCamera, &mut Transform), Changed<OrbitCamera>>) {
+    cameras.iter_mut().for_each(|(orbit, mut transform)| {
+        let rotation = Quat::from_euler(EulerRot::YXZ, orbit.yaw, orbit.pitch, 0.0);
+
+        *transform = Transform::from_translation(rotation * Vec3::Z * orbit.distance)
+            .with_rotation(rotation);
+    });
+}
+
 fn clamp_camera_zoom(mut projection: Single<&mut Projection>, simulation_size: SimulationSize) {
     let Projection::Orthographic(ref mut project) = **projection else {
         return;
@@ -51,6 +115,7 @@ pub struct FollowParticle(pub Entity);
 fn camera_follow_particle(
     follow_particle: Res<FollowParticle>,
     mut particles: Query<&mut Transform, With<Particle>>,
+    mut biome_map: ResMut<BiomeMap>,
     projection: Single<&Projection>,
     time: Res<Time>,
     mut commands: Commands,
@@ -70,6 +135,8 @@ fn camera_follow_particle(
     particles.iter_mut().for_each(|mut transform| {
         transform.translation -= translation;
     });
+
+    biome_map.pan(-translation.truncate());
 }
 
 #[derive(SystemParam)]
@@ -81,6 +148,20 @@ impl Viewport<'_> {
     pub fn to_world(&self, position: Vec2) -> Vec2 {
         let (camera, transform) = *self.camera;
 
-        camera.viewport_to_world_2d(transform, position).unwrap()
+        // In 2D this is the whole world, in 3D it's the slice through the middle of the volume
+        let ray = camera.viewport_to_world(transform, position).unwrap();
+
+        ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Z))
+            .map_or(ray.origin, |distance| ray.get_point(distance))
+            .truncate()
+    }
+
+    /// Where a point in the world appears in the viewport, in logical pixels.
+    pub fn to_viewport(&self, position: Vec2) -> Option<Vec2> {
+        let (camera, transform) = *self.camera;
+
+        camera
+            .world_to_viewport(transform, position.extend(0.0))
+            .ok()
     }
 }
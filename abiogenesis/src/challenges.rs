@@ -0,0 +1,643 @@
+use std::{collections::BTreeSet, sync::LazyLock};
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    particles::{
+        biomes::BiomeMap,
+        colour::{NUM_COLOURS, ParticleColour},
+        dimensions::Dimensions,
+        lineage::{OrganismId, OrganismRegistry},
+        model::Model,
+        particle::Particle,
+        playback::{Playback, SIMULATION_STEP, SimulationClock, simulation_running},
+        simulation::SimulationParams,
+        spawner::{Respawn, SpawnShape, SpawnerConfig},
+    },
+    scenes::Scene,
+};
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    use crate::challenges::ChallengeRecord;
+
+    #[wasm_bindgen]
+    extern "C" {
+        fn wasm_load_challenges() -> Option<String>;
+        fn wasm_save_challenges(record: String);
+    }
+
+    pub fn load() -> ChallengeRecord {
+        wasm_load_challenges()
+            .and_then(|record| {
+                serde_json::from_str(&record)
+                    .inspect_err(|err| tracing::warn!(?err, "failed to parse challenge record"))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(record: &ChallengeRecord) {
+        match serde_json::to_string(record) {
+            Ok(record) => wasm_save_challenges(record),
+            Err(err) => tracing::warn!(?err, "failed to serialize challenge record"),
+        }
+    }
+}
+
+mod native {
+    use crate::challenges::ChallengeRecord;
+
+    const RECORD_PATH: &str = "abiogenesis_challenges.json";
+
+    pub fn load() -> ChallengeRecord {
+        let record = match std::fs::read_to_string(RECORD_PATH) {
+            Ok(record) => record,
+            // Nothing has been completed yet
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return ChallengeRecord::default();
+            }
+            Err(err) => {
+                tracing::warn!(?err, path = RECORD_PATH, "failed to read challenge record");
+                return ChallengeRecord::default();
+            }
+        };
+
+        serde_json::from_str(&record)
+            .inspect_err(|err| tracing::warn!(?err, "failed to parse challenge record"))
+            .unwrap_or_default()
+    }
+
+    pub fn save(record: &ChallengeRecord) {
+        let record = match serde_json::to_string(record) {
+            Ok(record) => record,
+            Err(err) => {
+                tracing::warn!(?err, "failed to serialize challenge record");
+                return;
+            }
+        };
+
+        if let Err(err) = std::fs::write(RECORD_PATH, record) {
+            tracing::warn!(?err, path = RECORD_PATH, "failed to write challenge record");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use wasm as storage;
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as storage;
+
+pub struct ChallengesPlugin;
+
+impl Plugin for ChallengesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load())
+            .add_observer(start_challenge)
+            .add_observer(abandon_challenge)
+            .add_systems(OnEnter(Scene::Challenge), setup_challenge)
+            .add_systems(OnExit(Scene::Challenge), end_challenge)
+            .add_systems(
+                Update,
+                enforce_locks.run_if(
+                    in_state(Scene::Challenge)
+                        .and(resource_changed::<SimulationParams>.or(resource_changed::<Model>)),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                evaluate_challenge.run_if(in_state(Scene::Challenge).and(simulation_running)),
+            )
+            .add_systems(
+                Update,
+                save_record.run_if(resource_changed::<ChallengeRecord>),
+            );
+    }
+}
+
+/// A law of creation that can't be changed while a challenge is underway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Law {
+    Friction,
+    ForceStrength,
+    AttractionRadius,
+    PeakAttractionRadius,
+    RepulsionRadius,
+    DecayRate,
+    Colours,
+    Model,
+}
+
+impl Law {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Law::Friction => "Friction",
+            Law::ForceStrength => "Force",
+            Law::AttractionRadius => "Attraction Radius",
+            Law::PeakAttractionRadius => "Peak Attraction Radius",
+            Law::RepulsionRadius => "Repulsion Radius",
+            Law::DecayRate => "Entropy",
+            Law::Colours => "Colours",
+            Law::Model => "Forces",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    /// An organism at least this massive lives for the given number of seconds.
+    Organism { mass: usize, seconds: f32 },
+    /// A colour holds at least this share of all the particles for the given number of seconds.
+    Dominance {
+        colour: ParticleColour,
+        share: f32,
+        seconds: f32,
+    },
+}
+
+impl Goal {
+    pub fn seconds(&self) -> f32 {
+        match self {
+            Goal::Organism { seconds, .. } | Goal::Dominance { seconds, .. } => *seconds,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Goal::Organism { mass, seconds } => {
+                format!("An organism of {mass} particles survives {seconds}s")
+            }
+            Goal::Dominance {
+                colour,
+                share,
+                seconds,
+            } => format!(
+                "{colour:?} holds {:.0}% of the mass for {seconds}s",
+                share * 100.0
+            ),
+        }
+    }
+
+    /// How long the goal has been met for, following on from the previous step. `reached` keeps
+    /// when each organism last grew to the goal's mass, so an old organism only counts the time
+    /// since, and starts over whenever it shrinks below it.
+    pub fn held(
+        &self,
+        held: f32,
+        registry: &OrganismRegistry,
+        colour_mass: [usize; NUM_COLOURS],
+        reached: &mut HashMap<OrganismId, f32>,
+        now: f32,
+    ) -> f32 {
+        match *self {
+            Goal::Organism { mass, .. } => {
+                let massive = registry
+                    .living()
+                    .iter()
+                    .filter(|organism| organism.cluster.total_mass() >= mass)
+                    .map(|organism| organism.id)
+                    .collect::<Vec<_>>();
+
+                reached.retain(|id, _| massive.contains(id));
+                massive.into_iter().for_each(|id| {
+                    reached.entry(id).or_insert(now);
+                });
+
+                reached
+                    .values()
+                    .map(|since| now - since)
+                    .fold(0.0, f32::max)
+            }
+            Goal::Dominance { colour, share, .. } => {
+                let total = colour_mass.iter().sum::<usize>();
+
+                if total > 0 && colour_mass[colour.index()] as f32 / total as f32 >= share {
+                    held + SIMULATION_STEP
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub name: &'static str,
+    pub model: Model,
+    pub params: SimulationParams,
+    pub spawner: SpawnerConfig,
+    pub locked: &'static [Law],
+    pub goal: Goal,
+}
+
+pub const NUM_CHALLENGES: usize = 4;
+pub static CHALLENGES: LazyLock<[Challenge; NUM_CHALLENGES]> = LazyLock::new(|| {
+    [
+        Challenge {
+            name: "First Breath",
+            model: Model::from_3x3([[0.3, 0.4, 0.5], [0.7, -0.4, 0.3], [-0.5, 0.5, 0.0]]),
+            params: SimulationParams {
+                num_colours: 3,
+                ..SimulationParams::DEFAULT
+            },
+            spawner: SpawnerConfig::Uniform,
+            locked: &[Law::Colours, Law::Model],
+            goal: Goal::Organism {
+                mass: 100,
+                seconds: 30.0,
+            },
+        },
+        Challenge {
+            name: "Leviathan",
+            model: Model::from_3x3([[-0.2, 0.2, 0.8], [0.0, 0.7, 0.3], [0.6, 0.3, -0.5]]),
+            params: SimulationParams {
+                num_colours: 3,
+                ..SimulationParams::DEFAULT
+            },
+            spawner: SpawnerConfig::Uniform,
+            locked: &[Law::Colours, Law::ForceStrength, Law::AttractionRadius],
+            goal: Goal::Organism {
+                mass: 200,
+                seconds: 60.0,
+            },
+        },
+        Challenge {
+            name: "Blue Dominion",
+            model: Model::from_3x3([[0.9, -0.8, -0.9], [-0.1, 0.9, -0.4], [0.6, 0.8, -0.5]]),
+            params: SimulationParams {
+                decay_rate: 80.0,
+                num_colours: 3,
+                ..SimulationParams::DEFAULT
+            },
+            spawner: SpawnerConfig::Uniform,
+            locked: &[Law::Colours, Law::DecayRate, Law::Friction],
+            goal: Goal::Dominance {
+                colour: ParticleColour::Blue,
+                share: 0.5,
+                seconds: 20.0,
+            },
+        },
+        Challenge {
+            name: "Serpent's Coil",
+            model: Model::from_6x6([
+                [-0.8, 0.7, 0.7, 0.0, 0.0, 0.0],
+                [0.7, -0.8, 0.7, 0.0, 0.0, 0.0],
+                [0.0, 0.7, -0.8, 0.7, 0.0, 0.0],
+                [0.0, 0.0, 0.7, -0.8, 0.7, 0.0],
+                [0.0, 0.0, 0.0, 0.7, -0.8, 0.7],
+                [0.7, 0.0, 0.0, 0.0, 0.7, -0.8],
+            ]),
+            params: SimulationParams {
+                friction: 2.5,
+                attraction_radius: 120.0,
+                peak_attraction_radius: 80.0,
+                repulsion_radius: 20.0,
+                decay_rate: 80.0,
+                num_colours: 6,
+                ..SimulationParams::DEFAULT
+            },
+            spawner: SpawnerConfig::Custom(vec![(
+                ParticleColour::Aqua,
+                SpawnShape::HollowCircle {
+                    position: Vec2::ZERO,
+                    inner_radius: 200.0,
+                    outer_radius: 250.0,
+                },
+            )]),
+            locked: &[
+                Law::Colours,
+                Law::Friction,
+                Law::PeakAttractionRadius,
+                Law::RepulsionRadius,
+                Law::DecayRate,
+            ],
+            goal: Goal::Organism {
+                mass: 300,
+                seconds: 45.0,
+            },
+        },
+    ]
+});
+
+/// The names of every challenge that has been completed, kept between sessions.
+#[derive(Debug, Resource, Default, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub completed: BTreeSet<String>,
+}
+
+#[derive(Debug, Resource, Clone)]
+pub struct ActiveChallenge {
+    pub index: usize,
+    /// Seconds the goal has been met for.
+    pub held: f32,
+    /// When each organism reached the mass an organism goal asks for.
+    pub reached: HashMap<OrganismId, f32>,
+    pub complete: bool,
+}
+
+impl ActiveChallenge {
+    pub fn challenge(&self) -> &'static Challenge {
+        &CHALLENGES[self.index]
+    }
+
+    pub fn progress(&self) -> f32 {
+        (self.held / self.challenge().goal.seconds()).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct StartChallenge(pub usize);
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct AbandonChallenge;
+
+fn start_challenge(
+    trigger: Trigger<StartChallenge>,
+    scene: Res<State<Scene>>,
+    mut next_scene: ResMut<NextState<Scene>>,
+    mut commands: Commands,
+) {
+    if trigger.0 >= NUM_CHALLENGES {
+        return;
+    }
+
+    commands.insert_resource(ActiveChallenge {
+        index: trigger.0,
+        held: 0.0,
+        reached: HashMap::default(),
+        complete: false,
+    });
+
+    // Switching between challenges stays in the scene, so there's no transition to set it up
+    match **scene {
+        Scene::Challenge => commands.run_system_cached(setup_challenge),
+        _ => next_scene.set(Scene::Challenge),
+    }
+}
+
+fn abandon_challenge(
+    _trigger: Trigger<AbandonChallenge>,
+    mut next_scene: ResMut<NextState<Scene>>,
+) {
+    next_scene.set(Scene::Sandbox);
+}
+
+fn setup_challenge(
+    active: Res<ActiveChallenge>,
+    mut biome_map: ResMut<BiomeMap>,
+    mut playback: ResMut<Playback>,
+    dimensions: Res<Dimensions>,
+    mut commands: Commands,
+) {
+    let challenge = active.challenge();
+
+    // Organisms are only found in the flat world, so flatten it before the respawn lays it out
+    if *dimensions != Dimensions::Two {
+        commands.insert_resource(Dimensions::Two);
+    }
+
+    commands.insert_resource(challenge.model.clone());
+    commands.insert_resource(challenge.params);
+    commands.insert_resource(challenge.spawner.clone());
+    commands.trigger(Respawn);
+
+    // Biomes would bend the locked laws
+    biome_map.replace(BiomeMap::default());
+    playback.paused = false;
+}
+
+fn end_challenge(mut commands: Commands) {
+    commands.remove_resource::<ActiveChallenge>();
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn enforce_locks(
+    active: Res<ActiveChallenge>,
+    mut params: ResMut<SimulationParams>,
+    mut model: ResMut<Model>,
+) {
+    let challenge = active.challenge();
+    let locked = challenge.params;
+
+    // Only write back what differs, so a restored law doesn't count as another change
+    for law in challenge.locked {
+        match law {
+            Law::Friction if params.friction != locked.friction => {
+                params.friction = locked.friction
+            }
+            Law::ForceStrength if params.force_strength != locked.force_strength => {
+                params.force_strength = locked.force_strength
+            }
+            Law::AttractionRadius if params.attraction_radius != locked.attraction_radius => {
+                params.attraction_radius = locked.attraction_radius
+            }
+            Law::PeakAttractionRadius
+                if params.peak_attraction_radius != locked.peak_attraction_radius =>
+            {
+                params.peak_attraction_radius = locked.peak_attraction_radius
+            }
+            Law::RepulsionRadius if params.repulsion_radius != locked.repulsion_radius => {
+                params.repulsion_radius = locked.repulsion_radius
+            }
+            Law::DecayRate if params.decay_rate != locked.decay_rate => {
+                params.decay_rate = locked.decay_rate
+            }
+            Law::Colours if params.num_colours != locked.num_colours => {
+                params.num_colours = locked.num_colours
+            }
+            Law::Model if model.as_slice() != challenge.model.as_slice() => {
+                *model = challenge.model.clone()
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn evaluate_challenge(
+    mut active: ResMut<ActiveChallenge>,
+    mut record: ResMut<ChallengeRecord>,
+    registry: Res<OrganismRegistry>,
+    clock: Res<SimulationClock>,
+    particles: Query<&ParticleColour, With<Particle>>,
+) {
+    if active.complete {
+        return;
+    }
+
+    let mut mass = [0; NUM_COLOURS];
+    particles
+        .iter()
+        .for_each(|colour| mass[colour.index()] += 1);
+
+    let active = &mut *active;
+    let challenge = active.challenge();
+    active.held = challenge
+        .goal
+        .held(active.held, &registry, mass, &mut active.reached, **clock);
+
+    if active.held >= challenge.goal.seconds() {
+        active.complete = true;
+        record.completed.insert(challenge.name.to_string());
+    }
+}
+
+fn save_record(record: Res<ChallengeRecord>) {
+    // Loading the record on startup counts as a change, there's no need to write it straight back
+    if record.is_added() {
+        return;
+    }
+
+    storage::save(&record);
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{ecs::entity::Entity, math::Vec2};
+
+    use super::*;
+    use crate::particles::organisms::Cluster;
+
+    fn cluster(size: u32) -> Cluster {
+        let mut mass = [0; NUM_COLOURS];
+        mass[0] = size as usize;
+
+        Cluster {
+            members: (0..size).map(Entity::from_raw).collect(),
+            centroid: Vec2::ZERO,
+            mass,
+            radius: 0.0,
+            velocity: Vec2::ZERO,
+        }
+    }
+
+    #[test]
+    fn organism_goal_counts_from_reaching_the_mass() {
+        let goal = Goal::Organism {
+            mass: 100,
+            seconds: 60.0,
+        };
+        let mut reached = HashMap::default();
+
+        let mut registry = OrganismRegistry::default();
+        registry.track(&[cluster(150)], 10.0);
+
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 10.0),
+            0.0
+        );
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 25.0),
+            15.0
+        );
+    }
+
+    #[test]
+    fn old_organism_growing_past_the_mass_starts_from_zero() {
+        let goal = Goal::Organism {
+            mass: 100,
+            seconds: 30.0,
+        };
+        let mut reached = HashMap::default();
+
+        // Small for a long time, then it grows
+        let mut registry = OrganismRegistry::default();
+        registry.track(&[cluster(50)], 0.0);
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 0.0),
+            0.0
+        );
+
+        registry.track(&[cluster(150)], 100.0);
+        assert_eq!(registry.living()[0].age(100.0), 100.0);
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 100.0),
+            0.0
+        );
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 110.0),
+            10.0
+        );
+
+        // Shrinking below the mass starts the count over
+        registry.track(&[cluster(60)], 120.0);
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 120.0),
+            0.0
+        );
+        registry.track(&[cluster(150)], 130.0);
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 130.0),
+            0.0
+        );
+        assert_eq!(
+            goal.held(0.0, &registry, [0; NUM_COLOURS], &mut reached, 135.0),
+            5.0
+        );
+    }
+
+    #[test]
+    fn organism_goal_ignores_small_organisms() {
+        let goal = Goal::Organism {
+            mass: 100,
+            seconds: 60.0,
+        };
+
+        let mut registry = OrganismRegistry::default();
+        registry.track(&[cluster(50)], 0.0);
+
+        assert_eq!(
+            goal.held(
+                0.0,
+                &registry,
+                [0; NUM_COLOURS],
+                &mut HashMap::default(),
+                25.0
+            ),
+            0.0
+        );
+    }
+
+    #[test]
+    fn dominance_accumulates_and_resets() {
+        let goal = Goal::Dominance {
+            colour: ParticleColour::Blue,
+            share: 0.5,
+            seconds: 20.0,
+        };
+        let registry = OrganismRegistry::default();
+
+        let mut reached = HashMap::default();
+
+        let held = goal.held(1.0, &registry, [10, 10, 20, 0, 0, 0], &mut reached, 0.0);
+        assert_eq!(held, 1.0 + SIMULATION_STEP);
+
+        let held = goal.held(held, &registry, [10, 10, 19, 0, 0, 0], &mut reached, 0.0);
+        assert_eq!(held, 0.0);
+    }
+
+    #[test]
+    fn dominance_of_nothing() {
+        let goal = Goal::Dominance {
+            colour: ParticleColour::Blue,
+            share: 0.0,
+            seconds: 20.0,
+        };
+
+        assert_eq!(
+            goal.held(
+                0.0,
+                &OrganismRegistry::default(),
+                [0; NUM_COLOURS],
+                &mut HashMap::default(),
+                0.0
+            ),
+            0.0
+        );
+    }
+}
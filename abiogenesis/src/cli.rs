@@ -0,0 +1,586 @@
+//! Flags for starting the desktop build somewhere other than the title screen and the first page
+//! of the Book of Fates, along with a headless mode that runs the laws without a window and writes
+//! what happened to a file, one line per frame.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    args::{value, window_size},
+    browser_state::State,
+    particles::{
+        headless::HeadlessWorld,
+        model::Model,
+        organisms::{Cluster, ClusterSettings},
+        particle::MAX_PARTICLES,
+        presets::{Preset, StartingPreset, file_stem},
+        simulation::SimulationParams,
+        spawner::RespawnOverrides,
+    },
+    scenes::Scene,
+    snapshot::WorldSnapshot,
+    ui::menu_button::StartWithUiHidden,
+};
+
+pub const USAGE: &str = "\
+usage: abiogenesis [START] [--seed N] [--particles N] [--hide-ui] [--skip-title] [--window WxH]
+       abiogenesis --headless [START] [--seed N] [--particles N] [--frames N] [--out FILE]
+       abiogenesis search --help
+       abiogenesis evolve --help
+
+START is at most one of --preset NAME, --state FILE or --snapshot FILE";
+
+/// Where presets are looked for when there's no asset server to find them, in the same place the
+/// asset server would look, so it works from any directory.
+fn presets_dir() -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join(AssetPlugin::default().file_path)
+        .join("presets")
+}
+
+const BOOK_FILE: &str = "fates.book.json";
+
+/// What the world starts with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Start {
+    /// The first preset in the book.
+    #[default]
+    Book,
+    /// A preset found by name or file name.
+    Preset(String),
+    /// A shared state, as exported.
+    State(PathBuf),
+    /// A snapshot of the world, particles and all.
+    Snapshot(PathBuf),
+}
+
+impl Start {
+    /// Reads whatever file the world starts from, so a bad one stops things before a window opens.
+    pub fn load(&self) -> Result<Beginning, String> {
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))
+        };
+
+        Ok(match self {
+            Start::Book => Beginning::Book,
+            Start::Preset(name) => Beginning::Preset(name.clone()),
+            Start::State(path) => Beginning::State(
+                State::from_str(&read(path)?)
+                    .map_err(|error| format!("{}: {error}", path.display()))?,
+            ),
+            Start::Snapshot(path) => Beginning::Snapshot(
+                WorldSnapshot::from_str(&read(path)?)
+                    .map_err(|error| format!("{}: {error}", path.display()))?,
+            ),
+        })
+    }
+}
+
+/// A [`Start`] with its file read.
+#[derive(Debug, Resource, Clone)]
+pub enum Beginning {
+    Book,
+    Preset(String),
+    State(State),
+    Snapshot(WorldSnapshot),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessSettings {
+    pub frames: usize,
+    /// Where the metrics go, one JSON object per line.
+    pub out: PathBuf,
+}
+
+impl Default for HeadlessSettings {
+    fn default() -> Self {
+        Self {
+            frames: 600,
+            out: PathBuf::from("metrics.jsonl"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cli {
+    pub start: Start,
+    pub seed: Option<u64>,
+    pub particles: Option<usize>,
+    pub hide_ui: bool,
+    pub skip_title: bool,
+    pub window: Option<UVec2>,
+    /// Runs without a window when set.
+    pub headless: Option<HeadlessSettings>,
+    pub help: bool,
+}
+
+impl Cli {
+    /// Reads the flags given to the binary, anything left out keeps its default.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli = Self::default();
+        let mut headless = false;
+        let mut frames = None;
+        let mut out = None;
+        let mut args = args.into_iter();
+
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--preset" => cli.start_with(Start::Preset(value(&flag, args.next())?))?,
+                "--state" => cli.start_with(Start::State(value(&flag, args.next())?))?,
+                "--snapshot" => cli.start_with(Start::Snapshot(value(&flag, args.next())?))?,
+                "--seed" => cli.seed = Some(value(&flag, args.next())?),
+                "--particles" => cli.particles = Some(value(&flag, args.next())?),
+                "--hide-ui" => cli.hide_ui = true,
+                "--skip-title" => cli.skip_title = true,
+                "--window" => cli.window = Some(window_size(&flag, args.next())?),
+                "--headless" => headless = true,
+                "--frames" => frames = Some(value(&flag, args.next())?),
+                "--out" => out = Some(value(&flag, args.next())?),
+                "--help" | "-h" => cli.help = true,
+                _ => return Err(format!("unknown argument {flag:?}\n{USAGE}")),
+            }
+        }
+
+        if headless {
+            if cli.hide_ui || cli.skip_title || cli.window.is_some() {
+                return Err(format!(
+                    "--hide-ui, --skip-title and --window need a window\n{USAGE}"
+                ));
+            }
+
+            let defaults = HeadlessSettings::default();
+            cli.headless = Some(HeadlessSettings {
+                frames: frames.unwrap_or(defaults.frames),
+                out: out.unwrap_or(defaults.out),
+            });
+        } else if frames.is_some() || out.is_some() {
+            return Err(format!("--frames and --out need --headless\n{USAGE}"));
+        }
+
+        Ok(cli)
+    }
+
+    fn start_with(&mut self, start: Start) -> Result<(), String> {
+        if self.start != Start::Book {
+            return Err(format!(
+                "only one of --preset, --state or --snapshot can be given\n{USAGE}"
+            ));
+        }
+
+        self.start = start;
+        Ok(())
+    }
+}
+
+/// Reads the flags, returning the plugin to start the app with them. Asking for help, running
+/// headless or getting the flags wrong all finish without opening a window, with how to exit.
+pub fn start(args: impl IntoIterator<Item = String>) -> Result<CliPlugin, AppExit> {
+    let fail = |error: String| {
+        eprintln!("{error}");
+        AppExit::error()
+    };
+
+    let cli = Cli::from_args(args).map_err(fail)?;
+    if cli.help {
+        println!("{USAGE}");
+        return Err(AppExit::Success);
+    }
+
+    let beginning = cli.start.load().map_err(fail)?;
+    match &cli.headless {
+        Some(settings) => {
+            Err(run_headless(&cli, beginning, settings).map_or_else(fail, |()| AppExit::Success))
+        }
+        None => Ok(CliPlugin { cli, beginning }),
+    }
+}
+
+/// Starts the windowed app the way the flags say.
+pub struct CliPlugin {
+    pub cli: Cli,
+    pub beginning: Beginning,
+}
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RespawnOverrides {
+            seed: self.cli.seed,
+            particles: self.cli.particles,
+        });
+
+        match &self.beginning {
+            Beginning::Book => {}
+            Beginning::Preset(name) => {
+                app.insert_resource(StartingPreset::Named(name.clone()));
+            }
+            Beginning::State(_) | Beginning::Snapshot(_) => {
+                app.insert_resource(StartingPreset::None)
+                    .insert_resource(self.beginning.clone())
+                    .add_systems(PostStartup, begin);
+            }
+        }
+
+        if self.cli.skip_title {
+            app.insert_state(Scene::Sandbox);
+        }
+
+        if self.cli.hide_ui {
+            app.insert_resource(StartWithUiHidden);
+        }
+
+        if let Some(size) = self.cli.window {
+            app.add_systems(Startup, move |mut window: Single<&mut Window>| {
+                window.resolution.set(size.x as f32, size.y as f32);
+            });
+        }
+    }
+}
+
+/// Replaces the world the particles were first spawned into with the state or snapshot.
+fn begin(world: &mut World) {
+    match world.remove_resource::<Beginning>() {
+        Some(Beginning::State(state)) => {
+            state.apply(&mut world.commands());
+            world.flush();
+        }
+        Some(Beginning::Snapshot(snapshot)) => snapshot.restore(world),
+        _ => {}
+    }
+}
+
+/// What the world looked like after a frame of a headless run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metrics {
+    pub frame: usize,
+    pub time: f32,
+    /// The number of particles of each colour in play.
+    pub colours: Vec<usize>,
+    pub mean_speed: f32,
+    pub organisms: usize,
+    /// Members of the largest organism.
+    pub largest_organism: usize,
+    /// The fraction of particles in any organism.
+    pub clustered: f32,
+}
+
+impl Metrics {
+    pub fn measure(
+        frame: usize,
+        world: &HeadlessWorld,
+        clusters: &[Cluster],
+        num_colours: usize,
+    ) -> Self {
+        let mut colours = vec![0; num_colours];
+        for colour in world.colours() {
+            if let Some(count) = colours.get_mut(colour.index()) {
+                *count += 1;
+            }
+        }
+
+        let particles = world.len().max(1) as f32;
+        let clustered = clusters
+            .iter()
+            .map(|cluster| cluster.members.len())
+            .sum::<usize>();
+
+        Self {
+            frame,
+            time: world.time,
+            colours,
+            mean_speed: world
+                .velocities()
+                .iter()
+                .map(|velocity| velocity.length())
+                .sum::<f32>()
+                / particles,
+            organisms: clusters.len(),
+            largest_organism: clusters
+                .iter()
+                .map(|cluster| cluster.members.len())
+                .max()
+                .unwrap_or(0),
+            clustered: clustered as f32 / particles,
+        }
+    }
+}
+
+/// Runs the laws the flags start with for `--frames` steps without a window, writing
+/// [`Metrics`] for each to `--out`.
+///
+/// Like the search, it leaves out spawner layouts, biomes and the third dimension. Presets and
+/// states start uniformly scattered, and snapshots carry on from their particles flattened onto
+/// the plane, without their biomes.
+pub fn run_headless(
+    cli: &Cli,
+    beginning: Beginning,
+    settings: &HeadlessSettings,
+) -> Result<(), String> {
+    let (model, params, recommended, snapshot) = match beginning {
+        Beginning::Book => {
+            let preset = first_preset(&presets_dir())?;
+            (preset.model, preset.params, preset.view.particles, None)
+        }
+        Beginning::Preset(name) => {
+            let directory = presets_dir();
+            let preset = find_preset(&directory, &name)?
+                .ok_or_else(|| format!("no preset called {name:?} in {}", directory.display()))?;
+            (preset.model, preset.params, preset.view.particles, None)
+        }
+        Beginning::State(state) => (state.model().clone(), *state.params(), None, None),
+        Beginning::Snapshot(snapshot) => {
+            if cli.particles.is_some() {
+                return Err(
+                    "--particles can't be used with --snapshot, its particles are kept".into(),
+                );
+            }
+
+            let particles = snapshot
+                .particles
+                .iter()
+                .map(|particle| {
+                    (
+                        particle.position.truncate(),
+                        particle.velocity.truncate(),
+                        particle.colour,
+                    )
+                })
+                .collect::<Vec<_>>();
+            (snapshot.model, snapshot.params, None, Some(particles))
+        }
+    };
+
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    let mut world = match snapshot {
+        Some(particles) => HeadlessWorld::from_particles(particles, seed),
+        None => {
+            let particles = cli
+                .particles
+                .or(recommended)
+                .unwrap_or(MAX_PARTICLES)
+                .min(MAX_PARTICLES);
+            HeadlessWorld::new(particles, params.num_colours, seed)
+        }
+    };
+
+    eprintln!(
+        "running {} frames of {} particles from seed {seed}",
+        settings.frames,
+        world.len()
+    );
+
+    let file = File::create(&settings.out)
+        .map_err(|error| format!("{}: {error}", settings.out.display()))?;
+    let mut out = BufWriter::new(file);
+
+    run(&mut world, &model, &params, settings.frames, |metrics| {
+        serde_json::to_writer(&mut out, &metrics).map_err(|error| error.to_string())?;
+        writeln!(out).map_err(|error| error.to_string())
+    })
+    .and_then(|()| out.flush().map_err(|error| error.to_string()))
+    .map_err(|error| format!("{}: {error}", settings.out.display()))
+}
+
+/// Steps `world` `frames` times, measuring it after each.
+pub fn run(
+    world: &mut HeadlessWorld,
+    model: &Model,
+    params: &SimulationParams,
+    frames: usize,
+    mut record: impl FnMut(Metrics) -> Result<(), String>,
+) -> Result<(), String> {
+    let settings = ClusterSettings::default();
+
+    for frame in 1..=frames {
+        world.step(model, params);
+        let clusters = world.clusters(&settings);
+        record(Metrics::measure(
+            frame,
+            world,
+            &clusters,
+            params.num_colours,
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn read_preset(path: &Path) -> Result<Preset, String> {
+    std::fs::read(path)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| Preset::from_slice(&bytes).map_err(|error| error.to_string()))
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// The first preset in the book in `directory`.
+fn first_preset(directory: &Path) -> Result<Preset, String> {
+    let book = directory.join(BOOK_FILE);
+    let paths = std::fs::read(&book)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| {
+            serde_json::from_slice::<Vec<String>>(&bytes).map_err(|error| error.to_string())
+        })
+        .map_err(|error| format!("{}: {error}", book.display()))?;
+
+    let first = paths
+        .first()
+        .ok_or_else(|| format!("{} is empty", book.display()))?;
+    read_preset(&directory.join(first))
+}
+
+/// Looks through `directory` and everything beneath it for a preset going by `name`, see
+/// [`Preset::is_called`], or with `name` as its file name.
+fn find_preset(directory: &Path, name: &str) -> Result<Option<Preset>, String> {
+    let mut entries = std::fs::read_dir(directory)
+        .map_err(|error| format!("{}: {error}", directory.display()))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if let Some(preset) = find_preset(&path, name)? {
+                return Ok(Some(preset));
+            }
+        } else if let Some(stem) = file_stem(&path) {
+            // One broken file, like a half written discovery, shouldn't hide every other preset
+            let preset = match read_preset(&path) {
+                Ok(preset) => preset,
+                Err(error) => {
+                    eprintln!("skipping {error}");
+                    continue;
+                }
+            };
+
+            if stem == name || preset.is_called(name) {
+                return Ok(Some(preset));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use bevy::math::UVec2;
+
+    use super::{Cli, HeadlessSettings, Start, find_preset, first_preset, run};
+    use crate::particles::{headless::HeadlessWorld, presets::load_preset_file};
+
+    fn parse(args: &str) -> Result<Cli, String> {
+        Cli::from_args(args.split_whitespace().map(String::from))
+    }
+
+    fn presets() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/presets")
+    }
+
+    #[test]
+    fn no_flags() {
+        assert_eq!(parse(""), Ok(Cli::default()));
+    }
+
+    #[test]
+    fn windowed_flags() {
+        let cli =
+            parse("--preset jormungandr --seed 7 --particles 500 --hide-ui --skip-title --window 1280x720")
+                .unwrap();
+
+        assert_eq!(cli.start, Start::Preset("jormungandr".to_string()));
+        assert_eq!(cli.seed, Some(7));
+        assert_eq!(cli.particles, Some(500));
+        assert!(cli.hide_ui);
+        assert!(cli.skip_title);
+        assert_eq!(cli.window, Some(UVec2::new(1280, 720)));
+        assert_eq!(cli.headless, None);
+    }
+
+    #[test]
+    fn headless_flags() {
+        let cli = parse("--headless --frames 30 --out run.jsonl --state garden.json").unwrap();
+
+        assert_eq!(cli.start, Start::State(PathBuf::from("garden.json")));
+        assert_eq!(
+            cli.headless,
+            Some(HeadlessSettings {
+                frames: 30,
+                out: PathBuf::from("run.jsonl"),
+            })
+        );
+
+        assert_eq!(
+            parse("--headless").unwrap().headless,
+            Some(HeadlessSettings::default())
+        );
+    }
+
+    #[test]
+    fn bad_flags() {
+        assert!(parse("--preset a --snapshot b.json").is_err());
+        assert!(parse("--frames 10").is_err());
+        assert!(parse("--headless --hide-ui").is_err());
+        assert!(parse("--window 1280").is_err());
+        assert!(parse("--window 0x720").is_err());
+        assert!(parse("--seed").is_err());
+        assert!(parse("--fast").is_err());
+    }
+
+    #[test]
+    fn finds_presets_on_disk() {
+        let first = first_preset(&presets()).unwrap();
+        assert_eq!(first.name, load_preset_file("the_first_garden").name);
+
+        let by_name = find_preset(&presets(), "Jörmungandr").unwrap().unwrap();
+        let by_file = find_preset(&presets(), "jormungandr").unwrap().unwrap();
+        assert_eq!(by_name.name, by_file.name);
+
+        assert!(find_preset(&presets(), "nothing").unwrap().is_none());
+    }
+
+    #[test]
+    fn skips_broken_presets() {
+        let directory = std::env::temp_dir().join(format!("abiogenesis_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a_broken.preset.json"), "{\"name\": ").unwrap();
+        std::fs::copy(
+            presets().join("jormungandr.preset.json"),
+            directory.join("jormungandr.preset.json"),
+        )
+        .unwrap();
+
+        let found = find_preset(&directory, "jormungandr");
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(found.unwrap().is_some());
+    }
+
+    #[test]
+    fn measures_every_frame() {
+        let preset = load_preset_file("the_first_garden");
+        let mut world = HeadlessWorld::new(300, preset.params.num_colours, 0);
+
+        let mut metrics = Vec::new();
+        run(&mut world, &preset.model, &preset.params, 5, |frame| {
+            metrics.push(frame);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(metrics.len(), 5);
+        assert_eq!(metrics[4].frame, 5);
+        assert!(metrics[4].time > metrics[0].time);
+        assert_eq!(metrics[0].colours.len(), preset.params.num_colours);
+        assert_eq!(metrics[0].colours.iter().sum::<usize>(), 300);
+        assert!((0.0..=1.0).contains(&metrics[4].clustered));
+    }
+}
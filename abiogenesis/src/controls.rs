@@ -3,9 +3,12 @@ use std::{cmp::Ordering, time::Duration};
 use bevy::prelude::*;
 
 use crate::{
-    camera::{FollowParticle, Viewport},
+    camera::{FollowParticle, MAX_ORBIT_DISTANCE, MIN_ORBIT_DISTANCE, OrbitCamera, Viewport},
     particles::{
-        particle::Particle, size::SimulationSize, spatial_index::SpatialIndex,
+        biomes::{BiomeMap, SelectedBiome},
+        particle::Particle,
+        size::SimulationSize,
+        spatial_index::SpatialIndex,
         spawner::SpawnParticle,
     },
     systems::AppSystems,
@@ -36,6 +39,7 @@ fn touch_pan(
     simulation_size: SimulationSize,
     touch_registration_timeout: Option<ResMut<TouchRegistrationTimeout>>,
     mut particles: Query<&mut Transform, With<Particle>>,
+    mut biome_map: ResMut<BiomeMap>,
     mut camera: Single<&mut Projection, With<Camera>>,
     mut commands: Commands,
 ) {
@@ -92,6 +96,9 @@ fn touch_pan(
     particles.iter_mut().for_each(|mut particle| {
         particle.translation = transform.transform_point(particle.translation);
     });
+
+    // The biome grid can't rotate, so it only follows the pan
+    biome_map.pan(transform.translation.truncate());
 }
 
 fn touch_registration_timeout(
@@ -150,6 +157,7 @@ pub fn select_follow_particle(
 pub fn drag_screen(
     trigger: Trigger<Pointer<Drag>>,
     mut particles: Query<&mut Transform, With<Particle>>,
+    mut biome_map: ResMut<BiomeMap>,
     projection: Single<&Projection>,
     mut commands: Commands,
 ) {
@@ -170,6 +178,8 @@ pub fn drag_screen(
     for mut particle in &mut particles {
         particle.translation += delta.extend(0.0);
     }
+
+    biome_map.pan(delta);
 }
 
 #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
@@ -186,6 +196,29 @@ pub fn scroll_wheel_zoom(
     project.scale = (project.scale - trigger.y.clamp(-0.05, 0.05)).clamp(min_zoom, max_zoom);
 }
 
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn orbit_camera_drag(
+    trigger: Trigger<Pointer<Drag>>,
+    tool: Res<Tool>,
+    mut orbit: Single<&mut OrbitCamera>,
+) {
+    // Right click always orbits, left click only does with the camera tool so the brushes still work
+    match trigger.button {
+        PointerButton::Secondary => {}
+        PointerButton::Primary if *tool == Tool::Camera => {}
+        _ => return,
+    }
+
+    orbit.yaw -= trigger.delta.x * 0.005;
+    orbit.pitch = (orbit.pitch - trigger.delta.y * 0.005).clamp(-1.5, 1.5);
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn orbit_camera_zoom(trigger: Trigger<Pointer<Scroll>>, mut orbit: Single<&mut OrbitCamera>) {
+    orbit.distance = (orbit.distance * (1.0 - 2.0 * trigger.y.clamp(-0.05, 0.05)))
+        .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+}
+
 #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
 pub fn particle_brush_start(
     trigger: Trigger<Pointer<Pressed>>,
@@ -271,3 +304,49 @@ pub fn eraser_brush_drag(
         commands.entity(entity).try_despawn();
     }
 }
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn terraform_brush_start(
+    trigger: Trigger<Pointer<Pressed>>,
+    tool: Res<Tool>,
+    viewport: Viewport,
+    simulation_size: SimulationSize,
+    selected_biome: Res<SelectedBiome>,
+    mut biome_map: ResMut<BiomeMap>,
+) {
+    let Tool::Terraform = *tool else {
+        return;
+    };
+
+    if !matches!(trigger.button, PointerButton::Primary) {
+        return;
+    }
+
+    let position = viewport.to_world(trigger.pointer_location.position);
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+
+    biome_map.paint(position, bounds, **selected_biome);
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn terraform_brush_drag(
+    trigger: Trigger<Pointer<Drag>>,
+    tool: Res<Tool>,
+    viewport: Viewport,
+    simulation_size: SimulationSize,
+    selected_biome: Res<SelectedBiome>,
+    mut biome_map: ResMut<BiomeMap>,
+) {
+    let Tool::Terraform = *tool else {
+        return;
+    };
+
+    if !matches!(trigger.button, PointerButton::Primary) {
+        return;
+    }
+
+    let position = viewport.to_world(trigger.pointer_location.position);
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+
+    biome_map.paint(position, bounds, **selected_biome);
+}
@@ -0,0 +1,524 @@
+//! Breeding organisms that compete, run with `abiogenesis evolve`. A population of models and
+//! params is run headless each generation, and the fittest are mutated and crossed over into the
+//! next. Every generation is logged, and its champion written out as a preset.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, Normal};
+use serde::Serialize;
+
+use crate::{
+    args::value,
+    particles::{
+        colour::{NUM_COLOURS, ParticleColour},
+        generators::{Generator, random_laws},
+        headless::HeadlessWorld,
+        model::Model,
+        organisms::Cluster,
+        presets::{Preset, RecommendedView},
+        simulation::{
+            ATTRACTION_RADIUS_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE, SimulationParams,
+        },
+        spawner::SpawnerConfig,
+    },
+    search::in_parallel,
+};
+
+const USAGE: &str = "usage: abiogenesis evolve [--fitness largest|<colour>] [--seed N] \
+                     [--generations N] [--population N] [--elite N] [--seconds N] \
+                     [--particles N] [--colours N] [--out DIR] [--log FILE]";
+
+/// How many of the population are picked at random to compete for each parent.
+const TOURNAMENT_SIZE: usize = 3;
+
+/// How often children are bred from two parents rather than just mutated from one.
+const CROSSOVER_RATE: f64 = 0.7;
+
+/// What the organisms are bred for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fitness {
+    /// The share of all the particles in the largest organism, once it has had time to grow.
+    LargestOrganism,
+    /// The share of all the particles that are this colour and held in an organism.
+    ColourShare(ParticleColour),
+}
+
+impl FromStr for Fitness {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.eq_ignore_ascii_case("largest") {
+            return Ok(Fitness::LargestOrganism);
+        }
+
+        name.parse()
+            .map(Fitness::ColourShare)
+            .map_err(|_| format!("{name:?} is neither \"largest\" nor a colour"))
+    }
+}
+
+impl Fitness {
+    pub fn describe(&self) -> String {
+        match self {
+            Fitness::LargestOrganism => "the largest surviving organism".to_string(),
+            Fitness::ColourShare(colour) => format!("{colour} holding the most mass"),
+        }
+    }
+
+    /// Runs `genome` for `seconds` and averages its fitness over the second half, giving the
+    /// organisms the first half to form.
+    pub fn measure(&self, world: &mut HeadlessWorld, genome: &Genome, seconds: f32) -> f32 {
+        let (mut samples, mut total) = (0, 0.0);
+
+        world.run(
+            &genome.model,
+            &genome.params,
+            seconds,
+            seconds / 2.0,
+            |world, clusters| {
+                let mass = match self {
+                    Fitness::LargestOrganism => clusters.first().map_or(0, Cluster::total_mass),
+                    Fitness::ColourShare(colour) => clusters
+                        .iter()
+                        .map(|cluster| cluster.mass[colour.index()])
+                        .sum(),
+                };
+
+                samples += 1;
+                total += mass as f32 / world.len().max(1) as f32;
+            },
+        );
+
+        total / samples.max(1) as f32
+    }
+}
+
+/// Everything evolution gets to change.
+#[derive(Debug, Clone)]
+pub struct Genome {
+    pub model: Model,
+    pub params: SimulationParams,
+}
+
+impl Genome {
+    /// A fresh genome from any generator but mutation.
+    pub fn random(num_colours: usize, rng: &mut impl Rng) -> Self {
+        let (_, model, params) = random_laws(num_colours..=num_colours, rng);
+        Genome { model, params }
+    }
+
+    /// Nudges every weight like [`Generator::Mutate`], and every param by a few percent of its
+    /// range.
+    pub fn mutate(&self, rng: &mut impl Rng) -> Self {
+        let model = Generator::Mutate.generate(&self.model, self.params.num_colours, rng);
+
+        let mut nudge = |value: f32, end: f32| {
+            (value + Normal::new(0.0, end * 0.05).unwrap().sample(rng)).clamp(0.0, end)
+        };
+
+        let mut params = self.params;
+        params.friction = nudge(params.friction, *FRICTION_RANGE.end());
+        params.force_strength = nudge(params.force_strength, *FORCE_STRENGTH_RANGE.end());
+        params.attraction_radius = nudge(params.attraction_radius, *ATTRACTION_RADIUS_RANGE.end());
+        params.peak_attraction_radius =
+            nudge(params.peak_attraction_radius, params.attraction_radius);
+        params.repulsion_radius = nudge(params.repulsion_radius, params.peak_attraction_radius);
+
+        Genome { model, params }
+    }
+
+    /// Each colour's row of weights comes whole from one parent or the other, so the way a colour
+    /// behaves is inherited together. Each param comes from either.
+    pub fn crossover(&self, other: &Genome, rng: &mut impl Rng) -> Self {
+        let mut model = self.model.clone();
+        for colour in (0..self.params.num_colours).map(ParticleColour::from_index) {
+            if rng.gen_bool(0.5) {
+                model.set_row(colour, &other.model.row(colour));
+            }
+        }
+
+        let mut pick = |a: f32, b: f32| if rng.gen_bool(0.5) { a } else { b };
+
+        let (a, b) = (self.params, other.params);
+        let mut params = SimulationParams {
+            friction: pick(a.friction, b.friction),
+            force_strength: pick(a.force_strength, b.force_strength),
+            attraction_radius: pick(a.attraction_radius, b.attraction_radius),
+            peak_attraction_radius: pick(a.peak_attraction_radius, b.peak_attraction_radius),
+            repulsion_radius: pick(a.repulsion_radius, b.repulsion_radius),
+            ..a
+        };
+
+        // Radii from different parents can end up out of order
+        let mut radii = [
+            params.repulsion_radius,
+            params.peak_attraction_radius,
+            params.attraction_radius,
+        ];
+        radii.sort_by(f32::total_cmp);
+        [
+            params.repulsion_radius,
+            params.peak_attraction_radius,
+            params.attraction_radius,
+        ] = radii;
+
+        Genome { model, params }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvolutionSettings {
+    pub fitness: Fitness,
+    pub seed: u64,
+    pub generations: usize,
+    pub population: usize,
+    /// How many of the fittest go through to the next generation untouched.
+    pub elite: usize,
+    /// How long to run each genome for, in simulated seconds.
+    pub seconds: f32,
+    pub particles: usize,
+    pub colours: usize,
+    /// Where to write each generation's champion.
+    pub out: PathBuf,
+    /// Where to write the fitness of each generation, one line of JSON per generation.
+    pub log: PathBuf,
+}
+
+impl Default for EvolutionSettings {
+    fn default() -> Self {
+        Self {
+            fitness: Fitness::LargestOrganism,
+            seed: 0,
+            generations: 30,
+            population: 48,
+            elite: 4,
+            seconds: 15.0,
+            particles: 1500,
+            colours: 4,
+            out: PathBuf::from("assets/presets/evolved"),
+            log: PathBuf::from("evolution.jsonl"),
+        }
+    }
+}
+
+impl EvolutionSettings {
+    /// Reads settings from the arguments after `evolve`, anything left out keeps its default.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut settings = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--fitness" => settings.fitness = value(&flag, args.next())?,
+                "--seed" => settings.seed = value(&flag, args.next())?,
+                "--generations" => settings.generations = value(&flag, args.next())?,
+                "--population" => settings.population = value(&flag, args.next())?,
+                "--elite" => settings.elite = value(&flag, args.next())?,
+                "--seconds" => settings.seconds = value(&flag, args.next())?,
+                "--particles" => settings.particles = value(&flag, args.next())?,
+                "--colours" => settings.colours = value(&flag, args.next())?,
+                "--out" => settings.out = value(&flag, args.next())?,
+                "--log" => settings.log = value(&flag, args.next())?,
+                _ => return Err(format!("unknown argument {flag:?}\n{USAGE}")),
+            }
+        }
+
+        if !(1..=NUM_COLOURS).contains(&settings.colours) {
+            return Err(format!("--colours must be from 1 to {NUM_COLOURS}"));
+        }
+
+        if let Fitness::ColourShare(colour) = settings.fitness
+            && colour.index() >= settings.colours
+        {
+            return Err(format!(
+                "{colour} isn't one of the first {} colours",
+                settings.colours
+            ));
+        }
+
+        if settings.population == 0 || settings.elite > settings.population {
+            return Err("--elite can't be more than a non-empty --population".to_string());
+        }
+
+        Ok(settings)
+    }
+}
+
+/// How a generation fared.
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationLog {
+    pub generation: usize,
+    pub best: f32,
+    pub mean: f32,
+    pub worst: f32,
+}
+
+/// Evolves a population for `settings.generations`, handing each generation's log and champion to
+/// `on_generation` as it's done.
+pub fn evolve(
+    settings: &EvolutionSettings,
+    mut on_generation: impl FnMut(&GenerationLog, &Genome),
+) {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut population = (0..settings.population)
+        .map(|_| Genome::random(settings.colours, &mut rng))
+        .collect::<Vec<_>>();
+
+    for generation in 0..settings.generations {
+        // Everyone in a generation faces the same starting world, but it changes every generation
+        // so nothing gets bred for one lucky layout
+        let world_seed = rng.r#gen();
+
+        // Each generation is reported as a whole once it's done
+        let fitness = in_parallel(
+            &population,
+            |genome| {
+                let mut world =
+                    HeadlessWorld::new(settings.particles, settings.colours, world_seed);
+                settings
+                    .fitness
+                    .measure(&mut world, genome, settings.seconds)
+            },
+            |_, _| {},
+        );
+
+        let mut ranked = population.into_iter().zip(fitness).collect::<Vec<_>>();
+        ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let log = GenerationLog {
+            generation,
+            best: ranked[0].1,
+            mean: ranked.iter().map(|(_, fitness)| fitness).sum::<f32>() / ranked.len() as f32,
+            worst: ranked[ranked.len() - 1].1,
+        };
+        on_generation(&log, &ranked[0].0);
+
+        population = breed(&ranked, settings.elite, &mut rng);
+    }
+}
+
+/// The next generation, with the elite carried over and the rest bred from tournament winners.
+fn breed(ranked: &[(Genome, f32)], elite: usize, rng: &mut StdRng) -> Vec<Genome> {
+    let tournament = |rng: &mut StdRng| {
+        (0..TOURNAMENT_SIZE)
+            .map(|_| &ranked[rng.gen_range(0..ranked.len())])
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(genome, _)| genome)
+            .unwrap()
+    };
+
+    let mut next = ranked[..elite]
+        .iter()
+        .map(|(genome, _)| genome.clone())
+        .collect::<Vec<_>>();
+
+    while next.len() < ranked.len() {
+        let parent = tournament(rng);
+
+        let child = if rng.gen_bool(CROSSOVER_RATE) {
+            parent.crossover(tournament(rng), rng)
+        } else {
+            parent.clone()
+        };
+
+        next.push(child.mutate(rng));
+    }
+
+    next
+}
+
+fn champion(settings: &EvolutionSettings, log: &GenerationLog, genome: &Genome) -> Preset {
+    Preset {
+        name: format!("Champion of Generation {}", log.generation),
+        description: format!(
+            "Bred for {} from seed {}, scoring {:.3} against a generation averaging {:.3}.",
+            settings.fitness.describe(),
+            settings.seed,
+            log.best,
+            log.mean,
+        ),
+        author: None,
+        tags: vec!["evolved".to_string()],
+        view: RecommendedView::default(),
+        thumbnail: None,
+        model: genome.model.clone(),
+        params: genome.params,
+        spawner: SpawnerConfig::None,
+    }
+}
+
+/// The whole of `abiogenesis evolve`, given the arguments after `evolve`.
+pub fn main(args: impl IntoIterator<Item = String>) -> AppExit {
+    match EvolutionSettings::from_args(args).and_then(|settings| run(&settings)) {
+        Ok(()) => AppExit::Success,
+        Err(error) => {
+            eprintln!("{error}");
+            AppExit::error()
+        }
+    }
+}
+
+/// Evolves, logging each generation and writing its champion as soon as it's known, so a long run
+/// can be stopped at any point.
+fn run(settings: &EvolutionSettings) -> Result<(), String> {
+    std::fs::create_dir_all(&settings.out).map_err(|error| error.to_string())?;
+    let mut log = File::create(&settings.log)
+        .map(BufWriter::new)
+        .map_err(|error| format!("{}: {error}", settings.log.display()))?;
+
+    let mut result = Ok(());
+    evolve(settings, |generation, genome| {
+        eprintln!(
+            "generation {}: best {:.3}, mean {:.3}, worst {:.3}",
+            generation.generation, generation.best, generation.mean, generation.worst
+        );
+
+        if result.is_ok() {
+            result = write_generation(settings, &mut log, generation, genome);
+        }
+    });
+
+    result
+}
+
+fn write_generation(
+    settings: &EvolutionSettings,
+    log: &mut impl Write,
+    generation: &GenerationLog,
+    genome: &Genome,
+) -> Result<(), String> {
+    let line = serde_json::to_string(generation).map_err(|error| error.to_string())?;
+    writeln!(log, "{line}")
+        .and_then(|_| log.flush())
+        .map_err(|error| format!("{}: {error}", settings.log.display()))?;
+
+    let path = settings.out.join(format!(
+        "generation_{:03}.preset.json",
+        generation.generation
+    ));
+
+    let json = champion(settings, generation, genome)
+        .to_json()
+        .map_err(|error| error.to_string())?;
+
+    std::fs::write(&path, json).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::{EvolutionSettings, Fitness, Genome, evolve};
+    use crate::particles::{
+        colour::ParticleColour,
+        simulation::{ATTRACTION_RADIUS_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE},
+    };
+
+    fn args(args: &str) -> Result<EvolutionSettings, String> {
+        EvolutionSettings::from_args(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn reads_fitness() {
+        assert_eq!("largest".parse(), Ok(Fitness::LargestOrganism));
+        assert_eq!(
+            "blue".parse(),
+            Ok(Fitness::ColourShare(ParticleColour::Blue))
+        );
+        assert!("mauve".parse::<Fitness>().is_err());
+    }
+
+    #[test]
+    fn reads_arguments() {
+        assert_eq!(args(""), Ok(EvolutionSettings::default()));
+
+        let settings = args("--fitness Green --generations 5 --population 10 --elite 2").unwrap();
+        assert_eq!(
+            settings.fitness,
+            Fitness::ColourShare(ParticleColour::Green)
+        );
+        assert_eq!(settings.generations, 5);
+        assert_eq!(settings.population, 10);
+        assert_eq!(settings.elite, 2);
+    }
+
+    #[test]
+    fn rejects_impossible_settings() {
+        assert!(args("--colours 0").is_err());
+        assert!(args("--colours 2 --fitness aqua").is_err());
+        assert!(args("--population 4 --elite 5").is_err());
+        assert!(args("--generation 5").is_err());
+    }
+
+    #[test]
+    fn mutation_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut genome = Genome::random(4, &mut rng);
+
+        for _ in 0..100 {
+            genome = genome.mutate(&mut rng);
+
+            let params = genome.params;
+            assert!(FRICTION_RANGE.contains(&params.friction));
+            assert!(FORCE_STRENGTH_RANGE.contains(&params.force_strength));
+            assert!(ATTRACTION_RADIUS_RANGE.contains(&params.attraction_radius));
+            assert!(params.repulsion_radius <= params.peak_attraction_radius);
+            assert!(params.peak_attraction_radius <= params.attraction_radius);
+            assert!(
+                genome
+                    .model
+                    .iter()
+                    .all(|weight| (-1.0..=1.0).contains(weight))
+            );
+        }
+    }
+
+    #[test]
+    fn children_inherit_whole_rows() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let (a, b) = (Genome::random(4, &mut rng), Genome::random(4, &mut rng));
+
+        let child = a.crossover(&b, &mut rng);
+
+        for colour in (0..4).map(ParticleColour::from_index) {
+            let row = child.model.row(colour);
+            assert!(row == a.model.row(colour) || row == b.model.row(colour));
+        }
+
+        assert!(child.params.repulsion_radius <= child.params.peak_attraction_radius);
+        assert!(child.params.peak_attraction_radius <= child.params.attraction_radius);
+    }
+
+    #[test]
+    fn logs_every_generation_reproducibly() {
+        let settings = EvolutionSettings {
+            generations: 3,
+            population: 4,
+            elite: 1,
+            seconds: 0.5,
+            particles: 50,
+            ..EvolutionSettings::default()
+        };
+
+        let run = || {
+            let mut logs = Vec::new();
+            evolve(&settings, |log, _| {
+                logs.push((log.generation, log.best, log.mean))
+            });
+            logs
+        };
+
+        let logs = run();
+        assert_eq!(logs.len(), 3);
+        assert!(logs.iter().all(|(_, best, mean)| best >= mean));
+        assert_eq!(logs, run());
+    }
+}
@@ -0,0 +1,185 @@
+#![feature(iter_collect_into)]
+#![feature(coroutines)]
+#![feature(gen_blocks)]
+#![feature(trait_alias)]
+
+use bevy::{
+    asset::{AssetMetaCheck, load_internal_binary_asset},
+    prelude::*,
+    window::WindowResolution,
+};
+use bevy_tweening::{AnimationSystem, TweeningPlugin};
+use particles::ParticlePlugin;
+use ui::UIPlugin;
+
+use crate::{
+    browser_state::BrowserStatePlugin, camera::CameraPlugin, challenges::ChallengesPlugin,
+    controls::ControlsPlugin, library::LibraryPlugin, scenes::ScenePlugin,
+    snapshot::SnapshotPlugin, systems::AppSystems,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod args;
+mod browser_state;
+mod bundle_fn;
+mod camera;
+mod challenges;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod controls;
+#[cfg(not(target_arch = "wasm32"))]
+mod evolution;
+mod library;
+mod math;
+mod observe;
+mod particles;
+mod scenes;
+#[cfg(not(target_arch = "wasm32"))]
+mod scripting;
+#[cfg(not(target_arch = "wasm32"))]
+mod search;
+mod snapshot;
+mod spatial_hash;
+mod systems;
+mod ui;
+
+/// The parts of the simulation measured by the benchmarks in `benches/`, everything else is
+/// private to the app.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::{
+        math::TorodialMath,
+        particles::{
+            biomes::BiomeMap,
+            colour::ParticleColour,
+            dimensions::Dimensions,
+            headless::WORLD_SIZE,
+            model::Model,
+            particle::{Particle, ParticleIndex, Velocity},
+            presets::Preset,
+            simulation::{SimulationParams, compute_forces, rebuild_spatial_index},
+            spatial_index::{GRID_CELLS, SpatialIndex},
+        },
+        spatial_hash::SpatialHashGrid,
+    };
+}
+
+const CLEAR_COLOUR: Color = Color::srgb_from_array([44.0 / 255.0, 30.0 / 255.0, 49.0 / 255.0]);
+
+/// Runs the app, or one of the windowless tools when asked to on the command line.
+pub fn run() -> AppExit {
+    #[cfg(not(target_arch = "wasm32"))]
+    match std::env::args().nth(1).as_deref() {
+        Some("search") => return search::main(std::env::args().skip(2)),
+        Some("evolve") => return evolution::main(std::env::args().skip(2)),
+        _ => {}
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let cli = match cli::start(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(exit) => return exit,
+    };
+
+    let mut app = App::new();
+
+    // Bevy Plugins;
+    bevy_systems(&mut app);
+    third_party_systems(&mut app);
+    app_systems(&mut app);
+
+    // Last, so the flags win over the defaults
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(cli);
+
+    app.run()
+}
+
+fn bevy_systems(app: &mut App) {
+    app.add_plugins(
+        DefaultPlugins
+            .set(AssetPlugin {
+                meta_check: AssetMetaCheck::Never,
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: Window {
+                    title: "ABIOGENESIS".into(),
+                    // // #[cfg(not(target_arch = "wasm32"))]
+                    // resolution: WindowResolution::new(2.0 * 362.0, 688.0),
+                    fit_canvas_to_parent: true,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            }),
+    )
+    .insert_resource(ClearColor(CLEAR_COLOUR));
+
+    #[cfg(feature = "debug_ui")]
+    app.insert_resource(UiDebugOptions {
+        enabled: true,
+        ..default()
+    });
+
+    #[cfg(all(debug_assertions, not(feature = "hot_reload")))]
+    app.edit_schedule(Update, |schedule| {
+        schedule.set_build_settings(ScheduleBuildSettings {
+            ambiguity_detection: LogLevel::Warn,
+            ..default()
+        });
+    });
+}
+
+fn third_party_systems(app: &mut App) {
+    app.add_plugins((
+        TweeningPlugin,
+        #[cfg(feature = "egui")]
+        (
+            bevy_inspector_egui::bevy_egui::EguiPlugin {
+                enable_multipass_for_primary_context: true,
+            },
+            bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
+        ),
+    ));
+
+    #[cfg(feature = "hot_reload")]
+    app.add_plugins(bevy_simple_subsecond_system::SimpleSubsecondPlugin::default());
+}
+
+fn app_systems(app: &mut App) {
+    app.add_plugins((
+        ParticlePlugin,
+        UIPlugin,
+        ScenePlugin,
+        CameraPlugin,
+        ControlsPlugin,
+        BrowserStatePlugin,
+        SnapshotPlugin,
+        ChallengesPlugin,
+        LibraryPlugin,
+    ));
+
+    // Scripts can only be started from the environment, which the browser doesn't have
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(scripting::ScriptingPlugin);
+
+    app.configure_sets(
+        Update,
+        (
+            AppSystems::TickTimers,
+            AnimationSystem::AnimationUpdate,
+            AppSystems::RecordInput,
+            AppSystems::Update,
+            AppSystems::Camera,
+        )
+            .chain(),
+    );
+
+    load_internal_binary_asset!(
+        app,
+        TextFont::default().font,
+        "../assets/fonts/CreatoDisplay-Bold.otf",
+        |bytes: &[u8], _path: String| { Font::try_from_bytes(bytes.to_vec()).unwrap() }
+    );
+}
@@ -0,0 +1,438 @@
+use bevy::prelude::*;
+use serde::{Serialize, Serializer, ser::SerializeSeq};
+use serde_json::Value;
+
+use crate::{
+    browser_state::{Received, State},
+    particles::{
+        automation::Automations, model::Model, morph::MorphSettings, simulation::SimulationParams,
+        spawner::SpawnerConfig,
+    },
+};
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    use crate::library::Library;
+
+    #[wasm_bindgen]
+    extern "C" {
+        fn wasm_load_library() -> Option<String>;
+        fn wasm_save_library(library: String);
+    }
+
+    pub fn load() -> Library {
+        wasm_load_library()
+            .map(|library| Library::parse(&library))
+            .unwrap_or_default()
+    }
+
+    pub fn save(library: &Library) {
+        match serde_json::to_string(library) {
+            Ok(library) => wasm_save_library(library),
+            Err(err) => tracing::warn!(?err, "failed to serialize library"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::path::PathBuf;
+
+    use crate::{browser_state::native::save_dir, library::Library};
+
+    fn path() -> PathBuf {
+        save_dir().join("abiogenesis_library.json")
+    }
+
+    pub fn load() -> Library {
+        let path = path();
+        match std::fs::read_to_string(&path) {
+            Ok(library) => Library::parse(&library),
+            // Nothing has been written in the book yet
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Library::default(),
+            Err(err) => {
+                tracing::warn!(?err, ?path, "failed to read library");
+                Library::default()
+            }
+        }
+    }
+
+    pub fn save(library: &Library) {
+        let library = match serde_json::to_string(library) {
+            Ok(library) => library,
+            Err(err) => {
+                tracing::warn!(?err, "failed to serialize library");
+                return;
+            }
+        };
+
+        let path = path();
+        if let Err(err) =
+            std::fs::create_dir_all(save_dir()).and_then(|_| std::fs::write(&path, library))
+        {
+            tracing::warn!(?err, ?path, "failed to write library");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use wasm as storage;
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as storage;
+
+pub struct LibraryPlugin;
+
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load())
+            .add_observer(save_preset)
+            .add_observer(load_preset)
+            .add_observer(rename_preset)
+            .add_observer(delete_preset)
+            .add_observer(move_preset)
+            .add_observer(offer_received)
+            .add_observer(save_received)
+            .add_observer(dismiss_received)
+            .add_systems(Update, save_library.run_if(resource_changed::<Library>));
+    }
+}
+
+/// A preset written into the Book of Fates by the user, rather than compiled in.
+#[derive(Debug, Clone)]
+pub struct UserPreset {
+    pub name: String,
+    pub state: State,
+}
+
+/// The user's own presets, in the order they've arranged them.
+///
+/// Each preset is stored as its name alongside a shared [`State`], so old books are migrated the
+/// same way as old links.
+#[derive(Debug, Resource, Default, Clone)]
+pub struct Library {
+    pub presets: Vec<UserPreset>,
+}
+
+impl Library {
+    /// Parses a saved library, dropping any presets that can no longer be read rather than losing
+    /// the whole book.
+    pub fn parse(library: &str) -> Self {
+        let presets = match serde_json::from_str::<Value>(library) {
+            Ok(Value::Array(presets)) => presets,
+            Ok(_) => {
+                tracing::warn!("library is not a list of presets");
+                return Library::default();
+            }
+            Err(err) => {
+                tracing::warn!(?err, "failed to parse library");
+                return Library::default();
+            }
+        };
+
+        let presets = presets
+            .into_iter()
+            .filter_map(|mut preset| {
+                let name = preset
+                    .as_object_mut()?
+                    .remove("name")?
+                    .as_str()?
+                    .to_string();
+                State::from_json(preset)
+                    .inspect_err(|err| tracing::warn!(?err, name, "failed to parse preset"))
+                    .ok()
+                    .map(|state| UserPreset { name, state })
+            })
+            .collect();
+
+        Library { presets }
+    }
+
+    /// Adds a preset to the end of the book, numbering the name if it's already taken.
+    pub fn add(&mut self, name: &str, state: State) -> usize {
+        let name = self.unique_name(name);
+        self.presets.push(UserPreset { name, state });
+        self.presets.len() - 1
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) {
+        let name = name.trim();
+        if name.is_empty()
+            || self
+                .presets
+                .get(index)
+                .is_none_or(|preset| preset.name == name)
+        {
+            return;
+        }
+
+        let name = self.unique_name(name);
+        self.presets[index].name = name;
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.presets.len() {
+            self.presets.remove(index);
+        }
+    }
+
+    /// Moves a preset up (negative) or down (positive) the book, stopping at either end.
+    pub fn shift(&mut self, index: usize, offset: isize) {
+        if index >= self.presets.len() {
+            return;
+        }
+
+        let target = index
+            .saturating_add_signed(offset)
+            .min(self.presets.len() - 1);
+        let preset = self.presets.remove(index);
+        self.presets.insert(target, preset);
+    }
+
+    fn unique_name(&self, name: &str) -> String {
+        let taken = |name: &str| self.presets.iter().any(|preset| preset.name == name);
+
+        if !taken(name) {
+            return name.to_string();
+        }
+
+        (2..)
+            .map(|n| format!("{name} {n}"))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+}
+
+impl Serialize for Library {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Entry<'a> {
+            name: &'a str,
+            #[serde(flatten)]
+            state: &'a State,
+        }
+
+        let mut seq = serializer.serialize_seq(Some(self.presets.len()))?;
+        for preset in &self.presets {
+            seq.serialize_element(&Entry {
+                name: &preset.name,
+                state: &preset.state,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+/// Writes the current model, params and spawner into the book.
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct SavePreset;
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct LoadPreset(pub usize);
+
+#[derive(Debug, Event, Clone, Reflect)]
+pub struct RenamePreset {
+    pub index: usize,
+    pub name: String,
+}
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct DeletePreset(pub usize);
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct MovePreset {
+    pub index: usize,
+    pub offset: isize,
+}
+
+/// A state that arrived through "Receive", waiting on whether it should be saved to the book.
+#[derive(Debug, Resource, Clone)]
+pub struct ReceivedState(pub State);
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct SaveReceived;
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct DismissReceived;
+
+fn save_preset(
+    _: Trigger<SavePreset>,
+    mut library: ResMut<Library>,
+    model: Res<Model>,
+    params: Res<SimulationParams>,
+    spawner: Res<SpawnerConfig>,
+    automations: Res<Automations>,
+) {
+    library.add(
+        "Unwritten Fate",
+        State::new(*params, model.clone(), spawner.clone(), automations.clone()),
+    );
+}
+
+fn load_preset(
+    trigger: Trigger<LoadPreset>,
+    library: Res<Library>,
+    morph: Res<MorphSettings>,
+    mut commands: Commands,
+) {
+    if let Some(preset) = library.presets.get(trigger.0) {
+        preset.state.clone().morph_or_apply(&morph, &mut commands);
+    }
+}
+
+fn rename_preset(trigger: Trigger<RenamePreset>, mut library: ResMut<Library>) {
+    library.rename(trigger.index, &trigger.name);
+}
+
+fn delete_preset(trigger: Trigger<DeletePreset>, mut library: ResMut<Library>) {
+    library.remove(trigger.0);
+}
+
+fn move_preset(trigger: Trigger<MovePreset>, mut library: ResMut<Library>) {
+    library.shift(trigger.index, trigger.offset);
+}
+
+fn offer_received(trigger: Trigger<Received>, mut commands: Commands) {
+    commands.insert_resource(ReceivedState(trigger.0.clone()));
+}
+
+fn save_received(
+    _: Trigger<SaveReceived>,
+    received: Option<Res<ReceivedState>>,
+    mut library: ResMut<Library>,
+    mut commands: Commands,
+) {
+    if let Some(received) = received {
+        library.add("Received Fate", received.0.clone());
+        commands.remove_resource::<ReceivedState>();
+    }
+}
+
+fn dismiss_received(_: Trigger<DismissReceived>, mut commands: Commands) {
+    commands.remove_resource::<ReceivedState>();
+}
+
+fn save_library(library: Res<Library>) {
+    // Loading the library on startup counts as a change, there's no need to write it straight back
+    if library.is_added() {
+        return;
+    }
+
+    storage::save(&library);
+}
+
+#[cfg(test)]
+mod test {
+    use super::Library;
+    use crate::{
+        browser_state::State,
+        particles::{automation::Automations, presets::load_preset_file},
+    };
+
+    fn state(name: &str) -> State {
+        let preset = load_preset_file(name);
+        State::new(
+            preset.params,
+            preset.model,
+            preset.spawner,
+            Automations::default(),
+        )
+    }
+
+    fn names(library: &Library) -> Vec<&str> {
+        library
+            .presets
+            .iter()
+            .map(|preset| preset.name.as_str())
+            .collect()
+    }
+
+    fn library(names: &[&str]) -> Library {
+        let mut library = Library::default();
+        for name in names {
+            library.add(name, state("the_first_garden"));
+        }
+        library
+    }
+
+    #[test]
+    fn unique_names() {
+        let library = library(&["Fate", "Fate", "Fate", "Other"]);
+        assert_eq!(names(&library), ["Fate", "Fate 2", "Fate 3", "Other"]);
+    }
+
+    #[test]
+    fn rename() {
+        let mut library = library(&["A", "B"]);
+
+        library.rename(0, "  C ");
+        assert_eq!(names(&library), ["C", "B"]);
+
+        library.rename(0, "B");
+        assert_eq!(names(&library), ["B 2", "B"]);
+
+        library.rename(1, "   ");
+        library.rename(5, "D");
+        assert_eq!(names(&library), ["B 2", "B"]);
+    }
+
+    #[test]
+    fn rename_to_itself() {
+        let mut library = library(&["A"]);
+        library.rename(0, "A");
+        assert_eq!(names(&library), ["A"]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut library = library(&["A", "B", "C"]);
+        library.remove(1);
+        library.remove(7);
+        assert_eq!(names(&library), ["A", "C"]);
+    }
+
+    #[test]
+    fn shift() {
+        let mut library = library(&["A", "B", "C"]);
+
+        library.shift(0, 1);
+        assert_eq!(names(&library), ["B", "A", "C"]);
+
+        library.shift(2, -2);
+        assert_eq!(names(&library), ["C", "B", "A"]);
+
+        library.shift(0, -1);
+        library.shift(2, 1);
+        assert_eq!(names(&library), ["C", "B", "A"]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut library = library(&["A"]);
+        library.add("B", state("rainbow_serpent"));
+
+        let parsed = Library::parse(&serde_json::to_string(&library).unwrap());
+
+        assert_eq!(names(&parsed), ["A", "B"]);
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&library).unwrap()
+        );
+    }
+
+    #[test]
+    fn skips_unreadable_presets() {
+        let garden = include_str!("../fixtures/state/v0_first_garden.json").trim();
+        let library = Library::parse(&format!(
+            r#"[{{"name": "Old", {}, {{"name": "Broken", "version": 99}}, {{"weights": []}}]"#,
+            &garden[1..]
+        ));
+
+        assert_eq!(names(&library), ["Old"]);
+    }
+}
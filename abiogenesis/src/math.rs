@@ -1,4 +1,6 @@
-use bevy::math::{Rect, Vec2};
+use std::f32::consts::TAU;
+
+use bevy::math::{Rect, Vec2, Vec3, primitives::Cuboid};
 
 #[inline]
 pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
@@ -16,11 +18,19 @@ pub fn remap(value: f32, a: f32, b: f32, c: f32, d: f32) -> f32 {
 }
 
 pub trait TorodialMath {
-    fn toroidal_displacement(&self, a: Vec2, b: Vec2) -> Vec2;
-    fn toroidal_wrap(&self, pos: Vec2) -> Vec2;
+    type Vector;
+
+    fn toroidal_displacement(&self, a: Self::Vector, b: Self::Vector) -> Self::Vector;
+    fn toroidal_wrap(&self, pos: Self::Vector) -> Self::Vector;
+    /// The circular mean of the points, so a group straddling an edge is centred on the edge
+    /// rather than in the middle of the world.
+    fn toroidal_mean(&self, points: impl IntoIterator<Item = Self::Vector>)
+    -> Option<Self::Vector>;
 }
 
 impl TorodialMath for Rect {
+    type Vector = Vec2;
+
     fn toroidal_displacement(&self, a: Vec2, b: Vec2) -> Vec2 {
         let width = self.max.x - self.min.x;
         let height = self.max.y - self.min.y;
@@ -70,6 +80,84 @@ impl TorodialMath for Rect {
 
         Vec2::new(x, y)
     }
+
+    fn toroidal_mean(&self, points: impl IntoIterator<Item = Vec2>) -> Option<Vec2> {
+        let size = self.size();
+
+        let (cos, sin, count) =
+            points
+                .into_iter()
+                .fold((Vec2::ZERO, Vec2::ZERO, 0), |(cos, sin, count), point| {
+                    let angle = (point - self.min) / size * TAU;
+                    (
+                        cos + Vec2::new(angle.x.cos(), angle.y.cos()),
+                        sin + Vec2::new(angle.x.sin(), angle.y.sin()),
+                        count + 1,
+                    )
+                });
+
+        (count > 0).then(|| {
+            let angle = Vec2::new(sin.x.atan2(cos.x), sin.y.atan2(cos.y));
+            self.toroidal_wrap(self.min + angle.rem_euclid(Vec2::splat(TAU)) / TAU * size)
+        })
+    }
+}
+
+// The 3D simulation volume is always centered on the origin, so a Cuboid is enough to describe it
+impl TorodialMath for Cuboid {
+    type Vector = Vec3;
+
+    fn toroidal_displacement(&self, a: Vec3, b: Vec3) -> Vec3 {
+        let size = 2.0 * self.half_size;
+        let mut displacement = b - a;
+
+        for axis in 0..3 {
+            if displacement[axis] > self.half_size[axis] {
+                displacement[axis] -= size[axis];
+            } else if displacement[axis] < -self.half_size[axis] {
+                displacement[axis] += size[axis];
+            }
+        }
+
+        displacement
+    }
+
+    fn toroidal_wrap(&self, pos: Vec3) -> Vec3 {
+        let size = 2.0 * self.half_size;
+        let mut pos = pos;
+
+        for axis in 0..3 {
+            while pos[axis] > self.half_size[axis] {
+                pos[axis] -= size[axis];
+            }
+            while pos[axis] < -self.half_size[axis] {
+                pos[axis] += size[axis];
+            }
+        }
+
+        pos
+    }
+
+    fn toroidal_mean(&self, points: impl IntoIterator<Item = Vec3>) -> Option<Vec3> {
+        let size = 2.0 * self.half_size;
+
+        let (cos, sin, count) =
+            points
+                .into_iter()
+                .fold((Vec3::ZERO, Vec3::ZERO, 0), |(cos, sin, count), point| {
+                    let angle = (point + self.half_size) / size * TAU;
+                    (
+                        cos + Vec3::new(angle.x.cos(), angle.y.cos(), angle.z.cos()),
+                        sin + Vec3::new(angle.x.sin(), angle.y.sin(), angle.z.sin()),
+                        count + 1,
+                    )
+                });
+
+        (count > 0).then(|| {
+            let angle = Vec3::new(sin.x.atan2(cos.x), sin.y.atan2(cos.y), sin.z.atan2(cos.z));
+            self.toroidal_wrap(angle.rem_euclid(Vec3::splat(TAU)) / TAU * size - self.half_size)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -211,4 +299,131 @@ mod test {
             );
         }
     }
+
+    mod toroidal_displacement_3d {
+        use bevy::math::{Vec3, primitives::Cuboid};
+
+        use super::super::TorodialMath;
+
+        #[test]
+        fn zero() {
+            assert_eq!(
+                Cuboid::from_length(100.).toroidal_displacement(Vec3::ZERO, Vec3::ZERO),
+                Vec3::ZERO
+            );
+        }
+
+        #[test]
+        fn diagonal() {
+            assert_eq!(
+                Cuboid::from_length(100.).toroidal_displacement(Vec3::ZERO, Vec3::splat(25.0)),
+                Vec3::splat(25.0)
+            );
+        }
+
+        #[test]
+        fn wrap_z() {
+            assert_eq!(
+                Cuboid::from_length(100.)
+                    .toroidal_displacement(Vec3::ZERO, Vec3::new(0.0, 0.0, 75.0)),
+                Vec3::new(0.0, 0.0, -25.0)
+            );
+        }
+
+        #[test]
+        fn wrap_xyz() {
+            assert_eq!(
+                Cuboid::new(100.0, 200.0, 50.0)
+                    .toroidal_displacement(Vec3::ZERO, Vec3::new(75.0, 150.0, -40.0)),
+                Vec3::new(-25.0, -50.0, 10.0)
+            );
+        }
+    }
+
+    mod toroidal_wrap_3d {
+        use bevy::math::{Vec3, primitives::Cuboid};
+
+        use super::super::TorodialMath;
+
+        #[test]
+        fn within_bounds() {
+            assert_eq!(
+                Cuboid::from_length(100.0).toroidal_wrap(Vec3::new(25.0, -25.0, 25.0)),
+                Vec3::new(25.0, -25.0, 25.0)
+            );
+        }
+
+        #[test]
+        fn wrap_z() {
+            assert_eq!(
+                Cuboid::from_length(100.0).toroidal_wrap(Vec3::new(0.0, 0.0, 75.0)),
+                Vec3::new(0.0, 0.0, -25.0)
+            );
+        }
+
+        #[test]
+        fn wrap_xyz() {
+            assert_eq!(
+                Cuboid::new(100.0, 200.0, 50.0).toroidal_wrap(Vec3::new(75.0, -150.0, 40.0)),
+                Vec3::new(-25.0, 50.0, -10.0)
+            );
+        }
+    }
+
+    mod toroidal_mean {
+        use bevy::math::{Rect, Vec2, Vec3, primitives::Cuboid};
+
+        use super::super::TorodialMath;
+
+        fn approx_eq(a: Vec2, b: Vec2) -> bool {
+            (a - b).abs().max_element() < 0.01
+        }
+
+        #[test]
+        fn empty() {
+            assert_eq!(
+                Rect::from_center_size(Vec2::ZERO, Vec2::splat(100.0)).toroidal_mean([]),
+                None
+            );
+        }
+
+        #[test]
+        fn within_bounds() {
+            let mean = Rect::from_center_size(Vec2::ZERO, Vec2::splat(100.0))
+                .toroidal_mean([Vec2::new(10.0, 10.0), Vec2::new(20.0, -10.0)])
+                .unwrap();
+
+            assert!(approx_eq(mean, Vec2::new(15.0, 0.0)));
+        }
+
+        #[test]
+        fn across_edge() {
+            let mean = Rect::from_center_size(Vec2::ZERO, Vec2::splat(100.0))
+                .toroidal_mean([Vec2::new(45.0, 0.0), Vec2::new(-45.0, 0.0)])
+                .unwrap();
+
+            assert!((mean.x.abs() - 50.0).abs() < 0.01);
+            assert!(mean.y.abs() < 0.01);
+        }
+
+        #[test]
+        fn non_zero_origin() {
+            let mean = Rect::from_center_size(Vec2::new(100.0, 50.0), Vec2::splat(80.0))
+                .toroidal_mean([Vec2::new(135.0, 50.0), Vec2::new(65.0, 50.0)])
+                .unwrap();
+
+            assert!(
+                approx_eq(mean, Vec2::new(60.0, 50.0)) || approx_eq(mean, Vec2::new(140.0, 50.0))
+            );
+        }
+
+        #[test]
+        fn across_edge_3d() {
+            let mean = Cuboid::from_length(100.0)
+                .toroidal_mean([Vec3::new(0.0, 0.0, 40.0), Vec3::new(0.0, 0.0, -50.0)])
+                .unwrap();
+
+            assert!((mean - Vec3::new(0.0, 0.0, 45.0)).abs().max_element() < 0.01);
+        }
+    }
 }
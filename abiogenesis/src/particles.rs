@@ -1,14 +1,29 @@
 use bevy::prelude::*;
 
 use crate::particles::{
-    decay::DecayPlugin, model::*, simulation::SimulationPlugin, size::SimulationSizePlugin,
-    spatial_index::SpatialIndexPlugin, spawner::SpawnerPlugin,
+    automation::AutomationPlugin, biomes::BiomesPlugin, decay::DecayPlugin,
+    dimensions::DimensionsPlugin, history::HistoryPlugin, lineage::LineagePlugin, model::*,
+    morph::MorphPlugin, organisms::OrganismsPlugin, playback::PlaybackPlugin,
+    presets::PresetsPlugin, recording::RecordingPlugin, simulation::SimulationPlugin,
+    size::SimulationSizePlugin, spatial_index::SpatialIndexPlugin, spawner::SpawnerPlugin,
 };
 
+pub mod automation;
+pub mod biomes;
 pub mod colour;
 pub mod decay;
+pub mod dimensions;
+pub mod generators;
+pub mod headless;
+pub mod history;
+pub mod lineage;
 pub mod model;
+pub mod morph;
+pub mod organisms;
 pub mod particle;
+pub mod playback;
+pub mod presets;
+pub mod recording;
 pub mod simulation;
 pub mod size;
 pub mod spatial_index;
@@ -20,8 +35,16 @@ impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             particle::ParticlePlugin,
-            ModelPlugin,
+            (ModelPlugin, MorphPlugin, AutomationPlugin),
+            BiomesPlugin,
             DecayPlugin,
+            DimensionsPlugin,
+            HistoryPlugin,
+            LineagePlugin,
+            OrganismsPlugin,
+            PlaybackPlugin,
+            PresetsPlugin,
+            RecordingPlugin,
             SimulationPlugin,
             SimulationSizePlugin,
             SpatialIndexPlugin,
@@ -0,0 +1,595 @@
+use std::{f32::consts::TAU, ops::RangeInclusive};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::lerp,
+    particles::{
+        colour::ParticleColour,
+        model::Model,
+        playback::{SimulationClock, simulation_running},
+        recording::replaying,
+        simulation::{
+            ATTRACTION_RADIUS_RANGE, DECAY_RATE_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE,
+            PEAK_ATTRACTION_RADIUS_RANGE, REPULSION_RADIUS_RANGE, SimulationParams,
+        },
+    },
+};
+
+pub struct AutomationPlugin;
+
+impl Plugin for AutomationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Automations>()
+            .add_observer(edit_automation)
+            // Before the step, so the forces always feel the laws of the moment
+            .add_systems(
+                FixedPreUpdate,
+                automate.run_if(simulation_running.and(not(replaying))),
+            );
+    }
+}
+
+/// Something that can be automated, any param or a single cell of the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Automated {
+    Friction,
+    ForceStrength,
+    AttractionRadius,
+    PeakAttractionRadius,
+    RepulsionRadius,
+    DecayRate,
+    Weight {
+        source: ParticleColour,
+        target: ParticleColour,
+    },
+}
+
+impl Automated {
+    pub fn name(&self) -> String {
+        match self {
+            Automated::Friction => "Friction".to_string(),
+            Automated::ForceStrength => "Force".to_string(),
+            Automated::AttractionRadius => "Attraction Radius".to_string(),
+            Automated::PeakAttractionRadius => "Peak Attraction Radius".to_string(),
+            Automated::RepulsionRadius => "Repulsion Radius".to_string(),
+            Automated::DecayRate => "Entropy".to_string(),
+            Automated::Weight { source, target } => format!("{source} to {target}"),
+        }
+    }
+
+    pub fn range(&self) -> RangeInclusive<f32> {
+        match self {
+            Automated::Friction => FRICTION_RANGE,
+            Automated::ForceStrength => FORCE_STRENGTH_RANGE,
+            Automated::AttractionRadius => ATTRACTION_RADIUS_RANGE,
+            Automated::PeakAttractionRadius => PEAK_ATTRACTION_RADIUS_RANGE,
+            Automated::RepulsionRadius => REPULSION_RADIUS_RANGE,
+            Automated::DecayRate => DECAY_RATE_RANGE,
+            Automated::Weight { .. } => -1.0..=1.0,
+        }
+    }
+
+    pub fn get(&self, model: &Model, params: &SimulationParams) -> f32 {
+        match *self {
+            Automated::Friction => params.friction,
+            Automated::ForceStrength => params.force_strength,
+            Automated::AttractionRadius => params.attraction_radius,
+            Automated::PeakAttractionRadius => params.peak_attraction_radius,
+            Automated::RepulsionRadius => params.repulsion_radius,
+            Automated::DecayRate => params.decay_rate,
+            Automated::Weight { source, target } => model.weight(source, target),
+        }
+    }
+
+    /// Sets the value, kept within the range of whatever is automated.
+    pub fn set(&self, model: &mut Model, params: &mut SimulationParams, value: f32) {
+        let value = value.clamp(*self.range().start(), *self.range().end());
+
+        match *self {
+            Automated::Friction => params.friction = value,
+            Automated::ForceStrength => params.force_strength = value,
+            Automated::AttractionRadius => params.attraction_radius = value,
+            Automated::PeakAttractionRadius => params.peak_attraction_radius = value,
+            Automated::RepulsionRadius => params.repulsion_radius = value,
+            Automated::DecayRate => params.decay_rate = value,
+            Automated::Weight { source, target } => model.set_weight(source, target, value),
+        }
+    }
+}
+
+/// The shape of an oscillator, each swinging between -1 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Wave {
+    Sine,
+    Triangle,
+    /// Wanders to a new random point four times a period, smoothly, the same way every time.
+    RandomWalk,
+}
+
+impl Wave {
+    pub const ALL: [Wave; 3] = [Wave::Sine, Wave::Triangle, Wave::RandomWalk];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Wave::Sine => "Sine",
+            Wave::Triangle => "Triangle",
+            Wave::RandomWalk => "Wander",
+        }
+    }
+
+    /// Where the wave is `phase` periods in.
+    pub fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Wave::Sine => (phase * TAU).sin(),
+            Wave::Triangle => 1.0 - 4.0 * ((phase + 0.25).rem_euclid(1.0) - 0.5).abs(),
+            Wave::RandomWalk => {
+                let steps = phase * 4.0;
+                let step = steps.floor();
+                let t = steps - step;
+
+                lerp(
+                    noise(step as i64),
+                    noise(step as i64 + 1),
+                    t * t * (3.0 - 2.0 * t),
+                )
+            }
+        }
+    }
+}
+
+/// A random value in [-1, 1] for each step, always the same for the same step.
+fn noise(step: i64) -> f32 {
+    // SplitMix64's finaliser
+    let mut x = (step as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+
+    (x >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Seconds of simulated time.
+    pub time: f32,
+    pub value: f32,
+}
+
+/// How an automated value changes with simulated time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Curve {
+    /// Swings `depth` either side of `centre`, once every `period` seconds.
+    Oscillator {
+        wave: Wave,
+        centre: f32,
+        depth: f32,
+        period: f32,
+    },
+    /// Eases linearly from one keyframe to the next, holding the first and last beyond them.
+    /// With a period the keyframes repeat, easing from the last back around to the first.
+    Keyframes {
+        keyframes: Vec<Keyframe>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        period: Option<f32>,
+    },
+}
+
+impl Curve {
+    /// The curve with a keyframe period no shorter than the keyframes themselves, so the loop
+    /// never cuts back to the first keyframe before reaching the last.
+    pub fn clamped(self) -> Self {
+        match self {
+            Curve::Keyframes { keyframes, period } => {
+                let span = keyframe_span(&keyframes);
+                Curve::Keyframes {
+                    period: period.map(|period| period.max(span)),
+                    keyframes,
+                }
+            }
+            oscillator => oscillator,
+        }
+    }
+
+    pub fn value(&self, time: f32) -> Option<f32> {
+        match self {
+            Curve::Oscillator {
+                wave,
+                centre,
+                depth,
+                period,
+            } => Some(centre + depth * wave.sample(time / period.max(f32::EPSILON))),
+            Curve::Keyframes { keyframes, period } => {
+                let (first, last) = (keyframes.first()?, keyframes.last()?);
+
+                let (time, before, after) = match period.filter(|period| *period > 0.0) {
+                    Some(period) => {
+                        // Time wrapped to one period starting from the first keyframe, the last
+                        // keyframe eases into the first of the next period
+                        let time = first.time + (time - first.time).rem_euclid(period);
+                        let next = Keyframe {
+                            time: first.time + period,
+                            value: first.value,
+                        };
+                        (time, *first, next)
+                    }
+                    None => (time, *first, *last),
+                };
+
+                if time <= before.time {
+                    return Some(before.value);
+                }
+
+                let window = keyframes
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(after))
+                    .collect::<Vec<_>>();
+
+                Some(window.windows(2).find(|pair| time <= pair[1].time).map_or(
+                    after.value,
+                    |pair| {
+                        let span = pair[1].time - pair[0].time;
+                        if span <= 0.0 {
+                            pair[1].value
+                        } else {
+                            lerp(pair[0].value, pair[1].value, (time - pair[0].time) / span)
+                        }
+                    },
+                ))
+            }
+        }
+    }
+}
+
+/// The time from the first keyframe to the last.
+fn keyframe_span(keyframes: &[Keyframe]) -> f32 {
+    match (keyframes.first(), keyframes.last()) {
+        (Some(first), Some(last)) => last.time - first.time,
+        _ => 0.0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Automation {
+    pub target: Automated,
+    pub curve: Curve,
+}
+
+/// Every automation running, at most one for each thing automated. Saved along with the state.
+#[derive(Debug, Resource, Clone, Default, PartialEq, Deref, Serialize)]
+#[serde(transparent)]
+pub struct Automations(Vec<Automation>);
+
+/// Loaded through [`Automations::set`], so whatever was saved is held to the same rules as edits.
+impl<'de> Deserialize<'de> for Automations {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut automations = Automations::default();
+        for Automation { target, curve } in Vec::<Automation>::deserialize(deserializer)? {
+            automations.set(target, curve);
+        }
+
+        Ok(automations)
+    }
+}
+
+impl Automations {
+    pub fn get(&self, target: Automated) -> Option<&Curve> {
+        self.iter()
+            .find(|automation| automation.target == target)
+            .map(|automation| &automation.curve)
+    }
+
+    pub fn set(&mut self, target: Automated, curve: Curve) {
+        self.remove(target);
+        self.0.push(Automation {
+            target,
+            curve: curve.clamped(),
+        });
+    }
+
+    pub fn remove(&mut self, target: Automated) {
+        self.0.retain(|automation| automation.target != target);
+    }
+}
+
+/// A change to one automation, from a slider's or the model matrix's context menu.
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub struct EditAutomation {
+    pub target: Automated,
+    pub edit: AutomationEdit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutomationEdit {
+    /// Oscillates around the current value, or switches the wave of an oscillator already there.
+    Oscillate(Wave),
+    /// Scales the period of an oscillator, or of keyframes that loop.
+    ScalePeriod(f32),
+    ScaleDepth(f32),
+    /// Adds the current value as a keyframe at the current time, replacing an oscillator.
+    AddKeyframe,
+    /// Has keyframes repeat, or stop repeating.
+    ToggleLoop,
+    Remove,
+}
+
+/// How long a new oscillator takes to come back around, in seconds.
+const DEFAULT_PERIOD: f32 = 30.0;
+
+/// How far a new oscillator swings either side, as a fraction of the whole range.
+const DEFAULT_DEPTH: f32 = 0.25;
+
+fn edit_automation(
+    trigger: Trigger<EditAutomation>,
+    mut automations: ResMut<Automations>,
+    model: Res<Model>,
+    params: Res<SimulationParams>,
+    clock: Res<SimulationClock>,
+) {
+    let EditAutomation { target, edit } = *trigger;
+    let value = target.get(&model, &params);
+    let current = automations.get(target).cloned();
+
+    let curve = match (edit, current) {
+        (
+            AutomationEdit::Oscillate(wave),
+            Some(Curve::Oscillator {
+                centre,
+                depth,
+                period,
+                ..
+            }),
+        ) => Curve::Oscillator {
+            wave,
+            centre,
+            depth,
+            period,
+        },
+        (AutomationEdit::Oscillate(wave), _) => Curve::Oscillator {
+            wave,
+            centre: value,
+            depth: DEFAULT_DEPTH * (target.range().end() - target.range().start()),
+            period: DEFAULT_PERIOD,
+        },
+        (
+            AutomationEdit::ScalePeriod(scale),
+            Some(Curve::Oscillator {
+                wave,
+                centre,
+                depth,
+                period,
+            }),
+        ) => Curve::Oscillator {
+            wave,
+            centre,
+            depth,
+            period: period * scale,
+        },
+        (
+            AutomationEdit::ScalePeriod(scale),
+            Some(Curve::Keyframes {
+                keyframes,
+                period: Some(period),
+            }),
+        ) => Curve::Keyframes {
+            keyframes,
+            period: Some(period * scale),
+        },
+        (
+            AutomationEdit::ScaleDepth(scale),
+            Some(Curve::Oscillator {
+                wave,
+                centre,
+                depth,
+                period,
+            }),
+        ) => Curve::Oscillator {
+            wave,
+            centre,
+            depth: depth * scale,
+            period,
+        },
+        (AutomationEdit::AddKeyframe, current) => {
+            let (mut keyframes, period) = match current {
+                Some(Curve::Keyframes { keyframes, period }) => (keyframes, period),
+                _ => (Vec::new(), None),
+            };
+
+            let time = **clock;
+            keyframes.retain(|keyframe| keyframe.time != time);
+            keyframes.push(Keyframe { time, value });
+            keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+            Curve::Keyframes { keyframes, period }
+        }
+        (AutomationEdit::ToggleLoop, Some(Curve::Keyframes { keyframes, period })) => {
+            // A loop comes back around to the first keyframe as long after the last as the
+            // keyframes are apart on average
+            let span = keyframe_span(&keyframes);
+            let period = match period {
+                Some(_) => None,
+                None => Some(span + span / keyframes.len().saturating_sub(1).max(1) as f32),
+            };
+
+            Curve::Keyframes { keyframes, period }
+        }
+        (AutomationEdit::Remove, _) => {
+            automations.remove(target);
+            return;
+        }
+        // Scaling only makes sense for oscillators and loops, and only keyframes loop
+        (
+            AutomationEdit::ScalePeriod(_)
+            | AutomationEdit::ScaleDepth(_)
+            | AutomationEdit::ToggleLoop,
+            _,
+        ) => return,
+    };
+
+    automations.set(target, curve);
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn automate(
+    automations: Res<Automations>,
+    clock: Res<SimulationClock>,
+    mut model: ResMut<Model>,
+    mut params: ResMut<SimulationParams>,
+) {
+    // Touching the laws when nothing is automated would have everything watching them redraw
+    if automations.is_empty() {
+        return;
+    }
+
+    for automation in automations.iter() {
+        if let Some(value) = automation.curve.value(**clock) {
+            automation.target.set(&mut model, &mut params, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Automated, Automations, Curve, Keyframe, Wave};
+    use crate::particles::{colour::ParticleColour, model::Model, simulation::SimulationParams};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn waves_swing_between_minus_one_and_one() {
+        for wave in Wave::ALL {
+            for step in 0..400 {
+                let sample = wave.sample(step as f32 / 100.0 - 2.0);
+                assert!((-1.0..=1.0).contains(&sample), "{wave:?} {sample}");
+            }
+        }
+
+        assert!(close(Wave::Sine.sample(0.25), 1.0));
+        assert!(close(Wave::Triangle.sample(0.0), 0.0));
+        assert!(close(Wave::Triangle.sample(0.25), 1.0));
+        assert!(close(Wave::Triangle.sample(0.75), -1.0));
+        assert_eq!(Wave::RandomWalk.sample(1.3), Wave::RandomWalk.sample(1.3),);
+    }
+
+    #[test]
+    fn oscillators_swing_around_their_centre() {
+        let curve = Curve::Oscillator {
+            wave: Wave::Sine,
+            centre: 2.0,
+            depth: 0.5,
+            period: 10.0,
+        };
+
+        assert!(close(curve.value(0.0).unwrap(), 2.0));
+        assert!(close(curve.value(2.5).unwrap(), 2.5));
+        assert!(close(curve.value(7.5).unwrap(), 1.5));
+    }
+
+    fn keyframes(period: Option<f32>) -> Curve {
+        Curve::Keyframes {
+            keyframes: vec![
+                Keyframe {
+                    time: 10.0,
+                    value: 0.0,
+                },
+                Keyframe {
+                    time: 20.0,
+                    value: 1.0,
+                },
+            ],
+            period,
+        }
+    }
+
+    #[test]
+    fn keyframes_hold_at_either_end() {
+        let curve = keyframes(None);
+
+        assert_eq!(curve.value(0.0), Some(0.0));
+        assert_eq!(curve.value(15.0), Some(0.5));
+        assert_eq!(curve.value(100.0), Some(1.0));
+        assert_eq!(
+            Curve::Keyframes {
+                keyframes: vec![],
+                period: None
+            }
+            .value(0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn keyframes_loop_around() {
+        let curve = keyframes(Some(20.0));
+
+        assert_eq!(curve.value(15.0), Some(0.5));
+        // Easing back from the last to the first over the rest of the period
+        assert_eq!(curve.value(25.0), Some(0.5));
+        assert_eq!(curve.value(35.0), Some(0.5));
+        assert_eq!(curve.value(30.0), Some(0.0));
+    }
+
+    #[test]
+    fn keyframe_periods_cover_every_keyframe() {
+        let mut automations = Automations::default();
+        automations.set(Automated::Friction, keyframes(Some(4.0)));
+
+        let curve = automations.get(Automated::Friction).unwrap();
+        assert_eq!(curve, &keyframes(Some(10.0)));
+
+        // Rising all the way to the last keyframe before starting again
+        let values = (0..20)
+            .map(|step| curve.value(10.0 + step as f32 * 0.5).unwrap())
+            .collect::<Vec<_>>();
+        assert!(
+            values.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{values:?}"
+        );
+
+        let loaded = serde_json::from_str::<Automations>(
+            r#"[{"target": "friction", "curve": {"kind": "keyframes", "keyframes": [{"time": 10.0, "value": 0.0}, {"time": 20.0, "value": 1.0}], "period": 4.0}}]"#,
+        )
+        .unwrap();
+        assert_eq!(loaded, automations);
+    }
+
+    #[test]
+    fn automates_within_range() {
+        let mut model = Model::from_3x3([[0.0; 3]; 3]);
+        let mut params = SimulationParams::DEFAULT;
+        let (red, green) = (ParticleColour::Red, ParticleColour::Green);
+
+        let weight = Automated::Weight {
+            source: red,
+            target: green,
+        };
+        weight.set(&mut model, &mut params, 3.0);
+        assert_eq!(model.weight(red, green), 1.0);
+        assert_eq!(weight.get(&model, &params), 1.0);
+
+        Automated::Friction.set(&mut model, &mut params, -1.0);
+        assert_eq!(params.friction, 0.0);
+    }
+
+    #[test]
+    fn one_automation_each() {
+        let mut automations = Automations::default();
+        let curve = keyframes(None);
+
+        automations.set(Automated::Friction, curve.clone());
+        automations.set(Automated::Friction, curve.clone());
+        automations.set(Automated::DecayRate, curve.clone());
+        assert_eq!(automations.len(), 2);
+
+        automations.remove(Automated::Friction);
+        assert_eq!(automations.get(Automated::Friction), None);
+        assert_eq!(automations.get(Automated::DecayRate), Some(&curve));
+    }
+}
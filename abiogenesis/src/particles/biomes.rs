@@ -0,0 +1,318 @@
+use std::sync::LazyLock;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::TorodialMath,
+    particles::{model::Model, simulation::SimulationParams, size::SimulationSize},
+};
+
+pub struct BiomesPlugin;
+impl Plugin for BiomesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BiomeMap>()
+            .insert_resource(SelectedBiome(Some(0)))
+            .add_systems(Startup, init_assets)
+            .add_systems(Update, (spawn_biome_tiles, position_biome_tiles).chain());
+    }
+}
+
+pub const BIOME_GRID: (usize, usize) = (16, 9);
+
+/// A region of the world where some of the laws of creation are rewritten.
+#[derive(Debug, Clone)]
+pub struct Biome {
+    pub name: &'static str,
+    pub colour: Color,
+    pub friction: Option<f32>,
+    pub force_strength: Option<f32>,
+    pub model: Option<Model>,
+}
+
+pub static BIOMES: LazyLock<[Biome; 4]> = LazyLock::new(|| {
+    [
+        Biome {
+            name: "Mire",
+            colour: Color::srgb(0.55, 0.4, 0.2),
+            friction: Some(5.0),
+            force_strength: None,
+            model: None,
+        },
+        Biome {
+            name: "Tempest",
+            colour: Color::srgb(0.6, 0.8, 1.0),
+            friction: Some(0.5),
+            force_strength: Some(200.0),
+            model: None,
+        },
+        Biome {
+            name: "Void",
+            colour: Color::srgb(0.05, 0.0, 0.1),
+            friction: None,
+            force_strength: None,
+            model: Some(Model::from_3x3([[0.0; 3]; 3])),
+        },
+        Biome {
+            name: "Eden",
+            colour: Color::srgb(0.35, 0.9, 0.4),
+            friction: None,
+            force_strength: None,
+            model: Some(Model::from_3x3([
+                [0.3, 0.4, 0.5],
+                [0.7, -0.4, 0.3],
+                [-0.5, 0.5, 0.0],
+            ])),
+        },
+    ]
+});
+
+/// The laws a particle is subject to, the global ones with its biome's overrides applied.
+pub struct Laws<'a> {
+    pub friction: f32,
+    pub force_strength: f32,
+    pub model: &'a Model,
+}
+
+/// A coarse grid painted over the simulation, each cell holding an index into [`BIOMES`].
+///
+/// The camera pans by moving the particles, so the grid keeps an offset to move along with them.
+#[derive(Debug, Resource, Clone, Serialize, Deserialize)]
+pub struct BiomeMap {
+    cells: Vec<Option<usize>>,
+    offset: Vec2,
+    #[serde(skip)]
+    generation: usize,
+}
+
+impl Default for BiomeMap {
+    fn default() -> Self {
+        Self {
+            cells: vec![None; BIOME_GRID.0 * BIOME_GRID.1],
+            offset: Vec2::ZERO,
+            generation: 0,
+        }
+    }
+}
+
+impl BiomeMap {
+    pub fn cell(&self, position: Vec2, bounds: Rect) -> (usize, usize) {
+        let local = bounds.toroidal_wrap(position - self.offset);
+        let uv = (local - bounds.min) / bounds.size();
+
+        (
+            ((uv.x * BIOME_GRID.0 as f32) as usize).min(BIOME_GRID.0 - 1),
+            ((uv.y * BIOME_GRID.1 as f32) as usize).min(BIOME_GRID.1 - 1),
+        )
+    }
+
+    pub fn cell_center(&self, (x, y): (usize, usize), bounds: Rect) -> Vec2 {
+        let uv = Vec2::new(
+            (x as f32 + 0.5) / BIOME_GRID.0 as f32,
+            (y as f32 + 0.5) / BIOME_GRID.1 as f32,
+        );
+
+        bounds.toroidal_wrap(bounds.min + uv * bounds.size() + self.offset)
+    }
+
+    pub fn biome_at(&self, position: Vec2, bounds: Rect) -> Option<&'static Biome> {
+        let (x, y) = self.cell(position, bounds);
+
+        self.cells[y * BIOME_GRID.0 + x].map(|index| &BIOMES[index])
+    }
+
+    pub fn laws<'a>(
+        &self,
+        position: Vec2,
+        bounds: Rect,
+        params: &SimulationParams,
+        model: &'a Model,
+    ) -> Laws<'a> {
+        let biome = self.biome_at(position, bounds);
+
+        Laws {
+            friction: biome.and_then(|it| it.friction).unwrap_or(params.friction),
+            force_strength: biome
+                .and_then(|it| it.force_strength)
+                .unwrap_or(params.force_strength),
+            model: biome.and_then(|it| it.model.as_ref()).unwrap_or(model),
+        }
+    }
+
+    pub fn paint(&mut self, position: Vec2, bounds: Rect, biome: Option<usize>) {
+        let (x, y) = self.cell(position, bounds);
+        let cell = &mut self.cells[y * BIOME_GRID.0 + x];
+
+        if *cell != biome {
+            *cell = biome;
+            self.generation += 1;
+        }
+    }
+
+    /// Replace the whole map, making sure the overlay is rebuilt.
+    pub fn replace(&mut self, other: BiomeMap) {
+        let generation = self.generation + 1;
+
+        *self = other;
+        self.generation = generation;
+    }
+
+    pub fn pan(&mut self, delta: Vec2) {
+        self.offset += delta;
+    }
+}
+
+/// The biome the Terraform tool paints with, `None` erases.
+#[derive(Debug, Resource, Clone, Copy, Deref, DerefMut, PartialEq, Eq)]
+pub struct SelectedBiome(pub Option<usize>);
+
+impl SelectedBiome {
+    pub fn next(&self) -> Self {
+        match self.0 {
+            None => SelectedBiome(Some(0)),
+            Some(index) if index + 1 < BIOMES.len() => SelectedBiome(Some(index + 1)),
+            Some(_) => SelectedBiome(None),
+        }
+    }
+
+    pub fn colour(&self) -> Color {
+        match self.0 {
+            Some(index) => BIOMES[index].colour,
+            None => Color::NONE,
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+struct BiomeAssets {
+    mesh: Handle<Mesh>,
+    materials: Vec<Handle<ColorMaterial>>,
+}
+
+fn init_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(BiomeAssets {
+        mesh: meshes.add(Rectangle::new(1.0, 1.0)),
+        materials: BIOMES
+            .iter()
+            .map(|biome| materials.add(biome.colour.with_alpha(0.08)))
+            .collect(),
+    });
+}
+
+#[derive(Debug, Component, Clone, Copy)]
+struct BiomeTile((usize, usize));
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn spawn_biome_tiles(
+    biome_map: Res<BiomeMap>,
+    biome_assets: Res<BiomeAssets>,
+    tiles: Query<Entity, With<BiomeTile>>,
+    mut generation: Local<Option<usize>>,
+    mut commands: Commands,
+) {
+    if *generation == Some(biome_map.generation) {
+        return;
+    }
+
+    *generation = Some(biome_map.generation);
+
+    tiles
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+
+    for y in 0..BIOME_GRID.1 {
+        for x in 0..BIOME_GRID.0 {
+            let Some(biome) = biome_map.cells[y * BIOME_GRID.0 + x] else {
+                continue;
+            };
+
+            commands.spawn((
+                Name::from(BIOMES[biome].name),
+                BiomeTile((x, y)),
+                Mesh2d(biome_assets.mesh.clone()),
+                MeshMaterial2d(biome_assets.materials[biome].clone()),
+                // Position is filled in by position_biome_tiles, keep the tiles underneath the particles
+                Transform::from_xyz(0.0, 0.0, -1.0),
+            ));
+        }
+    }
+}
+
+fn position_biome_tiles(
+    biome_map: Res<BiomeMap>,
+    simulation_size: SimulationSize,
+    mut tiles: Query<(&BiomeTile, &mut Transform)>,
+) {
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+    let size = bounds.size() / Vec2::new(BIOME_GRID.0 as f32, BIOME_GRID.1 as f32);
+
+    tiles.iter_mut().for_each(|(tile, mut transform)| {
+        transform.translation = biome_map.cell_center(tile.0, bounds).extend(-1.0);
+        transform.scale = size.extend(1.0);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::{Rect, Vec2};
+
+    use super::*;
+
+    fn bounds() -> Rect {
+        Rect::from_center_size(Vec2::ZERO, Vec2::new(160.0, 90.0))
+    }
+
+    #[test]
+    fn cell_corners() {
+        let map = BiomeMap::default();
+
+        assert_eq!(map.cell(Vec2::new(-79.0, -44.0), bounds()), (0, 0));
+        assert_eq!(map.cell(Vec2::new(79.0, 44.0), bounds()), (15, 8));
+        assert_eq!(map.cell(Vec2::new(80.0, 45.0), bounds()), (15, 8));
+    }
+
+    #[test]
+    fn cell_follows_offset() {
+        let mut map = BiomeMap::default();
+        map.pan(Vec2::new(10.0, 0.0));
+
+        assert_eq!(map.cell(Vec2::new(-69.0, -44.0), bounds()), (0, 0));
+    }
+
+    #[test]
+    fn cell_wraps_offset() {
+        let mut map = BiomeMap::default();
+        map.pan(Vec2::new(20.0, 0.0));
+
+        // Panning pushed the first column over, so the far left of the world is now the last column
+        assert_eq!(map.cell(Vec2::new(-79.0, -44.0), bounds()), (14, 0));
+    }
+
+    #[test]
+    fn paint_and_lookup() {
+        let mut map = BiomeMap::default();
+        map.paint(Vec2::ZERO, bounds(), Some(1));
+
+        assert_eq!(
+            map.biome_at(Vec2::new(1.0, 1.0), bounds())
+                .map(|it| it.name),
+            Some("Tempest")
+        );
+        assert!(map.biome_at(Vec2::new(-70.0, -40.0), bounds()).is_none());
+    }
+
+    #[test]
+    fn cell_center_round_trip() {
+        let mut map = BiomeMap::default();
+        map.pan(Vec2::new(-33.0, 12.0));
+
+        assert_eq!(
+            map.cell(map.cell_center((3, 7), bounds()), bounds()),
+            (3, 7)
+        );
+    }
+}
@@ -1,12 +1,17 @@
+use std::str::FromStr;
+
 use bevy::{
     ecs::{component::HookContext, world::DeferredWorld},
     prelude::*,
 };
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::particles::spawner::ParticleAssets;
 
-#[derive(Debug, Reflect, Component, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(
+    Debug, Reflect, Component, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
 #[require(MeshMaterial2d<ColorMaterial>)]
 #[component(immutable, on_insert = on_insert)]
 pub enum ParticleColour {
@@ -20,13 +25,19 @@ pub enum ParticleColour {
 }
 
 fn on_insert(mut world: DeferredWorld, ctx: HookContext) {
-    let colour = world.get::<ParticleColour>(ctx.entity).unwrap();
+    let colour = *world.get::<ParticleColour>(ctx.entity).unwrap();
     let materials = world.get_resource::<ParticleAssets>().unwrap();
+    let material_3d = materials.material_3d(colour);
 
     world
         .get_mut::<MeshMaterial2d<ColorMaterial>>(ctx.entity)
         .unwrap()
-        .0 = materials.material(*colour);
+        .0 = materials.material(colour);
+
+    // Only present while the simulation is running in 3D
+    if let Some(mut material) = world.get_mut::<MeshMaterial3d<StandardMaterial>>(ctx.entity) {
+        material.0 = material_3d;
+    }
 }
 
 pub const NUM_COLOURS: usize = 6;
@@ -64,6 +75,18 @@ impl std::fmt::Display for ParticleColour {
     }
 }
 
+impl FromStr for ParticleColour {
+    type Err = String;
+
+    /// Any colour this build has, by name, ignoring case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        (0..NUM_COLOURS)
+            .map(ParticleColour::from_index)
+            .find(|colour| colour.to_string().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("{name:?} isn't a colour"))
+    }
+}
+
 impl ParticleColour {
     pub fn index(&self) -> usize {
         match self {
@@ -5,22 +5,28 @@ use crate::{
     particles::{
         colour::ParticleColour,
         particle::{ParticleIndex, Velocity},
+        playback::{SIMULATION_STEP, simulation_running},
+        recording::replaying,
         simulation::SimulationParams,
         size::SimulationSize,
         spawner::{OldestParticle, ParticleAssets},
     },
 };
 
-const SCHEDULE_INTERVAL: f32 = 0.1;
-
 pub struct DecayPlugin;
 impl Plugin for DecayPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Time::<Fixed>::from_seconds(SCHEDULE_INTERVAL as f64))
-            .add_systems(FixedUpdate, particle_decay);
+        app.add_event::<ParticleDecayed>().add_systems(
+            FixedUpdate,
+            particle_decay.run_if(simulation_running.and(not(replaying))),
+        );
     }
 }
 
+/// A particle was recycled into a new random particle.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct ParticleDecayed(pub Entity);
+
 #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
 fn particle_decay(
     particle_indexes: Res<ParticleIndex>,
@@ -30,19 +36,19 @@ fn particle_decay(
     params: Res<SimulationParams>,
     mut commands: Commands,
     particle_assets: Res<ParticleAssets>,
+    mut budget: Local<f32>,
+    mut decayed: EventWriter<ParticleDecayed>,
 ) -> Result<()> {
-    let Vec2 {
-        x: width,
-        y: height,
-    } = simulation_size.dimensions();
+    // Decay rates are per second but steps are much shorter, so carry the fractions over
+    *budget += params.decay_rate * SIMULATION_STEP;
 
-    let mut count = 0;
-    while count < (params.decay_rate * SCHEDULE_INTERVAL) as i32 {
+    while *budget >= 1.0 {
         let particle_index = particle_indexes.get(**oldest_particle);
 
         match particle_indexes.len() {
             0 => {
                 **oldest_particle = 0;
+                *budget = 0.0;
                 return Ok(());
             }
             num_particles => **oldest_particle = (**oldest_particle + 1) % num_particles,
@@ -61,18 +67,13 @@ fn particle_decay(
         let colour = ParticleColour::random(params.num_colours);
 
         commands.entity(particle_index).insert((
-            Transform::from_translation(
-                Vec2::new(
-                    rand::random::<f32>() * width - width / 2.0,
-                    rand::random::<f32>() * height - height / 2.0,
-                )
-                .extend(0.0),
-            ),
+            Transform::from_translation(simulation_size.random_position()),
             Velocity::default(),
             colour,
         ));
+        decayed.write(ParticleDecayed(particle_index));
 
-        count += 1;
+        *budget -= 1.0;
     }
 
     Ok(())
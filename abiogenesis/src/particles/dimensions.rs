@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::FollowParticle,
+    particles::{
+        colour::ParticleColour,
+        particle::{Particle, Velocity},
+        size::SimulationSize,
+        spawner::ParticleAssets,
+    },
+};
+
+pub struct DimensionsPlugin;
+impl Plugin for DimensionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Dimensions>()
+            .init_resource::<Dimensions>()
+            .add_systems(
+                Update,
+                (
+                    use_circle_meshes.run_if(resource_equals(Dimensions::Two)),
+                    use_sphere_meshes.run_if(resource_equals(Dimensions::Three)),
+                ),
+            )
+            .add_observer(toggle_dimensions);
+    }
+}
+
+/// Whether the particles live on a flat torus or in a toroidal volume.
+#[derive(Debug, Reflect, Resource, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum Dimensions {
+    #[default]
+    Two,
+    Three,
+}
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct ToggleDimensions;
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn toggle_dimensions(
+    _trigger: Trigger<ToggleDimensions>,
+    dimensions: Res<Dimensions>,
+    simulation_size: SimulationSize,
+    mut particles: Query<(&mut Transform, &mut Velocity), With<Particle>>,
+    mut commands: Commands,
+) {
+    let depth = simulation_size.dimensions().y;
+
+    let next = match *dimensions {
+        Dimensions::Two => Dimensions::Three,
+        Dimensions::Three => Dimensions::Two,
+    };
+
+    // Scatter the particles through the volume so the existing organisms inflate rather than
+    // collapsing into a single sheet, and squash them back down when returning to 2D.
+    particles
+        .iter_mut()
+        .for_each(|(mut transform, mut velocity)| {
+            transform.translation.z = match next {
+                Dimensions::Two => 0.0,
+                Dimensions::Three => depth * (rand::random::<f32>() - 0.5),
+            };
+            velocity.z = 0.0;
+        });
+
+    commands.remove_resource::<FollowParticle>();
+    commands.insert_resource(next);
+}
+
+fn use_sphere_meshes(
+    particles: Query<(Entity, &ParticleColour), (With<Particle>, With<Mesh2d>)>,
+    particle_assets: Res<ParticleAssets>,
+    mut commands: Commands,
+) {
+    particles.iter().for_each(|(entity, colour)| {
+        commands.entity(entity).remove::<Mesh2d>().insert((
+            Mesh3d(particle_assets.sphere()),
+            MeshMaterial3d(particle_assets.material_3d(*colour)),
+        ));
+    });
+}
+
+fn use_circle_meshes(
+    particles: Query<Entity, (With<Particle>, With<Mesh3d>)>,
+    particle_assets: Res<ParticleAssets>,
+    mut commands: Commands,
+) {
+    particles.iter().for_each(|entity| {
+        commands
+            .entity(entity)
+            .remove::<(Mesh3d, MeshMaterial3d<StandardMaterial>)>()
+            .insert(Mesh2d(particle_assets.mesh()));
+    });
+}
@@ -0,0 +1,312 @@
+use std::{cmp::Ordering, ops::RangeInclusive};
+
+use rand::{Rng, seq::SliceRandom};
+
+use crate::particles::{
+    colour::ParticleColour,
+    model::{Model, Reshape},
+    simulation::SimulationParams,
+};
+
+/// How much [`Generator::Mutate`] moves each weight by, at most.
+pub const MUTATION_SIZE: f32 = 0.1;
+
+/// Ways of coming up with a new model. Everything but [`Generator::Mutate`] only fills in the
+/// first `num_colours` colours, leaving the rest indifferent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Generator {
+    /// Every weight independently uniform in [-1, 1].
+    #[default]
+    Uniform,
+    /// Each colour shuns its own kind and chases the next, like "Jörmungandr".
+    Chase,
+    /// A pecking order where predators are drawn to their prey and prey flee their predators.
+    PredatorPrey,
+    /// Mostly indifferent, with a few strong likes and dislikes.
+    Sparse,
+    /// Every pair of colours feels the same way about each other.
+    Symmetric,
+    /// Colours band together in communities that keep to themselves.
+    Communities,
+    /// The current model, nudged by up to [`MUTATION_SIZE`].
+    Mutate,
+}
+
+impl Generator {
+    pub const ALL: [Generator; 7] = [
+        Generator::Uniform,
+        Generator::Chase,
+        Generator::PredatorPrey,
+        Generator::Sparse,
+        Generator::Symmetric,
+        Generator::Communities,
+        Generator::Mutate,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Uniform => "Chaos",
+            Generator::Chase => "Chase",
+            Generator::PredatorPrey => "Predator and prey",
+            Generator::Sparse => "Sparse",
+            Generator::Symmetric => "Symmetric",
+            Generator::Communities => "Communities",
+            Generator::Mutate => "Mutate",
+        }
+    }
+
+    pub fn generate(&self, current: &Model, num_colours: usize, rng: &mut impl Rng) -> Model {
+        let n = num_colours;
+
+        match self {
+            Generator::Uniform => matrix(n, |_, _| rng.gen_range(-1.0..=1.0)),
+            Generator::Chase => matrix(n, |source, target| {
+                if source == target {
+                    rng.gen_range(-0.9..=-0.5)
+                } else if target == (source + 1) % n {
+                    rng.gen_range(0.5..=0.9)
+                } else if source == (target + 1) % n {
+                    rng.gen_range(0.0..=0.7)
+                } else {
+                    rng.gen_range(-0.1..=0.1)
+                }
+            }),
+            Generator::PredatorPrey => {
+                let mut rank = (0..n).collect::<Vec<_>>();
+                rank.shuffle(rng);
+
+                matrix(n, |source, target| match rank[source].cmp(&rank[target]) {
+                    // Packs of the same colour are allowed to form
+                    Ordering::Equal => rng.gen_range(-0.3..=0.6),
+                    Ordering::Greater => rng.gen_range(0.3..=1.0),
+                    Ordering::Less => rng.gen_range(-1.0..=-0.3),
+                })
+            }
+            Generator::Sparse => matrix(n, |_, _| {
+                if rng.gen_bool(0.3) {
+                    rng.gen_range(-1.0..=1.0)
+                } else {
+                    0.0
+                }
+            }),
+            Generator::Symmetric => {
+                let mut model = matrix(n, |source, target| {
+                    if source <= target {
+                        rng.gen_range(-1.0..=1.0)
+                    } else {
+                        0.0
+                    }
+                });
+
+                for source in 0..n {
+                    for target in 0..source {
+                        let (source, target) = (
+                            ParticleColour::from_index(source),
+                            ParticleColour::from_index(target),
+                        );
+                        model.set_weight(source, target, model.weight(target, source));
+                    }
+                }
+
+                model
+            }
+            Generator::Communities => {
+                let communities = rng.gen_range(1..=n.div_ceil(2).max(1));
+                let community = (0..n)
+                    .map(|_| rng.gen_range(0..communities))
+                    .collect::<Vec<_>>();
+
+                matrix(n, |source, target| {
+                    if community[source] == community[target] {
+                        rng.gen_range(0.2..=1.0)
+                    } else {
+                        rng.gen_range(-1.0..=-0.2)
+                    }
+                })
+            }
+            Generator::Mutate => {
+                let mut model = current.clone();
+
+                for source in (0..n).map(ParticleColour::from_index) {
+                    for target in (0..n).map(ParticleColour::from_index) {
+                        let weight = model.weight(source, target)
+                            + rng.gen_range(-MUTATION_SIZE..=MUTATION_SIZE);
+                        model.set_weight(source, target, weight.clamp(-1.0, 1.0));
+                    }
+                }
+
+                model
+            }
+        }
+    }
+}
+
+/// Laws from scratch, by any generator but mutation over some number of `colours`, along with the
+/// [`Reshape`] that gives them so they can be found again.
+pub fn random_laws(
+    colours: RangeInclusive<usize>,
+    rng: &mut impl Rng,
+) -> (Reshape, Model, SimulationParams) {
+    let generators = Generator::ALL
+        .into_iter()
+        .filter(|generator| *generator != Generator::Mutate)
+        .collect::<Vec<_>>();
+
+    let reshape = Reshape {
+        generator: *generators.choose(rng).unwrap(),
+        seed: rng.r#gen(),
+    };
+
+    let params = SimulationParams {
+        num_colours: rng.gen_range(colours),
+        ..SimulationParams::DEFAULT
+    };
+
+    let (model, params) = reshape.apply(&Model::from_rows(&[]).unwrap(), &params);
+    (reshape, model, params)
+}
+
+/// A model whose first `size` colours feel about each other however `weight` says.
+fn matrix(size: usize, mut weight: impl FnMut(usize, usize) -> f32) -> Model {
+    let mut rows = Vec::with_capacity(size);
+    for source in 0..size {
+        rows.push((0..size).map(|target| weight(source, target)).collect());
+    }
+
+    Model::from_rows(&rows).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::{Generator, MUTATION_SIZE};
+    use crate::particles::{
+        colour::{NUM_COLOURS, ParticleColour},
+        model::Model,
+    };
+
+    fn current() -> Model {
+        Model::from_3x3([[0.3, 0.4, 0.5], [0.7, -0.4, 0.3], [-0.5, 0.5, 1.0]])
+    }
+
+    fn generate(generator: Generator, num_colours: usize, seed: u64) -> Model {
+        generator.generate(&current(), num_colours, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn weight(model: &Model, source: usize, target: usize) -> f32 {
+        model.weight(
+            ParticleColour::from_index(source),
+            ParticleColour::from_index(target),
+        )
+    }
+
+    #[test]
+    fn seeds_are_reproducible() {
+        for generator in Generator::ALL {
+            assert_eq!(*generate(generator, 4, 7), *generate(generator, 4, 7));
+        }
+
+        assert_ne!(
+            *generate(Generator::Uniform, 4, 7),
+            *generate(Generator::Uniform, 4, 8)
+        );
+    }
+
+    #[test]
+    fn weights_stay_in_range() {
+        for generator in Generator::ALL {
+            for seed in 0..20 {
+                let model = generate(generator, NUM_COLOURS, seed);
+                assert!(model.iter().all(|weight| (-1.0..=1.0).contains(weight)));
+            }
+        }
+    }
+
+    #[test]
+    fn inactive_colours_are_indifferent() {
+        for generator in Generator::ALL
+            .into_iter()
+            .filter(|generator| *generator != Generator::Mutate)
+        {
+            let model = generate(generator, 3, 1);
+
+            for source in 0..NUM_COLOURS {
+                for target in 3..NUM_COLOURS {
+                    assert_eq!(weight(&model, source, target), 0.0, "{generator:?}");
+                    assert_eq!(weight(&model, target, source), 0.0, "{generator:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chase_shuns_itself_and_chases_the_next() {
+        let model = generate(Generator::Chase, 5, 3);
+
+        for colour in 0..5 {
+            assert!(weight(&model, colour, colour) < 0.0);
+            assert!(weight(&model, colour, (colour + 1) % 5) > 0.0);
+        }
+    }
+
+    #[test]
+    fn predators_chase_prey_that_flee() {
+        let model = generate(Generator::PredatorPrey, NUM_COLOURS, 3);
+
+        for source in 0..NUM_COLOURS {
+            for target in 0..source {
+                assert!(weight(&model, source, target) * weight(&model, target, source) < 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn sparse_is_mostly_indifferent() {
+        let model = generate(Generator::Sparse, NUM_COLOURS, 3);
+        let zeros = model.iter().filter(|weight| **weight == 0.0).count();
+
+        assert!(zeros > NUM_COLOURS * NUM_COLOURS / 2);
+    }
+
+    #[test]
+    fn symmetric() {
+        let model = generate(Generator::Symmetric, NUM_COLOURS, 3);
+
+        for source in 0..NUM_COLOURS {
+            for target in 0..NUM_COLOURS {
+                assert_eq!(
+                    weight(&model, source, target),
+                    weight(&model, target, source)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn communities_agree_on_who_belongs() {
+        for seed in 0..20 {
+            let model = generate(Generator::Communities, NUM_COLOURS, seed);
+
+            for source in 0..NUM_COLOURS {
+                assert!(weight(&model, source, source) > 0.0);
+
+                for target in 0..NUM_COLOURS {
+                    assert_eq!(
+                        weight(&model, source, target) > 0.0,
+                        weight(&model, target, source) > 0.0
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mutate_stays_close() {
+        let model = generate(Generator::Mutate, 3, 3);
+
+        for (mutated, current) in model.iter().zip(current().iter()) {
+            assert!((mutated - current).abs() <= MUTATION_SIZE + 1e-6);
+        }
+    }
+}
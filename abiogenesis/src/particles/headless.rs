@@ -0,0 +1,493 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    math::TorodialMath,
+    particles::{
+        colour::ParticleColour,
+        lineage::OrganismRegistry,
+        model::Model,
+        organisms::{Cluster, ClusterSettings, DETECTION_INTERVAL, find_clusters},
+        playback::SIMULATION_STEP,
+        simulation::{SimulationParams, magnitude},
+        spatial_index::GRID_CELLS,
+    },
+    spatial_hash::SpatialHashGrid,
+};
+
+/// The smallest the simulation gets on screen, which is what every search runs at.
+pub const WORLD_SIZE: Vec2 = Vec2::new(1920.0, 1080.0);
+
+/// A flat simulation without the ECS, rendering or biomes, so many models can be run quickly and
+/// side by side. Follows the same forces, friction and decay as the real thing.
+#[derive(Debug, Clone)]
+pub struct HeadlessWorld {
+    bounds: Rect,
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    colours: Vec<ParticleColour>,
+    grid: SpatialHashGrid<usize>,
+    rng: StdRng,
+    oldest: usize,
+    decay_budget: f32,
+    pub time: f32,
+}
+
+impl HeadlessWorld {
+    /// Scatters `particles` uniformly over the world, in the first `num_colours` colours.
+    pub fn new(particles: usize, num_colours: usize, seed: u64) -> Self {
+        let bounds = Rect::from_center_size(Vec2::ZERO, WORLD_SIZE);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let positions = (0..particles)
+            .map(|_| random_position(bounds, &mut rng))
+            .collect();
+
+        let colours = (0..particles)
+            .map(|index| ParticleColour::from_index(index % num_colours.max(1)))
+            .collect();
+
+        let mut world = HeadlessWorld {
+            bounds,
+            positions,
+            velocities: vec![Vec2::ZERO; particles],
+            colours,
+            grid: SpatialHashGrid::new(bounds, GRID_CELLS),
+            rng,
+            oldest: 0,
+            decay_budget: 0.0,
+            time: 0.0,
+        };
+
+        world.index();
+        world
+    }
+
+    /// Carries on from particles laid out elsewhere, each a position, velocity and colour. Anything
+    /// outside the world wraps back into it, and `seed` decides where decayed particles reappear.
+    pub fn from_particles(
+        particles: impl IntoIterator<Item = (Vec2, Vec2, ParticleColour)>,
+        seed: u64,
+    ) -> Self {
+        let bounds = Rect::from_center_size(Vec2::ZERO, WORLD_SIZE);
+
+        let mut world = HeadlessWorld {
+            bounds,
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            colours: Vec::new(),
+            grid: SpatialHashGrid::new(bounds, GRID_CELLS),
+            rng: StdRng::seed_from_u64(seed),
+            oldest: 0,
+            decay_budget: 0.0,
+            time: 0.0,
+        };
+
+        for (position, velocity, colour) in particles {
+            world.positions.push(bounds.toroidal_wrap(position));
+            world.velocities.push(velocity);
+            world.colours.push(colour);
+        }
+
+        world.index();
+        world
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vec2] {
+        &self.velocities
+    }
+
+    pub fn colours(&self) -> &[ParticleColour] {
+        &self.colours
+    }
+
+    /// Advances the world by one [`SIMULATION_STEP`].
+    pub fn step(&mut self, model: &Model, params: &SimulationParams) {
+        let dt = SIMULATION_STEP;
+
+        for index in 0..self.len() {
+            let position = self.positions[index];
+            let colour = self.colours[index];
+
+            let force = self
+                .grid
+                .query(position, params.attraction_radius)
+                .filter(|(_, other)| **other != index)
+                .map(|(other_position, other)| {
+                    let displacement = self.bounds.toroidal_displacement(position, other_position);
+
+                    magnitude(
+                        params,
+                        model.weight(colour, self.colours[*other]),
+                        displacement.length(),
+                    ) * params.force_strength
+                        * displacement.normalize_or_zero()
+                })
+                .sum::<Vec2>();
+
+            let velocity = &mut self.velocities[index];
+            *velocity += force * dt;
+            *velocity *= (-params.friction * dt).exp();
+            *velocity = velocity.clamp_length(0.0, 200.0);
+        }
+
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position = self.bounds.toroidal_wrap(*position + *velocity * dt);
+        }
+
+        self.decay(params);
+        self.index();
+        self.time += dt;
+    }
+
+    /// Keeps the grid in step with the particles, so it's ready for both forces and clustering.
+    fn index(&mut self) {
+        self.grid.clear();
+        for (index, position) in self.positions.iter().enumerate() {
+            self.grid.insert(*position, index);
+        }
+    }
+
+    /// Recycles the oldest particles into new random ones, like [`super::decay`].
+    fn decay(&mut self, params: &SimulationParams) {
+        self.decay_budget += params.decay_rate * SIMULATION_STEP;
+
+        while self.decay_budget >= 1.0 && !self.is_empty() {
+            let index = self.oldest;
+            self.oldest = (self.oldest + 1) % self.len();
+
+            self.positions[index] = random_position(self.bounds, &mut self.rng);
+            self.velocities[index] = Vec2::ZERO;
+            self.colours[index] =
+                ParticleColour::from_index(self.rng.gen_range(0..params.num_colours.max(1)));
+
+            self.decay_budget -= 1.0;
+        }
+    }
+
+    /// Steps until `seconds`, handing the clusters to `sample` every [`DETECTION_INTERVAL`] once
+    /// the first `warm_up` seconds are over.
+    pub fn run(
+        &mut self,
+        model: &Model,
+        params: &SimulationParams,
+        seconds: f32,
+        warm_up: f32,
+        mut sample: impl FnMut(&HeadlessWorld, &[Cluster]),
+    ) {
+        let settings = ClusterSettings::default();
+
+        let mut next_detection = warm_up;
+        while self.time < seconds {
+            self.step(model, params);
+
+            if self.time >= next_detection {
+                next_detection += DETECTION_INTERVAL;
+                sample(self, &self.clusters(&settings));
+            }
+        }
+    }
+
+    /// Clusters the particles just like [`super::organisms`], with each particle's index standing
+    /// in for its entity.
+    pub fn clusters(&self, settings: &ClusterSettings) -> Vec<Cluster> {
+        let particles = (0..self.len())
+            .map(|index| {
+                (
+                    Entity::from_raw(index as u32),
+                    self.positions[index],
+                    self.velocities[index],
+                    self.colours[index],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let neighbours = self
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| {
+                self.grid
+                    .query(*position, settings.link_radius)
+                    .map(|(_, other)| *other)
+                    .filter(|other| *other != index)
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        find_clusters(&neighbours, settings.min_neighbours, settings.min_members)
+            .into_iter()
+            .map(|members| Cluster::new(self.bounds, &members, &particles))
+            .collect()
+    }
+}
+
+fn random_position(bounds: Rect, rng: &mut impl Rng) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(bounds.min.x..bounds.max.x),
+        rng.gen_range(bounds.min.y..bounds.max.y),
+    )
+}
+
+/// How interesting a run of a model was, measured over everything after the warm up.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Interestingness {
+    /// The mean number of clusters.
+    pub clusters: f32,
+    /// The mean age organisms reached, in seconds, counting those still alive at the end.
+    pub lifetime: f32,
+    /// How much the members of a cluster move together, from 0 for a jiggling crowd to 1 for a
+    /// body all heading the same way, weighted by cluster size.
+    pub coherence: f32,
+    /// The mean fraction of particles in any cluster, nearly none is heat death.
+    pub clustered: f32,
+    /// The mean fraction of particles in the largest cluster, nearly all is total collapse.
+    pub largest: f32,
+    /// How long was measured, in seconds.
+    pub duration: f32,
+}
+
+/// Below this fraction of particles clustered, the world is a featureless gas.
+pub const HEAT_DEATH: f32 = 0.05;
+
+/// Above this fraction of particles in one cluster, everything has fallen into one lump.
+pub const COLLAPSE: f32 = 0.5;
+
+impl Interestingness {
+    /// Lots of organisms that live long and move with purpose score highest. Heat death and total
+    /// collapse score nothing at all.
+    pub fn score(&self) -> f32 {
+        if self.clustered < HEAT_DEATH || self.largest > COLLAPSE || self.duration <= 0.0 {
+            return 0.0;
+        }
+
+        self.clusters.ln_1p() * (self.lifetime / self.duration).min(1.0) * (0.5 + self.coherence)
+    }
+}
+
+/// Runs `model` for `seconds`, ignoring the first `warm_up` seconds while the particles find their
+/// feet, and measures how interesting it was.
+pub fn measure(
+    world: &mut HeadlessWorld,
+    model: &Model,
+    params: &SimulationParams,
+    seconds: f32,
+    warm_up: f32,
+) -> Interestingness {
+    let mut registry = OrganismRegistry::default();
+
+    let (mut samples, mut clusters, mut coherence, mut clustered, mut largest) =
+        (0, 0.0, 0.0, 0.0, 0.0);
+
+    world.run(model, params, seconds, warm_up, |world, found| {
+        registry.track(found, world.time);
+
+        let members = found.iter().map(Cluster::total_mass).sum::<usize>();
+        samples += 1;
+        clusters += found.len() as f32;
+        clustered += members as f32 / world.len().max(1) as f32;
+        largest += found.first().map_or(0, Cluster::total_mass) as f32 / world.len().max(1) as f32;
+
+        if members > 0 {
+            coherence += found
+                .iter()
+                .map(|cluster| {
+                    let speed = cluster
+                        .members
+                        .iter()
+                        .map(|entity| world.velocities[entity.index() as usize].length())
+                        .sum::<f32>()
+                        / cluster.members.len() as f32;
+
+                    let alignment = if speed > 0.0 {
+                        cluster.velocity.length() / speed
+                    } else {
+                        0.0
+                    };
+
+                    alignment * cluster.total_mass() as f32
+                })
+                .sum::<f32>()
+                / members as f32;
+        }
+    });
+
+    let organisms = registry
+        .living()
+        .iter()
+        .chain(registry.departed())
+        .map(|organism| organism.age(world.time))
+        .collect::<Vec<_>>();
+
+    let samples = samples.max(1) as f32;
+
+    Interestingness {
+        clusters: clusters / samples,
+        lifetime: organisms.iter().sum::<f32>() / organisms.len().max(1) as f32,
+        coherence: coherence / samples,
+        clustered: clustered / samples,
+        largest: largest / samples,
+        duration: (world.time - warm_up).max(0.0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::Vec2;
+
+    use super::{COLLAPSE, HEAT_DEATH, HeadlessWorld, Interestingness, WORLD_SIZE};
+    use crate::particles::{colour::ParticleColour, model::Model, simulation::SimulationParams};
+
+    fn params() -> SimulationParams {
+        SimulationParams {
+            num_colours: 3,
+            ..SimulationParams::DEFAULT
+        }
+    }
+
+    #[test]
+    fn same_seed_same_world() {
+        let model = Model::from_3x3([[0.3, 0.4, 0.5], [0.7, -0.4, 0.3], [-0.5, 0.5, 0.0]]);
+
+        let mut a = HeadlessWorld::new(200, 3, 7);
+        let mut b = HeadlessWorld::new(200, 3, 7);
+        for _ in 0..30 {
+            a.step(&model, &params());
+            b.step(&model, &params());
+        }
+
+        assert_eq!(a.positions(), b.positions());
+        assert_ne!(a.positions(), HeadlessWorld::new(200, 3, 8).positions());
+    }
+
+    #[test]
+    fn carries_on_from_given_particles() {
+        let world = HeadlessWorld::from_particles(
+            [
+                (Vec2::new(10.0, -20.0), Vec2::X, ParticleColour::Green),
+                (
+                    Vec2::new(WORLD_SIZE.x, 0.0),
+                    Vec2::ZERO,
+                    ParticleColour::Red,
+                ),
+            ],
+            0,
+        );
+
+        assert_eq!(world.len(), 2);
+        assert_eq!(world.positions()[0], Vec2::new(10.0, -20.0));
+        assert_eq!(world.velocities()[0], Vec2::X);
+        assert_eq!(
+            world.colours(),
+            [ParticleColour::Green, ParticleColour::Red]
+        );
+        assert!(world.bounds.contains(world.positions()[1]));
+    }
+
+    #[test]
+    fn particles_stay_in_the_world() {
+        let model = Model::from_3x3([[1.0; 3]; 3]);
+
+        let mut world = HeadlessWorld::new(200, 3, 1);
+        for _ in 0..60 {
+            world.step(&model, &params());
+        }
+
+        assert!(world.positions().iter().all(|position| {
+            position.x.abs() <= WORLD_SIZE.x / 2.0 && position.y.abs() <= WORLD_SIZE.y / 2.0
+        }));
+    }
+
+    #[test]
+    fn attraction_pulls_particles_together() {
+        let model = Model::from_3x3([[1.0; 3]; 3]);
+        let params = SimulationParams {
+            decay_rate: 0.0,
+            ..params()
+        };
+
+        let mut world = HeadlessWorld::new(2, 1, 1);
+        world.positions = vec![[0.0, 0.0].into(), [60.0, 0.0].into()];
+        world.index();
+        world.step(&model, &params);
+
+        assert!(world.velocities()[0].x > 0.0);
+        assert!(world.velocities()[1].x < 0.0);
+    }
+
+    #[test]
+    fn decay_recycles_particles() {
+        let params = SimulationParams {
+            decay_rate: 60.0,
+            ..params()
+        };
+
+        let mut world = HeadlessWorld::new(10, 3, 1);
+        let before = world.positions().to_vec();
+        world.step(&Model::from_3x3([[0.0; 3]; 3]), &params);
+
+        assert_ne!(world.positions()[0], before[0]);
+        assert_eq!(world.positions()[1], before[1]);
+    }
+
+    fn lively() -> Interestingness {
+        Interestingness {
+            clusters: 8.0,
+            lifetime: 5.0,
+            coherence: 0.5,
+            clustered: 0.4,
+            largest: 0.1,
+            duration: 10.0,
+        }
+    }
+
+    #[test]
+    fn heat_death_and_collapse_score_nothing() {
+        assert!(lively().score() > 0.0);
+
+        let gas = Interestingness {
+            clustered: HEAT_DEATH / 2.0,
+            ..lively()
+        };
+        assert_eq!(gas.score(), 0.0);
+
+        let lump = Interestingness {
+            largest: (COLLAPSE + 1.0) / 2.0,
+            ..lively()
+        };
+        assert_eq!(lump.score(), 0.0);
+    }
+
+    #[test]
+    fn longer_lives_and_coherent_motion_score_higher() {
+        let longer = Interestingness {
+            lifetime: 8.0,
+            ..lively()
+        };
+        assert!(longer.score() > lively().score());
+
+        let coherent = Interestingness {
+            coherence: 0.9,
+            ..lively()
+        };
+        assert!(coherent.score() > lively().score());
+
+        let more = Interestingness {
+            clusters: 16.0,
+            ..lively()
+        };
+        assert!(more.score() > lively().score());
+    }
+}
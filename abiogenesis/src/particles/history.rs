@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::particles::{
+    colour::ParticleColour,
+    particle::{MAX_PARTICLES, Particle, ParticleIndex, Velocity},
+    playback::{Playback, simulation_running},
+    size::SimulationSize,
+    spawner::ParticleAssets,
+};
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_observer(rewind)
+            .add_systems(FixedUpdate, record_history.run_if(simulation_running));
+    }
+}
+
+/// Steps between captured frames, 0.1 seconds of simulated time.
+const CAPTURE_INTERVAL: usize = 6;
+
+/// 20 seconds of history, with every frame capped at [`MAX_PARTICLES`] this bounds the buffer
+/// to `HISTORY_FRAMES * MAX_PARTICLES * size_of::<CompressedParticle>()`, a little over 8MB.
+pub const HISTORY_FRAMES: usize = 200;
+
+const MAX_SPEED: f32 = 200.0;
+
+/// Positions are quantised across the simulation volume and velocities across the speed limit.
+#[derive(Debug, Clone, Copy)]
+struct CompressedParticle {
+    position: [u16; 3],
+    velocity: [i16; 3],
+    colour: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    volume: Vec3,
+    particles: Vec<CompressedParticle>,
+}
+
+impl Snapshot {
+    pub fn capture(
+        volume: Vec3,
+        particles: impl Iterator<Item = (Vec3, Vec3, ParticleColour)>,
+    ) -> Self {
+        // Flat simulations have no depth, avoid dividing by it
+        let safe_volume = volume.max(Vec3::ONE);
+
+        let particles = particles
+            .take(MAX_PARTICLES)
+            .map(|(position, velocity, colour)| {
+                let position =
+                    (position / safe_volume + 0.5).clamp(Vec3::ZERO, Vec3::ONE) * u16::MAX as f32;
+                let velocity =
+                    (velocity / MAX_SPEED).clamp(Vec3::NEG_ONE, Vec3::ONE) * i16::MAX as f32;
+
+                CompressedParticle {
+                    position: position.round().to_array().map(|it| it as u16),
+                    velocity: velocity.round().to_array().map(|it| it as i16),
+                    colour: colour.index() as u8,
+                }
+            })
+            .collect();
+
+        Self { volume, particles }
+    }
+
+    pub fn particles(&self) -> impl Iterator<Item = (Vec3, Vec3, ParticleColour)> + '_ {
+        self.particles.iter().map(|particle| {
+            let position =
+                Vec3::from_array(particle.position.map(|it| it as f32)) / u16::MAX as f32;
+            let velocity =
+                Vec3::from_array(particle.velocity.map(|it| it as f32)) / i16::MAX as f32;
+
+            (
+                (position - 0.5) * self.volume,
+                velocity * MAX_SPEED,
+                ParticleColour::from_index(particle.colour as usize),
+            )
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+pub struct History {
+    frames: VecDeque<Snapshot>,
+    /// The frame being viewed while rewound, `None` when following the live simulation.
+    cursor: Option<usize>,
+}
+
+impl History {
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        // Resuming from a rewound frame discards the future that was rewound over
+        if let Some(cursor) = self.cursor.take() {
+            self.frames.truncate(cursor + 1);
+        }
+
+        if self.frames.len() >= HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(snapshot);
+    }
+}
+
+/// Restore the simulation to a captured frame and pause it there.
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct Rewind(pub usize);
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn record_history(
+    mut history: ResMut<History>,
+    particle_index: Res<ParticleIndex>,
+    particles: Query<(&Transform, &Velocity, &ParticleColour), With<Particle>>,
+    simulation_size: SimulationSize,
+    mut steps: Local<usize>,
+) {
+    *steps += 1;
+
+    // Stepping forward from a rewound frame should record straight away so the timeline keeps up
+    if *steps < CAPTURE_INTERVAL && history.cursor.is_none() {
+        return;
+    }
+
+    *steps = 0;
+
+    let snapshot = Snapshot::capture(
+        simulation_size.volume(),
+        particle_index
+            .iter()
+            .filter_map(|entity| particles.get(*entity).ok())
+            .map(|(transform, velocity, colour)| (transform.translation, **velocity, *colour)),
+    );
+
+    history.push(snapshot);
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn rewind(
+    trigger: Trigger<Rewind>,
+    mut history: ResMut<History>,
+    mut playback: ResMut<Playback>,
+    particle_index: Res<ParticleIndex>,
+    particle_assets: Res<ParticleAssets>,
+    mut commands: Commands,
+) {
+    let Some(snapshot) = history.frames.get(trigger.0) else {
+        return;
+    };
+
+    playback.paused = true;
+    playback.pending_steps = 0;
+
+    for (index, (position, velocity, colour)) in snapshot.particles().enumerate() {
+        match particle_index.get(index) {
+            Some(&entity) => {
+                commands.entity(entity).insert((
+                    Transform::from_translation(position),
+                    Velocity(velocity),
+                    colour,
+                ));
+            }
+            None => {
+                commands.spawn((
+                    Particle,
+                    Transform::from_translation(position),
+                    Velocity(velocity),
+                    colour,
+                    Mesh2d(particle_assets.mesh()),
+                ));
+            }
+        }
+    }
+
+    particle_index
+        .iter()
+        .skip(snapshot.len())
+        .for_each(|entity| commands.entity(*entity).despawn());
+
+    history.cursor = Some(trigger.0);
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::Vec3;
+
+    use super::*;
+
+    fn approx_eq(a: Vec3, b: Vec3, epsilon: f32) -> bool {
+        (a - b).abs().max_element() < epsilon
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let volume = Vec3::new(1920.0, 1080.0, 1080.0);
+        let particles = [
+            (
+                Vec3::new(-960.0, 540.0, 0.0),
+                Vec3::new(200.0, -200.0, 0.0),
+                ParticleColour::Red,
+            ),
+            (
+                Vec3::new(123.4, -56.7, 89.0),
+                Vec3::new(1.5, 0.0, -3.25),
+                ParticleColour::Aqua,
+            ),
+        ];
+
+        let snapshot = Snapshot::capture(volume, particles.into_iter());
+
+        for (
+            (position, velocity, colour),
+            (expected_position, expected_velocity, expected_colour),
+        ) in snapshot.particles().zip(particles)
+        {
+            assert!(approx_eq(position, expected_position, 0.05));
+            assert!(approx_eq(velocity, expected_velocity, 0.01));
+            assert_eq!(colour, expected_colour);
+        }
+    }
+
+    #[test]
+    fn snapshot_flat() {
+        let snapshot = Snapshot::capture(
+            Vec3::new(1920.0, 1080.0, 0.0),
+            [(Vec3::new(10.0, 20.0, 0.0), Vec3::ZERO, ParticleColour::Blue)].into_iter(),
+        );
+
+        let (position, _, _) = snapshot.particles().next().unwrap();
+
+        assert_eq!(position.z, 0.0);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::default();
+
+        (0..HISTORY_FRAMES + 10)
+            .for_each(|_| history.push(Snapshot::capture(Vec3::ONE, std::iter::empty())));
+
+        assert_eq!(history.len(), HISTORY_FRAMES);
+    }
+
+    #[test]
+    fn resuming_discards_the_future() {
+        let mut history = History::default();
+
+        (0..10).for_each(|_| history.push(Snapshot::capture(Vec3::ONE, std::iter::empty())));
+        history.cursor = Some(3);
+        history.push(Snapshot::capture(Vec3::ONE, std::iter::empty()));
+
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.cursor(), None);
+    }
+}
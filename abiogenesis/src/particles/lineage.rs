@@ -0,0 +1,394 @@
+use std::collections::VecDeque;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    camera::FollowParticle,
+    math::TorodialMath,
+    particles::{
+        organisms::{Cluster, Clusters},
+        particle::Particle,
+        playback::SimulationClock,
+        size::SimulationSize,
+    },
+};
+
+pub struct LineagePlugin;
+impl Plugin for LineagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OrganismRegistry>()
+            .add_observer(follow_organism)
+            .add_systems(
+                Update,
+                (
+                    track_organisms.run_if(resource_changed::<Clusters>),
+                    retarget_follow.run_if(resource_exists::<FollowOrganism>.and(
+                        resource_changed::<OrganismRegistry>.or(resource_changed::<FollowOrganism>),
+                    )),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Organisms that have died are remembered for their lineage, up to this many.
+const MAX_DEPARTED: usize = 256;
+
+/// The fraction of the smaller of two clusters they need to share to be related.
+const MIN_OVERLAP: f32 = 0.25;
+
+pub type OrganismId = u32;
+
+/// A cluster that has been followed across detection passes.
+#[derive(Debug, Clone)]
+pub struct Organism {
+    pub id: OrganismId,
+    pub birth: f32,
+    pub death: Option<f32>,
+    /// The organisms this one split from or merged out of.
+    pub parents: Vec<OrganismId>,
+    /// The organisms that split from or merged into this one.
+    pub children: Vec<OrganismId>,
+    /// The latest sighting of the organism.
+    pub cluster: Cluster,
+}
+
+impl Organism {
+    pub fn age(&self, now: f32) -> f32 {
+        self.death.unwrap_or(now) - self.birth
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.cluster.velocity.length()
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+pub struct OrganismRegistry {
+    /// In the same order as [`Clusters`], largest first.
+    living: Vec<Organism>,
+    departed: VecDeque<Organism>,
+    next_id: OrganismId,
+}
+
+impl OrganismRegistry {
+    pub fn living(&self) -> &[Organism] {
+        &self.living
+    }
+
+    /// The most recently departed organisms, oldest first.
+    pub fn departed(&self) -> impl Iterator<Item = &Organism> {
+        self.departed.iter()
+    }
+
+    pub fn get(&self, id: OrganismId) -> Option<&Organism> {
+        self.living
+            .iter()
+            .chain(self.departed.iter())
+            .find(|organism| organism.id == id)
+    }
+
+    /// Match the newly detected clusters against the living organisms by the members they share.
+    ///
+    /// An organism lives on in the cluster it shares the most with, as long as that cluster shares
+    /// the most with it in turn. Any other cluster it shares enough with is a child, split off or
+    /// merged into.
+    pub fn track(&mut self, clusters: &[Cluster], now: f32) {
+        let owners = self
+            .living
+            .iter()
+            .enumerate()
+            .flat_map(|(index, organism)| {
+                organism
+                    .cluster
+                    .members
+                    .iter()
+                    .map(move |entity| (*entity, index))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Every related (organism, cluster) pair, with the number of members they share
+        let mut overlaps = Vec::new();
+        for (cluster_index, cluster) in clusters.iter().enumerate() {
+            let mut shared = HashMap::<usize, usize>::default();
+            cluster
+                .members
+                .iter()
+                .filter_map(|entity| owners.get(entity))
+                .for_each(|organism| *shared.entry(*organism).or_default() += 1);
+
+            for (organism_index, shared) in shared {
+                let smaller = cluster
+                    .members
+                    .len()
+                    .min(self.living[organism_index].cluster.members.len());
+
+                if shared as f32 >= MIN_OVERLAP * smaller as f32 {
+                    overlaps.push((organism_index, cluster_index, shared));
+                }
+            }
+        }
+
+        overlaps.sort_unstable();
+
+        // Ties go to the earlier index, so tracking is deterministic
+        let best = |key: fn(&(usize, usize, usize)) -> usize, value: usize| {
+            overlaps
+                .iter()
+                .filter(|overlap| key(overlap) == value)
+                .max_by(|a, b| a.2.cmp(&b.2).then(b.0.cmp(&a.0)).then(b.1.cmp(&a.1)))
+                .copied()
+        };
+
+        let continues = (0..clusters.len())
+            .map(|cluster_index| {
+                best(|overlap| overlap.1, cluster_index)
+                    .map(|overlap| overlap.0)
+                    .filter(|organism_index| {
+                        best(|overlap| overlap.0, *organism_index)
+                            .is_some_and(|overlap| overlap.1 == cluster_index)
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let ids = continues
+            .iter()
+            .map(|organism_index| match organism_index {
+                Some(organism_index) => self.living[*organism_index].id,
+                None => {
+                    self.next_id += 1;
+                    self.next_id
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut previous = std::mem::take(&mut self.living)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        let mut parents = vec![Vec::new(); clusters.len()];
+        for &(organism_index, cluster_index, _) in &overlaps {
+            let Some(organism) = previous[organism_index].as_mut() else {
+                continue;
+            };
+
+            if organism.id != ids[cluster_index] {
+                organism.children.push(ids[cluster_index]);
+                parents[cluster_index].push(organism.id);
+            }
+        }
+
+        self.living = clusters
+            .iter()
+            .zip(continues)
+            .zip(ids)
+            .zip(parents)
+            .map(|(((cluster, continues), id), parents)| {
+                match continues.and_then(|organism_index| previous[organism_index].take()) {
+                    Some(mut organism) => {
+                        organism.parents.extend(parents);
+                        organism.cluster = cluster.clone();
+                        organism
+                    }
+                    None => Organism {
+                        id,
+                        birth: now,
+                        death: None,
+                        parents,
+                        children: Vec::new(),
+                        cluster: cluster.clone(),
+                    },
+                }
+            })
+            .collect();
+
+        for mut organism in previous.into_iter().flatten() {
+            organism.death = Some(now);
+
+            if self.departed.len() >= MAX_DEPARTED {
+                self.departed.pop_front();
+            }
+
+            self.departed.push_back(organism);
+        }
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn track_organisms(
+    clusters: Res<Clusters>,
+    clock: Res<SimulationClock>,
+    mut registry: ResMut<OrganismRegistry>,
+) {
+    registry.track(&clusters, **clock);
+}
+
+/// Keep the camera on an organism, by following whichever member is closest to its centroid.
+#[derive(Debug, Event, Resource, Clone, Copy, PartialEq, Eq)]
+pub struct FollowOrganism(pub OrganismId);
+
+fn follow_organism(trigger: Trigger<FollowOrganism>, mut commands: Commands) {
+    commands.insert_resource(*trigger.event());
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn retarget_follow(
+    follow_organism: Res<FollowOrganism>,
+    follow_particle: Option<Res<FollowParticle>>,
+    registry: Res<OrganismRegistry>,
+    particles: Query<&Transform, With<Particle>>,
+    simulation_size: SimulationSize,
+    mut last_target: Local<Option<Entity>>,
+    mut commands: Commands,
+) {
+    // Choosing another particle to follow, or letting go of the camera, stops following the organism
+    let current_target = follow_particle.map(|follow| **follow);
+    if !follow_organism.is_changed() && current_target != *last_target {
+        commands.remove_resource::<FollowOrganism>();
+        *last_target = None;
+        return;
+    }
+
+    let Some(organism) = registry
+        .get(follow_organism.0)
+        .filter(|organism| organism.death.is_none())
+    else {
+        commands.remove_resource::<FollowOrganism>();
+        *last_target = None;
+        return;
+    };
+
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+    let centroid = bounds
+        .toroidal_mean(
+            particles
+                .iter_many(&organism.cluster.members)
+                .map(|transform| transform.translation.truncate()),
+        )
+        .unwrap_or(organism.cluster.centroid);
+
+    let Some(target) = organism.cluster.members.iter().copied().min_by(|a, b| {
+        let distance = |entity| {
+            particles.get(entity).map_or(f32::INFINITY, |transform| {
+                bounds
+                    .toroidal_displacement(centroid, transform.translation.truncate())
+                    .length_squared()
+            })
+        };
+
+        distance(*a).total_cmp(&distance(*b))
+    }) else {
+        return;
+    };
+
+    *last_target = Some(target);
+    commands.insert_resource(FollowParticle(target));
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{ecs::entity::Entity, math::Vec2};
+
+    use super::*;
+    use crate::particles::colour::NUM_COLOURS;
+
+    fn cluster(members: impl IntoIterator<Item = u32>) -> Cluster {
+        let members = members
+            .into_iter()
+            .map(Entity::from_raw)
+            .collect::<Vec<_>>();
+
+        let mut mass = [0; NUM_COLOURS];
+        mass[0] = members.len();
+
+        Cluster {
+            members,
+            mass,
+            centroid: Vec2::ZERO,
+            radius: 0.0,
+            velocity: Vec2::ZERO,
+        }
+    }
+
+    fn ids(registry: &OrganismRegistry) -> Vec<OrganismId> {
+        registry
+            .living()
+            .iter()
+            .map(|organism| organism.id)
+            .collect()
+    }
+
+    #[test]
+    fn organisms_persist() {
+        let mut registry = OrganismRegistry::default();
+
+        registry.track(&[cluster(0..10), cluster(10..20)], 0.0);
+        let before = ids(&registry);
+
+        registry.track(&[cluster(1..11), cluster(12..20)], 1.0);
+
+        assert_eq!(ids(&registry), before);
+        assert_eq!(registry.living()[0].age(3.0), 3.0);
+    }
+
+    #[test]
+    fn organisms_die() {
+        let mut registry = OrganismRegistry::default();
+
+        registry.track(&[cluster(0..10)], 0.0);
+        let id = ids(&registry)[0];
+
+        registry.track(&[cluster(100..110)], 2.0);
+
+        assert_ne!(ids(&registry), vec![id]);
+        assert_eq!(registry.get(id).unwrap().death, Some(2.0));
+        assert_eq!(registry.get(id).unwrap().age(10.0), 2.0);
+    }
+
+    #[test]
+    fn splitting_links_parent_and_child() {
+        let mut registry = OrganismRegistry::default();
+
+        registry.track(&[cluster(0..20)], 0.0);
+        let parent = ids(&registry)[0];
+
+        registry.track(&[cluster(0..12), cluster(12..20)], 1.0);
+
+        let [survivor, child] = registry.living() else {
+            panic!("expected two organisms");
+        };
+
+        assert_eq!(survivor.id, parent);
+        assert_eq!(survivor.children, vec![child.id]);
+        assert_eq!(child.parents, vec![parent]);
+        assert_eq!(child.birth, 1.0);
+    }
+
+    #[test]
+    fn merging_links_parent_and_child() {
+        let mut registry = OrganismRegistry::default();
+
+        registry.track(&[cluster(0..12), cluster(12..20)], 0.0);
+        let [larger, smaller] = ids(&registry)[..] else {
+            panic!("expected two organisms");
+        };
+
+        registry.track(&[cluster(0..20)], 1.0);
+
+        assert_eq!(ids(&registry), vec![larger]);
+        assert_eq!(registry.living()[0].parents, vec![smaller]);
+        assert_eq!(registry.get(smaller).unwrap().children, vec![larger]);
+        assert_eq!(registry.get(smaller).unwrap().death, Some(1.0));
+    }
+
+    #[test]
+    fn departed_are_bounded() {
+        let mut registry = OrganismRegistry::default();
+
+        (0..MAX_DEPARTED as u32 + 10)
+            .for_each(|step| registry.track(&[cluster(step * 10..step * 10 + 10)], step as f32));
+
+        assert_eq!(registry.departed.len(), MAX_DEPARTED);
+    }
+}
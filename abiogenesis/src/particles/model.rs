@@ -1,158 +1,28 @@
-use std::sync::LazyLock;
-
 use bevy::math::Rect;
 use bevy::prelude::*;
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use rand_distr::{Distribution, Normal};
 use serde::{Deserialize, Deserializer, Serialize, Serializer, ser::SerializeSeq};
 
 use crate::particles::{
     colour::{NUM_COLOURS, ParticleColour},
+    generators::Generator,
     particle::{Particle, ParticleIndex},
     simulation::{
         ATTRACTION_RADIUS_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE,
         PEAK_ATTRACTION_RADIUS_RANGE, REPULSION_RADIUS_RANGE, SimulationParams,
     },
-    spawner::{SpawnShape, SpawnerConfig},
 };
 
-pub const NUM_PRESETS: usize = 9;
-pub const PRESETS: LazyLock<[(&str, Model, SpawnerConfig, SimulationParams); NUM_PRESETS]> =
-    LazyLock::new(|| {
-        [
-            (
-                "The First Garden",
-                Model::from_3x3([[0.3, 0.4, 0.5], [0.7, -0.4, 0.3], [-0.5, 0.5, 0.0]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Circle of Life",
-                Model::from_3x3([[-0.2, 0.2, 0.8], [0.0, 0.7, 0.3], [0.6, 0.3, -0.5]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Jörmungandr",
-                Model::from_3x3([[-0.8, 0.7, 0.7], [0.7, -0.8, 0.7], [0.3, 0.7, -0.8]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    friction: 2.5,
-                    force_strength: 100.0,
-                    attraction_radius: 120.0,
-                    peak_attraction_radius: 80.0,
-                    repulsion_radius: 20.0,
-                    decay_rate: 80.0,
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Rainbow Serpent",
-                Model::from_6x6([
-                    [-0.8, 0.7, 0.7, 0.0, 0.0, 0.0],
-                    [0.7, -0.8, 0.7, 0.0, 0.0, 0.0],
-                    [0.0, 0.7, -0.8, 0.7, 0.0, 0.0],
-                    [0.0, 0.0, 0.7, -0.8, 0.7, 0.0],
-                    [0.0, 0.0, 0.0, 0.7, -0.8, 0.7],
-                    [0.7, 0.0, 0.0, 0.0, 0.7, -0.8],
-                ]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    friction: 2.5,
-                    force_strength: 100.0,
-                    attraction_radius: 120.0,
-                    peak_attraction_radius: 80.0,
-                    repulsion_radius: 20.0,
-                    decay_rate: 80.0,
-                    num_colours: 6,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Predation",
-                Model::from_3x3([[0.9, -0.8, -0.9], [-0.1, 0.9, -0.4], [0.6, 0.8, -0.5]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    friction: 2.5,
-                    force_strength: 80.0,
-                    attraction_radius: 100.0,
-                    peak_attraction_radius: 20.0,
-                    repulsion_radius: 40.0,
-                    decay_rate: 80.0,
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Ouroboros",
-                Model::from_6x6([
-                    [1.0, 0.4, 0.0, 0.0, 0.0, 0.0],
-                    [0.0, 1.0, 0.4, 0.0, 0.0, 0.0],
-                    [0.0, 0.0, 1.0, 0.4, 0.0, 0.0],
-                    [0.0, 0.0, 0.0, 1.0, 0.4, 0.0],
-                    [0.0, 0.0, 0.0, 0.0, 1.0, 0.4],
-                    [0.4, 0.0, 0.0, 0.0, 0.0, 1.0],
-                ]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    num_colours: 6,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "The Trinity",
-                Model::from_3x3([[0.3, 0.4, 0.5], [0.7, -0.4, 0.3], [-0.5, 0.5, 0.0]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    friction: 5.0,
-                    force_strength: 180.0,
-                    attraction_radius: 200.0,
-                    peak_attraction_radius: 120.0,
-                    repulsion_radius: 110.0,
-                    decay_rate: 60.0,
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Divine Engine",
-                Model::from_3x3([[-0.1, 0.7, 0.0], [0.0, -0.1, 0.7], [0.7, 0.0, -0.1]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    friction: 5.0,
-                    force_strength: 120.,
-                    attraction_radius: 200.0,
-                    peak_attraction_radius: 120.0,
-                    repulsion_radius: 40.0,
-                    decay_rate: 100.0,
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-            (
-                "Heat Death",
-                Model::from_3x3([[0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]]),
-                SpawnerConfig::None,
-                SimulationParams {
-                    num_colours: 3,
-                    ..SimulationParams::DEFAULT
-                },
-            ),
-        ]
-    });
-
 pub struct ModelPlugin;
 impl Plugin for ModelPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(PRESETS[0].1.clone())
+        // Indifferent until the first preset in the book has loaded
+        app.insert_resource(Model::from_3x3([[0.0; 3]; 3]))
+            .init_resource::<LastGenerator>()
             .add_observer(randomise_model)
+            .add_observer(reshape_model)
+            .add_observer(apply_matrix_operation)
             .add_observer(clear_particles);
     }
 }
@@ -188,6 +58,40 @@ impl Model {
         }
     }
 
+    /// Builds a model from the rows of a square matrix of any size, see [`Model::resized`].
+    pub fn from_rows(rows: &[Vec<f32>]) -> Option<Self> {
+        let size = rows.len();
+        if rows.iter().any(|row| row.len() != size) {
+            return None;
+        }
+
+        let model = Self {
+            weights: rows.concat(),
+        };
+
+        Some(model.resized(size))
+    }
+
+    /// Fits weights saved as a `size`×`size` matrix to this build's number of colours, dropping
+    /// the colours it doesn't have and leaving any new ones indifferent to everything.
+    pub fn resized(&self, size: usize) -> Self {
+        debug_assert_eq!(self.weights.len(), size * size);
+
+        Self {
+            weights: (0..NUM_COLOURS)
+                .flat_map(|source| {
+                    (0..NUM_COLOURS).map(move |target| {
+                        if source < size && target < size {
+                            self.weights[source * size + target]
+                        } else {
+                            0.0
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
     pub fn weight(&self, source: ParticleColour, target: ParticleColour) -> f32 {
         debug_assert!(
             source.index() < NUM_COLOURS && target.index() < NUM_COLOURS,
@@ -209,6 +113,90 @@ impl Model {
 
         self.weights[source.index() * NUM_COLOURS + target.index()] = value;
     }
+
+    /// Has every pair of colours feel the same way about each other, meeting in the middle.
+    pub fn symmetrise(&mut self) {
+        for source in 0..NUM_COLOURS {
+            for target in source + 1..NUM_COLOURS {
+                let mean = (self.weights[index(source, target)]
+                    + self.weights[index(target, source)])
+                    / 2.0;
+
+                self.weights[index(source, target)] = mean;
+                self.weights[index(target, source)] = mean;
+            }
+        }
+    }
+
+    /// Has every pair of colours feel opposite ways about each other, which leaves each colour
+    /// indifferent to itself.
+    pub fn antisymmetrise(&mut self) {
+        for source in 0..NUM_COLOURS {
+            for target in source..NUM_COLOURS {
+                let half = (self.weights[index(source, target)]
+                    - self.weights[index(target, source)])
+                    / 2.0;
+
+                self.weights[index(source, target)] = half;
+                self.weights[index(target, source)] = -half;
+            }
+        }
+    }
+
+    /// Swaps how each colour feels about another with how the other feels about it.
+    pub fn transpose(&mut self) {
+        for source in 0..NUM_COLOURS {
+            for target in source + 1..NUM_COLOURS {
+                self.weights
+                    .swap(index(source, target), index(target, source));
+            }
+        }
+    }
+
+    pub fn negate(&mut self) {
+        self.weights
+            .iter_mut()
+            .for_each(|weight| *weight = -*weight);
+    }
+
+    /// Multiplies every weight by `factor`, keeping them within what the matrix can show.
+    pub fn scale(&mut self, factor: f32) {
+        self.weights
+            .iter_mut()
+            .for_each(|weight| *weight = (*weight * factor).clamp(-1.0, 1.0));
+    }
+
+    pub fn zero_diagonal(&mut self) {
+        (0..NUM_COLOURS).for_each(|colour| self.weights[index(colour, colour)] = 0.0);
+    }
+
+    /// How `source` feels about every colour.
+    pub fn row(&self, source: ParticleColour) -> Vec<f32> {
+        self.weights[index(source.index(), 0)..index(source.index() + 1, 0)].to_vec()
+    }
+
+    pub fn set_row(&mut self, source: ParticleColour, row: &[f32]) {
+        debug_assert_eq!(row.len(), NUM_COLOURS);
+
+        self.weights[index(source.index(), 0)..index(source.index() + 1, 0)].copy_from_slice(row);
+    }
+
+    /// Hands the relationships of each of the first `num_colours` colours to the colour after it,
+    /// wrapping around, so the same creatures form in different colours.
+    pub fn rotate_colours(&mut self, num_colours: usize) {
+        let weights = self.weights.clone();
+
+        for source in 0..num_colours {
+            for target in 0..num_colours {
+                self.weights[index((source + 1) % num_colours, (target + 1) % num_colours)] =
+                    weights[index(source, target)];
+            }
+        }
+    }
+}
+
+fn index(source: usize, target: usize) -> usize {
+    source * NUM_COLOURS + target
 }
 
 fn model_serializer<S>(weights: &Vec<f32>, serializer: S) -> Result<S::Ok, S::Error>
@@ -231,17 +219,73 @@ where
     Ok(integer_weights.iter().map(|&i| i as f32 / 100.0).collect())
 }
 
+/// Reshapes the model with the generator used last, from a fresh seed.
 #[derive(Debug, Event, Clone, Copy, Reflect)]
 pub struct Randomise;
 
-#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+/// Replaces the model with one from `generator`, and unless it's mutating, rolls new params too.
+/// The same seed always gives the same result.
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub struct Reshape {
+    pub generator: Generator,
+    pub seed: u64,
+}
+
+impl Reshape {
+    /// The model and params this gives, starting from `model` and `params`.
+    pub fn apply(&self, model: &Model, params: &SimulationParams) -> (Model, SimulationParams) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut params = *params;
+
+        // Mutations are meant to stay close to what's already there
+        if self.generator != Generator::Mutate {
+            randomise_params(&mut params, &mut rng);
+        }
+
+        let model = self.generator.generate(model, params.num_colours, &mut rng);
+
+        (model, params)
+    }
+}
+
+/// The generator the Reshape button uses, whichever was picked last, along with the seed it was
+/// last used with so that reshape can be repeated.
+#[derive(Debug, Resource, Clone, Copy, Default, Deref)]
+pub struct LastGenerator {
+    #[deref]
+    pub generator: Generator,
+    pub seed: Option<u64>,
+}
+
 fn randomise_model(
     _trigger: Trigger<Randomise>,
+    generator: Res<LastGenerator>,
+    mut commands: Commands,
+) {
+    commands.trigger(Reshape {
+        generator: **generator,
+        seed: rand::random(),
+    });
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn reshape_model(
+    trigger: Trigger<Reshape>,
     mut model: ResMut<Model>,
     mut params: ResMut<SimulationParams>,
+    mut commands: Commands,
 ) {
-    let mut rng = rand::thread_rng();
+    let Reshape { generator, seed } = *trigger;
+    tracing::info!("Reshaping with {generator:?} from seed {seed}");
+
+    (*model, *params) = trigger.apply(&model, &params);
+    commands.insert_resource(LastGenerator {
+        generator,
+        seed: Some(seed),
+    });
+}
 
+fn randomise_params(params: &mut SimulationParams, rng: &mut impl Rng) {
     // We're not truly random across the parameter range to try and encourage interesting results
     params.force_strength = rng.gen_range(40.0..=*FORCE_STRENGTH_RANGE.end());
     params.friction = rng.gen_range(
@@ -250,7 +294,7 @@ fn randomise_model(
 
     params.attraction_radius = Normal::<f32>::new(100.0, 10.0)
         .unwrap()
-        .sample(&mut rng)
+        .sample(rng)
         .clamp(20.0, *ATTRACTION_RADIUS_RANGE.end());
 
     params.peak_attraction_radius = Normal::<f32>::new(
@@ -258,7 +302,7 @@ fn randomise_model(
         params.attraction_radius * 0.1,
     )
     .unwrap()
-    .sample(&mut rng)
+    .sample(rng)
     .clamp(0.0, *PEAK_ATTRACTION_RADIUS_RANGE.end());
 
     params.repulsion_radius = Normal::<f32>::new(
@@ -266,15 +310,54 @@ fn randomise_model(
         params.attraction_radius * 0.1,
     )
     .unwrap()
-    .sample(&mut rng)
+    .sample(rng)
     .clamp(
         20.0_f32.min(params.attraction_radius),
         REPULSION_RADIUS_RANGE.end().min(params.attraction_radius),
     );
+}
 
-    model.weights.iter_mut().for_each(|value| {
-        *value = rand::random::<f32>() * 2.0 - 1.0;
-    });
+/// A change to the whole model at once, from the model matrix's context menu.
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub enum MatrixOperation {
+    Symmetrise,
+    Antisymmetrise,
+    Transpose,
+    Negate,
+    Scale(f32),
+    ZeroDiagonal,
+    CopyRow(ParticleColour),
+    PasteRow(ParticleColour),
+    RotateColours,
+}
+
+/// The row last copied from the model matrix, ready to paste onto another.
+#[derive(Debug, Resource, Clone, Deref)]
+pub struct CopiedRow(pub Vec<f32>);
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn apply_matrix_operation(
+    trigger: Trigger<MatrixOperation>,
+    mut model: ResMut<Model>,
+    params: Res<SimulationParams>,
+    copied_row: Option<Res<CopiedRow>>,
+    mut commands: Commands,
+) {
+    match *trigger {
+        MatrixOperation::Symmetrise => model.symmetrise(),
+        MatrixOperation::Antisymmetrise => model.antisymmetrise(),
+        MatrixOperation::Transpose => model.transpose(),
+        MatrixOperation::Negate => model.negate(),
+        MatrixOperation::Scale(factor) => model.scale(factor),
+        MatrixOperation::ZeroDiagonal => model.zero_diagonal(),
+        MatrixOperation::CopyRow(source) => commands.insert_resource(CopiedRow(model.row(source))),
+        MatrixOperation::PasteRow(source) => {
+            if let Some(row) = copied_row {
+                model.set_row(source, &row);
+            }
+        }
+        MatrixOperation::RotateColours => model.rotate_colours(params.num_colours),
+    }
 }
 
 #[derive(Debug, Event, Clone, Copy, Reflect)]
@@ -297,3 +380,131 @@ fn clear_particles(
 
     particle_index.clear();
 }
+
+#[cfg(test)]
+mod test {
+    use super::Model;
+    use crate::particles::colour::{NUM_COLOURS, ParticleColour};
+
+    fn model() -> Model {
+        Model::from_3x3([[0.1, 0.2, 0.3], [0.4, 0.5, 0.6], [0.7, 0.8, 0.9]])
+    }
+
+    fn rows(model: &Model) -> Vec<Vec<f32>> {
+        (0..3)
+            .map(|source| model.row(ParticleColour::from_index(source))[..3].to_vec())
+            .collect()
+    }
+
+    fn assert_rows(model: &Model, expected: [[f32; 3]; 3]) {
+        for (row, expected) in rows(model).iter().zip(expected) {
+            for (weight, expected) in row.iter().zip(expected) {
+                assert!((weight - expected).abs() < 1e-6, "{:?}", rows(model));
+            }
+        }
+    }
+
+    #[test]
+    fn symmetrise() {
+        let mut model = model();
+        model.symmetrise();
+
+        assert_rows(&model, [[0.1, 0.3, 0.5], [0.3, 0.5, 0.7], [0.5, 0.7, 0.9]]);
+    }
+
+    #[test]
+    fn antisymmetrise() {
+        let mut model = model();
+        model.antisymmetrise();
+
+        assert_rows(
+            &model,
+            [[0.0, -0.1, -0.2], [0.1, 0.0, -0.1], [0.2, 0.1, 0.0]],
+        );
+    }
+
+    #[test]
+    fn transpose() {
+        let mut model = model();
+        model.transpose();
+
+        assert_rows(&model, [[0.1, 0.4, 0.7], [0.2, 0.5, 0.8], [0.3, 0.6, 0.9]]);
+    }
+
+    #[test]
+    fn transposing_twice_is_the_identity() {
+        let original = model();
+        let mut model = original.clone();
+        model.transpose();
+        model.transpose();
+
+        assert_eq!(model.weights, original.weights);
+    }
+
+    #[test]
+    fn negate() {
+        let mut model = model();
+        model.negate();
+
+        assert_rows(
+            &model,
+            [[-0.1, -0.2, -0.3], [-0.4, -0.5, -0.6], [-0.7, -0.8, -0.9]],
+        );
+    }
+
+    #[test]
+    fn scale() {
+        let mut model = model();
+        model.scale(2.0);
+
+        assert_rows(&model, [[0.2, 0.4, 0.6], [0.8, 1.0, 1.0], [1.0, 1.0, 1.0]]);
+    }
+
+    #[test]
+    fn zero_diagonal() {
+        let mut model = model();
+        model.zero_diagonal();
+
+        assert_rows(&model, [[0.0, 0.2, 0.3], [0.4, 0.0, 0.6], [0.7, 0.8, 0.0]]);
+    }
+
+    #[test]
+    fn copy_and_paste_rows() {
+        let mut model = model();
+        let row = model.row(ParticleColour::from_index(0));
+        model.set_row(ParticleColour::from_index(2), &row);
+
+        assert_eq!(row.len(), NUM_COLOURS);
+        assert_rows(&model, [[0.1, 0.2, 0.3], [0.4, 0.5, 0.6], [0.1, 0.2, 0.3]]);
+    }
+
+    #[test]
+    fn rotate_colours() {
+        let mut model = model();
+        model.rotate_colours(3);
+
+        assert_rows(&model, [[0.9, 0.7, 0.8], [0.3, 0.1, 0.2], [0.6, 0.4, 0.5]]);
+    }
+
+    #[test]
+    fn rotating_leaves_inactive_colours_alone() {
+        let mut model = Model::from_rows(&vec![vec![0.5; NUM_COLOURS]; NUM_COLOURS]).unwrap();
+        model.set_weight(
+            ParticleColour::from_index(0),
+            ParticleColour::from_index(1),
+            1.0,
+        );
+        model.rotate_colours(2);
+
+        let inactive = ParticleColour::from_index(NUM_COLOURS - 1);
+        assert_eq!(model.row(inactive), vec![0.5; NUM_COLOURS]);
+        assert_eq!(
+            model.weight(ParticleColour::from_index(1), ParticleColour::from_index(0)),
+            1.0
+        );
+        assert_eq!(
+            model.weight(ParticleColour::from_index(0), ParticleColour::from_index(1)),
+            0.5
+        );
+    }
+}
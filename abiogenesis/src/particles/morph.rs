@@ -0,0 +1,320 @@
+use bevy::{math::curve::Curve, prelude::*};
+
+use crate::{
+    math::lerp,
+    particles::{
+        automation::{Automations, automate},
+        model::Model,
+        playback::{SimulationClock, simulation_running},
+        simulation::SimulationParams,
+    },
+};
+
+pub struct MorphPlugin;
+
+impl Plugin for MorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MorphSettings>()
+            .add_observer(start_morph)
+            // Before automation, so anything automated follows its automation rather than the morph
+            .add_systems(
+                FixedPreUpdate,
+                follow_morph.before(automate).run_if(simulation_running),
+            );
+    }
+}
+
+/// The easings to pick from, by name.
+pub const EASINGS: [(&str, EaseFunction); 4] = [
+    ("Smooth", EaseFunction::SmootherStep),
+    ("Linear", EaseFunction::Linear),
+    ("Gentle", EaseFunction::SineInOut),
+    ("Eager", EaseFunction::CubicOut),
+];
+
+/// How presets change the laws. With no duration they're swapped in at once, otherwise every
+/// weight and param is eased from where it is to the preset's over that many seconds of simulated
+/// time.
+#[derive(Debug, Resource, Clone, Copy, PartialEq)]
+pub struct MorphSettings {
+    pub seconds: f32,
+    pub easing: EaseFunction,
+}
+
+impl Default for MorphSettings {
+    fn default() -> Self {
+        Self {
+            seconds: 0.0,
+            easing: EASINGS[0].1,
+        }
+    }
+}
+
+impl MorphSettings {
+    pub fn enabled(&self) -> bool {
+        self.seconds > 0.0
+    }
+
+    pub fn easing_name(&self) -> &'static str {
+        EASINGS
+            .iter()
+            .find(|(_, easing)| *easing == self.easing)
+            .map_or("Custom", |(name, _)| name)
+    }
+
+    /// The easing after this one, wrapping around.
+    pub fn next_easing(&self) -> EaseFunction {
+        let index = EASINGS
+            .iter()
+            .position(|(_, easing)| *easing == self.easing)
+            .map_or(0, |index| index + 1);
+
+        EASINGS[index % EASINGS.len()].1
+    }
+}
+
+/// Eases the current model and params into these, following [`MorphSettings`]. Any morph already
+/// underway is abandoned where it is.
+#[derive(Debug, Event, Clone)]
+pub struct Morph {
+    pub model: Model,
+    pub params: SimulationParams,
+}
+
+fn blend_model(start: &Model, end: &Model, ratio: f32) -> Model {
+    let mut model = start.clone();
+    model
+        .iter_mut()
+        .zip(end.iter())
+        .for_each(|(weight, end)| *weight = lerp(*weight, *end, ratio));
+
+    model
+}
+
+fn blend_params(start: &SimulationParams, end: &SimulationParams, ratio: f32) -> SimulationParams {
+    SimulationParams {
+        friction: lerp(start.friction, end.friction, ratio),
+        force_strength: lerp(start.force_strength, end.force_strength, ratio),
+        peak_attraction_radius: lerp(
+            start.peak_attraction_radius,
+            end.peak_attraction_radius,
+            ratio,
+        ),
+        repulsion_radius: lerp(start.repulsion_radius, end.repulsion_radius, ratio),
+        attraction_radius: lerp(start.attraction_radius, end.attraction_radius, ratio),
+        decay_rate: lerp(start.decay_rate, end.decay_rate, ratio),
+        // Colours can't be part way there, so every colour either uses stays until the end
+        num_colours: if ratio < 1.0 {
+            start.num_colours.max(end.num_colours)
+        } else {
+            end.num_colours
+        },
+    }
+}
+
+/// A morph underway, easing the laws from where they were when it started to where it's heading.
+///
+/// This takes the place of `bevy_tweening` lenses for [`Model`] and [`SimulationParams`]. Tweens
+/// follow the frame clock, so a morph would carry on while paused and drift at other speeds, and
+/// they can only animate components and assets, not resources.
+#[derive(Debug, Component)]
+struct MorphTarget {
+    from: (Model, SimulationParams),
+    to: (Model, SimulationParams),
+    /// On the [`SimulationClock`], so it holds still while paused and keeps pace at any speed.
+    started: f32,
+    seconds: f32,
+    easing: EaseFunction,
+    /// The laws as the morph last left them. Finding them any different means they've been edited
+    /// since, which ends the morph there.
+    written: Option<(Model, SimulationParams)>,
+}
+
+impl MorphTarget {
+    /// How far along the morph is at `time`, eased, from 0 to 1.
+    fn progress(&self, time: f32) -> f32 {
+        let elapsed = (time - self.started) / self.seconds.max(f32::EPSILON);
+        self.easing.sample_clamped(elapsed)
+    }
+
+    fn lerp(&self, ratio: f32) -> (Model, SimulationParams) {
+        (
+            blend_model(&self.from.0, &self.to.0, ratio),
+            blend_params(&self.from.1, &self.to.1, ratio),
+        )
+    }
+
+    /// Whether the laws have changed since the morph last set them, other than by automation.
+    fn edited(&self, model: &Model, params: &SimulationParams, automations: &Automations) -> bool {
+        let Some((mut written_model, mut written_params)) = self.written.clone() else {
+            return false;
+        };
+
+        for automation in automations.iter() {
+            let value = automation.target.get(model, params);
+            automation
+                .target
+                .set(&mut written_model, &mut written_params, value);
+        }
+
+        **written_model != **model || written_params != *params
+    }
+}
+
+fn start_morph(
+    trigger: Trigger<Morph>,
+    settings: Res<MorphSettings>,
+    model: Res<Model>,
+    params: Res<SimulationParams>,
+    clock: Res<SimulationClock>,
+    morphs: Query<Entity, With<MorphTarget>>,
+    mut commands: Commands,
+) {
+    morphs
+        .iter()
+        .for_each(|morph| commands.entity(morph).despawn());
+
+    commands.spawn((
+        Name::new("Morph"),
+        MorphTarget {
+            from: (model.clone(), *params),
+            to: (trigger.model.clone(), trigger.params),
+            started: **clock,
+            seconds: settings.seconds,
+            easing: settings.easing,
+            written: None,
+        },
+    ));
+}
+
+/// Moves the laws along every morph underway, one step of the simulation at a time. A morph is
+/// dropped where it is when the laws are edited from anywhere else, while automated laws are left
+/// to their automation throughout.
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn follow_morph(
+    mut morphs: Query<(Entity, &mut MorphTarget)>,
+    mut model: ResMut<Model>,
+    mut params: ResMut<SimulationParams>,
+    automations: Res<Automations>,
+    clock: Res<SimulationClock>,
+    mut commands: Commands,
+) {
+    for (entity, mut morph) in &mut morphs {
+        if morph.edited(&model, &params, &automations) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let progress = morph.progress(**clock);
+        let (morphed_model, morphed_params) = morph.lerp(progress);
+        *model = morphed_model.clone();
+        *params = morphed_params;
+        morph.written = Some((morphed_model, morphed_params));
+
+        if progress >= 1.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::EaseFunction;
+
+    use super::{MorphTarget, blend_model, blend_params};
+    use crate::particles::{
+        automation::{Automated, Automations, Curve, Wave},
+        model::Model,
+        simulation::SimulationParams,
+    };
+
+    #[test]
+    fn blends_every_weight() {
+        let start = Model::from_3x3([[1.0, 0.0, -1.0]; 3]);
+        let end = Model::from_3x3([[-1.0, 0.5, 1.0]; 3]);
+
+        assert_eq!(
+            *blend_model(&start, &end, 0.5),
+            *Model::from_3x3([[0.0, 0.25, 0.0]; 3])
+        );
+        assert_eq!(*blend_model(&start, &end, 1.0), *end);
+    }
+
+    #[test]
+    fn keeps_every_colour_until_the_end() {
+        let start = SimulationParams {
+            num_colours: 6,
+            friction: 1.0,
+            ..SimulationParams::DEFAULT
+        };
+        let end = SimulationParams {
+            num_colours: 3,
+            friction: 3.0,
+            ..SimulationParams::DEFAULT
+        };
+
+        let halfway = blend_params(&start, &end, 0.5);
+        assert_eq!(halfway.friction, 2.0);
+        assert_eq!(halfway.num_colours, 6);
+
+        let there = blend_params(&start, &end, 1.0);
+        assert_eq!(there.friction, 3.0);
+        assert_eq!(there.num_colours, 3);
+    }
+
+    #[test]
+    fn edits_outside_automation_end_the_morph() {
+        let model = Model::from_3x3([[0.5; 3]; 3]);
+        let params = SimulationParams::DEFAULT;
+        let morph = MorphTarget {
+            from: (model.clone(), params),
+            to: (model.clone(), params),
+            started: 0.0,
+            seconds: 10.0,
+            easing: EaseFunction::Linear,
+            written: Some((model.clone(), params)),
+        };
+
+        let mut automations = Automations::default();
+        automations.set(
+            Automated::Friction,
+            Curve::Oscillator {
+                wave: Wave::Sine,
+                centre: 1.0,
+                depth: 0.5,
+                period: 10.0,
+            },
+        );
+
+        let automated = SimulationParams {
+            friction: params.friction + 0.5,
+            ..params
+        };
+        assert!(!morph.edited(&model, &params, &automations));
+        assert!(!morph.edited(&model, &automated, &automations));
+
+        let edited = SimulationParams {
+            force_strength: params.force_strength + 1.0,
+            ..params
+        };
+        assert!(morph.edited(&model, &edited, &automations));
+        assert!(morph.edited(&Model::from_3x3([[0.0; 3]; 3]), &params, &automations));
+    }
+
+    #[test]
+    fn progresses_with_the_simulation_clock() {
+        let model = Model::from_3x3([[0.0; 3]; 3]);
+        let morph = MorphTarget {
+            from: (model.clone(), SimulationParams::DEFAULT),
+            to: (model, SimulationParams::DEFAULT),
+            started: 5.0,
+            seconds: 10.0,
+            easing: EaseFunction::Linear,
+            written: None,
+        };
+
+        assert_eq!(morph.progress(5.0), 0.0);
+        assert_eq!(morph.progress(10.0), 0.5);
+        assert_eq!(morph.progress(20.0), 1.0);
+    }
+}
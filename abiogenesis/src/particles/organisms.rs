@@ -0,0 +1,502 @@
+use std::time::Duration;
+
+use bevy::{platform::collections::HashMap, prelude::*, time::common_conditions::on_timer};
+
+use crate::{
+    camera::Viewport,
+    math::TorodialMath,
+    particles::{
+        colour::{NUM_COLOURS, ParticleColour},
+        dimensions::Dimensions,
+        lineage::{OrganismRegistry, track_organisms},
+        particle::{Particle, ParticleIndex, Velocity},
+        size::SimulationSize,
+        spatial_index::SpatialIndex,
+    },
+    scenes::Shaping,
+};
+
+pub struct OrganismsPlugin;
+impl Plugin for OrganismsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ClusterSettings>()
+            .init_resource::<ClusterSettings>()
+            .init_resource::<Clusters>()
+            .init_resource::<ShowOrganisms>()
+            .add_observer(toggle_organisms)
+            .add_systems(Startup, init_assets)
+            .add_systems(
+                Update,
+                detect_clusters.run_if(on_timer(Duration::from_secs_f32(DETECTION_INTERVAL))),
+            )
+            .add_systems(
+                Update,
+                (spawn_outlines, position_outlines)
+                    .chain()
+                    .after(detect_clusters)
+                    .after(track_organisms)
+                    .run_if(resource_equals(ShowOrganisms(true)))
+                    // Outlines are flat meshes, which the 3D camera doesn't draw
+                    .run_if(resource_equals(Dimensions::Two))
+                    .run_if(in_state(Shaping)),
+            )
+            .add_systems(
+                Update,
+                despawn_outlines
+                    .run_if(resource_changed::<ShowOrganisms>.or(resource_changed::<Dimensions>)),
+            );
+    }
+}
+
+/// Seconds between each clustering pass, organisms change shape far slower than the simulation steps.
+pub const DETECTION_INTERVAL: f32 = 0.5;
+
+/// DBSCAN over the particles, where particles closer than the link radius are neighbours.
+///
+/// With `min_neighbours` at 1 this is plain connected components.
+#[derive(Debug, Reflect, Resource, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct ClusterSettings {
+    pub link_radius: f32,
+    /// Neighbours a particle needs to hold a cluster together, rather than just cling to its edge.
+    pub min_neighbours: usize,
+    /// Smaller clusters are discarded as noise.
+    pub min_members: usize,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        Self {
+            link_radius: 20.0,
+            min_neighbours: 3,
+            min_members: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub members: Vec<Entity>,
+    pub centroid: Vec2,
+    /// Number of members of each colour.
+    pub mass: [usize; NUM_COLOURS],
+    /// Distance from the centroid to the furthest member.
+    pub radius: f32,
+    pub velocity: Vec2,
+}
+
+impl Cluster {
+    /// Summarises the `members` of `particles`, each an entity with its position, velocity and
+    /// colour.
+    pub fn new(
+        bounds: Rect,
+        members: &[usize],
+        particles: &[(Entity, Vec2, Vec2, ParticleColour)],
+    ) -> Self {
+        let centroid = bounds
+            .toroidal_mean(members.iter().map(|index| particles[*index].1))
+            .unwrap_or_default();
+
+        let mut mass = [0; NUM_COLOURS];
+        members
+            .iter()
+            .for_each(|index| mass[particles[*index].3.index()] += 1);
+
+        let radius = members
+            .iter()
+            .map(|index| {
+                bounds
+                    .toroidal_displacement(centroid, particles[*index].1)
+                    .length()
+            })
+            .fold(0.0, f32::max);
+
+        let velocity = members
+            .iter()
+            .map(|index| particles[*index].2)
+            .sum::<Vec2>()
+            / members.len() as f32;
+
+        Cluster {
+            members: members.iter().map(|index| particles[*index].0).collect(),
+            centroid,
+            mass,
+            radius,
+            velocity,
+        }
+    }
+
+    pub fn total_mass(&self) -> usize {
+        self.mass.iter().sum()
+    }
+}
+
+/// The clusters found by the latest pass, largest first. Only the flat simulation is clustered.
+#[derive(Debug, Resource, Default, Deref)]
+pub struct Clusters(Vec<Cluster>);
+
+#[derive(Debug, Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShowOrganisms(pub bool);
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct ToggleOrganisms;
+
+fn toggle_organisms(_trigger: Trigger<ToggleOrganisms>, mut show: ResMut<ShowOrganisms>) {
+    show.0 = !show.0;
+}
+
+/// Group particles into clusters, returning the indices of each cluster's members.
+///
+/// `neighbours[i]` lists every particle within the link radius of particle `i`, excluding itself.
+pub fn find_clusters(
+    neighbours: &[Vec<usize>],
+    min_neighbours: usize,
+    min_members: usize,
+) -> Vec<Vec<usize>> {
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+
+        index
+    }
+
+    let core = neighbours
+        .iter()
+        .map(|neighbours| neighbours.len() >= min_neighbours)
+        .collect::<Vec<_>>();
+
+    let mut parents = (0..neighbours.len()).collect::<Vec<_>>();
+
+    // Core particles chain together into a cluster
+    for (index, neighbours) in neighbours.iter().enumerate() {
+        if !core[index] {
+            continue;
+        }
+
+        for &neighbour in neighbours.iter().filter(|neighbour| core[**neighbour]) {
+            let (a, b) = (root(&mut parents, index), root(&mut parents, neighbour));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    // Border particles join the first cluster they touch, anything else is noise
+    let mut clusters = HashMap::<usize, Vec<usize>>::default();
+    for (index, neighbours) in neighbours.iter().enumerate() {
+        let anchor = if core[index] {
+            Some(index)
+        } else {
+            neighbours
+                .iter()
+                .copied()
+                .find(|neighbour| core[*neighbour])
+        };
+
+        if let Some(anchor) = anchor {
+            clusters
+                .entry(root(&mut parents, anchor))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut clusters = clusters
+        .into_values()
+        .filter(|members| members.len() >= min_members)
+        .collect::<Vec<_>>();
+
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
+    clusters
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn detect_clusters(
+    mut clusters: ResMut<Clusters>,
+    settings: Res<ClusterSettings>,
+    spatial_index: Res<SpatialIndex>,
+    particle_index: Res<ParticleIndex>,
+    particles: Query<(&Transform, &Velocity, &ParticleColour), With<Particle>>,
+    simulation_size: SimulationSize,
+) {
+    // In 3D organisms are found from where the particles fall on the plane, like the index
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+
+    let particles = particle_index
+        .iter()
+        .filter_map(|entity| {
+            particles
+                .get(*entity)
+                .ok()
+                .map(|(transform, velocity, colour)| {
+                    (
+                        *entity,
+                        transform.translation.truncate(),
+                        velocity.truncate(),
+                        *colour,
+                    )
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let lookup = particles
+        .iter()
+        .enumerate()
+        .map(|(index, (entity, ..))| (*entity, index))
+        .collect::<HashMap<_, _>>();
+
+    let neighbours = particles
+        .iter()
+        .enumerate()
+        .map(|(index, (_, position, ..))| {
+            spatial_index
+                .query(*position, settings.link_radius)
+                .filter_map(|(_, (entity, _))| lookup.get(entity).copied())
+                .filter(|neighbour| *neighbour != index)
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    clusters.0 = find_clusters(&neighbours, settings.min_neighbours, settings.min_members)
+        .into_iter()
+        .map(|members| Cluster::new(bounds, &members, &particles))
+        .collect();
+}
+
+#[derive(Debug, Resource)]
+struct OrganismAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+fn init_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(OrganismAssets {
+        mesh: meshes.add(Annulus::new(0.95, 1.0)),
+        material: materials.add(Color::WHITE.with_alpha(0.4)),
+    });
+}
+
+#[derive(Debug, Component, Clone, Copy)]
+struct OrganismOutline(usize);
+
+#[derive(Debug, Component, Clone, Copy)]
+struct OrganismLabel(usize);
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn spawn_outlines(
+    registry: Res<OrganismRegistry>,
+    assets: Res<OrganismAssets>,
+    outlines: Query<Entity, Or<(With<OrganismOutline>, With<OrganismLabel>)>>,
+    mut commands: Commands,
+) {
+    if !registry.is_changed() && !outlines.is_empty() {
+        return;
+    }
+
+    outlines
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+
+    for (index, organism) in registry.living().iter().enumerate() {
+        commands.spawn((
+            Name::from("Organism Outline"),
+            OrganismOutline(index),
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(assets.material.clone()),
+            Transform::from_xyz(0.0, 0.0, -0.5),
+            StateScoped(Shaping),
+        ));
+
+        commands.spawn((
+            Name::from("Organism Label"),
+            OrganismLabel(index),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Text::new(format!(
+                "#{} {}",
+                organism.id,
+                organism.cluster.total_mass()
+            )),
+            TextFont::from_font_size(14.0),
+            TextColor(Color::WHITE.with_alpha(0.8)),
+            Pickable::IGNORE,
+            StateScoped(Shaping),
+        ));
+    }
+}
+
+/// Clusters are only detected every so often, so follow the members in between to keep up with panning.
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn position_outlines(
+    registry: Res<OrganismRegistry>,
+    particles: Query<&Transform, (With<Particle>, Without<OrganismOutline>)>,
+    mut outlines: Query<(Entity, &OrganismOutline, &mut Transform)>,
+    mut labels: Query<(Entity, &OrganismLabel, &mut Node)>,
+    simulation_size: SimulationSize,
+    viewport: Viewport,
+    ui_scale: Res<UiScale>,
+    mut commands: Commands,
+) {
+    let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+
+    let clusters = registry
+        .living()
+        .iter()
+        .map(|organism| &organism.cluster)
+        .collect::<Vec<_>>();
+
+    let centroids = clusters
+        .iter()
+        .map(|cluster| {
+            bounds
+                .toroidal_mean(
+                    particles
+                        .iter_many(&cluster.members)
+                        .map(|transform| transform.translation.truncate()),
+                )
+                .unwrap_or(cluster.centroid)
+        })
+        .collect::<Vec<_>>();
+
+    // An outline can outlast its organism until the next respawn of outlines catches up
+    outlines
+        .iter_mut()
+        .for_each(|(entity, outline, mut transform)| {
+            let (Some(centroid), Some(cluster)) =
+                (centroids.get(outline.0), clusters.get(outline.0))
+            else {
+                commands.entity(entity).despawn();
+                return;
+            };
+
+            transform.translation = centroid.extend(-0.5);
+            transform.scale = Vec3::splat(cluster.radius.max(1.0));
+        });
+
+    labels.iter_mut().for_each(|(entity, label, mut node)| {
+        let (Some(centroid), Some(cluster)) = (centroids.get(label.0), clusters.get(label.0))
+        else {
+            commands.entity(entity).despawn();
+            return;
+        };
+
+        let Some(position) =
+            viewport.to_viewport(*centroid + Vec2::new(cluster.radius, cluster.radius))
+        else {
+            return;
+        };
+
+        node.left = Val::Px(position.x / ui_scale.0);
+        node.top = Val::Px(position.y / ui_scale.0);
+    });
+}
+
+fn despawn_outlines(
+    show: Res<ShowOrganisms>,
+    dimensions: Res<Dimensions>,
+    outlines: Query<Entity, Or<(With<OrganismOutline>, With<OrganismLabel>)>>,
+    mut commands: Commands,
+) {
+    if show.0 && *dimensions == Dimensions::Two {
+        return;
+    }
+
+    outlines
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::{Rect, Vec2};
+
+    use super::*;
+
+    fn neighbours(points: &[Vec2], radius: f32) -> Vec<Vec<usize>> {
+        let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(100.0));
+
+        points
+            .iter()
+            .enumerate()
+            .map(|(index, a)| {
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, b)| {
+                        *other != index && bounds.toroidal_displacement(*a, **b).length() <= radius
+                    })
+                    .map(|(other, _)| other)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn connected_components() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(30.0, 30.0),
+            Vec2::new(30.0, 35.0),
+        ];
+
+        let clusters = find_clusters(&neighbours(&points, 6.0), 1, 1);
+
+        assert_eq!(clusters, vec![vec![0, 1, 2], vec![3, 4]]);
+    }
+
+    #[test]
+    fn clusters_wrap_around_edges() {
+        let points = [
+            Vec2::new(48.0, 0.0),
+            Vec2::new(-48.0, 0.0),
+            Vec2::new(0.0, 48.0),
+            Vec2::new(0.0, -48.0),
+        ];
+
+        let clusters = find_clusters(&neighbours(&points, 5.0), 1, 1);
+
+        assert_eq!(clusters, vec![vec![0, 1], vec![2, 3]]);
+    }
+
+    #[test]
+    fn small_clusters_are_discarded() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(30.0, 30.0),
+        ];
+
+        let clusters = find_clusters(&neighbours(&points, 6.0), 1, 2);
+
+        assert_eq!(clusters, vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn border_particles_do_not_bridge_clusters() {
+        // Two dense clumps joined by a single particle that only reaches one of each
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(6.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(12.0, 0.0),
+            Vec2::new(10.0, 2.0),
+            Vec2::new(12.0, 2.0),
+        ];
+
+        let clusters = find_clusters(&neighbours(&points, 4.0), 3, 1);
+
+        assert_eq!(clusters, vec![vec![0, 1, 2, 3, 4], vec![5, 6, 7, 8]]);
+    }
+}
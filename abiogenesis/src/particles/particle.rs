@@ -21,7 +21,7 @@ impl Plugin for ParticlePlugin {
 pub struct Particle;
 
 #[derive(Debug, Reflect, Component, Default, Clone, Copy, Deref, DerefMut)]
-pub struct Velocity(Vec2);
+pub struct Velocity(pub Vec3);
 
 #[derive(Debug, Reflect, Resource, Deref, DerefMut)]
 pub struct ParticleIndex(pub Vec<Entity>);
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+pub struct PlaybackPlugin;
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Playback>()
+            .init_resource::<Playback>()
+            .init_resource::<SimulationClock>()
+            .insert_resource(Time::<Fixed>::from_seconds(SIMULATION_STEP as f64))
+            .add_observer(toggle_pause)
+            .add_observer(step_simulation)
+            .add_observer(hasten)
+            .add_observer(linger)
+            .add_systems(
+                Update,
+                scale_fixed_time.run_if(resource_changed::<Playback>),
+            )
+            .add_systems(FixedUpdate, tick_clock.run_if(simulation_running))
+            .add_systems(
+                FixedLast,
+                consume_step.run_if(|playback: Res<Playback>| playback.pending_steps > 0),
+            );
+    }
+}
+
+/// The simulated time that passes each step, no matter how fast the simulation is running.
+pub const SIMULATION_STEP: f32 = 1.0 / 60.0;
+
+pub const SPEEDS: [f32; 7] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+#[derive(Debug, Reflect, Resource, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct Playback {
+    pub paused: bool,
+    pub speed: f32,
+    pub pending_steps: usize,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+            pending_steps: 0,
+        }
+    }
+}
+
+/// Seconds of simulated time, which stands still while paused.
+#[derive(Debug, Resource, Default, Clone, Copy, Deref)]
+pub struct SimulationClock(f32);
+
+/// Run condition for anything that advances the simulation.
+pub fn simulation_running(playback: Res<Playback>) -> bool {
+    !playback.paused || playback.pending_steps > 0
+}
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct TogglePause;
+
+/// Advance a paused simulation by the given number of steps.
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct StepSimulation(pub usize);
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct Hasten;
+
+#[derive(Debug, Event, Clone, Copy, Reflect)]
+pub struct Linger;
+
+fn toggle_pause(_trigger: Trigger<TogglePause>, mut playback: ResMut<Playback>) {
+    playback.paused = !playback.paused;
+    playback.pending_steps = 0;
+}
+
+fn step_simulation(trigger: Trigger<StepSimulation>, mut playback: ResMut<Playback>) {
+    playback.paused = true;
+    playback.pending_steps += trigger.0;
+}
+
+fn hasten(_trigger: Trigger<Hasten>, mut playback: ResMut<Playback>) {
+    playback.speed = SPEEDS
+        .into_iter()
+        .find(|speed| *speed > playback.speed)
+        .unwrap_or(SPEEDS[SPEEDS.len() - 1]);
+}
+
+fn linger(_trigger: Trigger<Linger>, mut playback: ResMut<Playback>) {
+    playback.speed = SPEEDS
+        .into_iter()
+        .rev()
+        .find(|speed| *speed < playback.speed)
+        .unwrap_or(SPEEDS[0]);
+}
+
+/// Running faster takes more fixed steps of the same size, so the simulation stays stable at 8x.
+fn scale_fixed_time(playback: Res<Playback>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_seconds((SIMULATION_STEP / playback.speed) as f64);
+}
+
+fn tick_clock(mut clock: ResMut<SimulationClock>) {
+    clock.0 += SIMULATION_STEP;
+}
+
+fn consume_step(mut playback: ResMut<Playback>) {
+    playback.pending_steps -= 1;
+}
@@ -0,0 +1,622 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize, de::Error};
+
+use crate::particles::{
+    colour::ParticleColour,
+    model::Model,
+    morph::{Morph, MorphSettings},
+    particle::MAX_PARTICLES,
+    simulation::SimulationParams,
+    spawner::{ParticleCount, Respawn, RespawnOverrides, SpawnerConfig},
+};
+
+pub struct PresetsPlugin;
+
+impl Plugin for PresetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Preset>()
+            .init_asset::<PresetBook>()
+            .register_asset_loader(PresetLoader)
+            .register_asset_loader(PresetBookLoader)
+            .init_resource::<StartingPreset>()
+            .add_systems(Startup, load_book)
+            .add_systems(
+                Update,
+                (apply_first_preset, reapply_modified_preset)
+                    .run_if(on_event::<AssetEvent<Preset>>),
+            )
+            .add_observer(apply_preset);
+    }
+}
+
+/// The presets shown in the Book of Fates, in order. Each entry is a path to a preset relative to
+/// the book itself.
+const BOOK_PATH: &str = "presets/fates.book.json";
+
+/// A built in preset, loaded from a `.preset.json` file under `assets/presets/`.
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub author: Option<String>,
+    pub tags: Vec<String>,
+    pub view: RecommendedView,
+    #[dependency]
+    pub thumbnail: Option<Handle<Image>>,
+    pub model: Model,
+    pub params: SimulationParams,
+    pub spawner: SpawnerConfig,
+}
+
+/// How a preset is best watched, applied along with it. Anything left out is left as it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecommendedView {
+    /// The scale of the 2D camera, smaller is closer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f32>,
+    /// How many particles to respawn with, capped at [`MAX_PARTICLES`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub particles: Option<usize>,
+}
+
+/// How a preset is written on disk, with the weights as rows of a square matrix so they're easy to
+/// read and tune by hand. Matrices smaller than the number of colours are padded with
+/// indifference.
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetFile {
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default)]
+    view: RecommendedView,
+    /// Path to an image, relative to the preset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    params: SimulationParams,
+    weights: Vec<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spawner: Option<SpawnerConfig>,
+}
+
+impl Preset {
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        Self::from_file(serde_json::from_slice(bytes)?)
+    }
+
+    fn from_file(file: PresetFile) -> Result<Self, serde_json::Error> {
+        let model = Model::from_rows(&file.weights)
+            .ok_or_else(|| serde_json::Error::custom("weights must be a square matrix"))?;
+
+        Ok(Preset {
+            name: file.name,
+            description: file.description,
+            author: file.author,
+            tags: file.tags,
+            view: file.view,
+            thumbnail: None,
+            model,
+            params: file.params,
+            spawner: file.spawner.unwrap_or(SpawnerConfig::None),
+        })
+    }
+
+    /// Writes the preset the way it's read, with just the weights of the colours in play. The
+    /// thumbnail is left out, as it's only known by its handle by now.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let num_colours = self.params.num_colours;
+
+        let weights = (0..num_colours)
+            .map(|source| {
+                self.model.row(ParticleColour::from_index(source))[..num_colours].to_vec()
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&PresetFile {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            author: self.author.clone(),
+            tags: self.tags.clone(),
+            view: self.view,
+            thumbnail: None,
+            params: self.params,
+            weights,
+            spawner: (self.spawner != SpawnerConfig::None).then(|| self.spawner.clone()),
+        })
+    }
+
+    /// Whether the preset goes by `name`, ignoring case, spaces and punctuation, so "The First
+    /// Garden" and "the_first_garden" both find it.
+    pub fn is_called(&self, name: &str) -> bool {
+        let simplify = |name: &str| {
+            name.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+
+        simplify(&self.name) == simplify(name)
+    }
+
+    /// Whether `search` turns up anywhere in the preset's name, description, author or tags,
+    /// ignoring case, and it has every one of `tags`.
+    pub fn matches(&self, search: &str, tags: &[String]) -> bool {
+        let search = search.trim().to_lowercase();
+        let mentions = |text: &str| text.to_lowercase().contains(&search);
+
+        tags.iter().all(|tag| self.tags.contains(tag))
+            && (mentions(&self.name)
+                || mentions(&self.description)
+                || self.author.as_deref().is_some_and(mentions)
+                || self.tags.iter().any(|tag| mentions(tag)))
+    }
+}
+
+#[derive(Debug, Asset, TypePath)]
+pub struct PresetBook {
+    #[dependency]
+    pub presets: Vec<Handle<Preset>>,
+}
+
+#[derive(Debug, Default)]
+struct PresetLoader;
+
+impl AssetLoader for PresetLoader {
+    type Asset = Preset;
+    type Settings = ();
+    type Error = serde_json::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Preset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(serde_json::Error::io)?;
+
+        let mut file = serde_json::from_slice::<PresetFile>(&bytes)?;
+
+        let thumbnail = file
+            .thumbnail
+            .take()
+            .map(|path| {
+                let path = load_context
+                    .asset_path()
+                    .resolve_embed(&path)
+                    .map_err(serde_json::Error::custom)?;
+
+                Ok(load_context.load(path))
+            })
+            .transpose()?;
+
+        Ok(Preset {
+            thumbnail,
+            ..Preset::from_file(file)?
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["preset.json"]
+    }
+}
+
+#[derive(Debug, Default)]
+struct PresetBookLoader;
+
+impl AssetLoader for PresetBookLoader {
+    type Asset = PresetBook;
+    type Settings = ();
+    type Error = serde_json::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<PresetBook, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(serde_json::Error::io)?;
+
+        let paths = serde_json::from_slice::<Vec<String>>(&bytes)?;
+
+        let presets = paths
+            .iter()
+            .map(|path| {
+                let path = load_context
+                    .asset_path()
+                    .resolve_embed(path)
+                    .map_err(serde_json::Error::custom)?;
+
+                Ok(load_context.load(path))
+            })
+            .collect::<Result<_, Self::Error>>()?;
+
+        Ok(PresetBook { presets })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["book.json"]
+    }
+}
+
+#[derive(Debug, Resource)]
+struct BookHandle(Handle<PresetBook>);
+
+fn load_book(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(BookHandle(asset_server.load(BOOK_PATH)));
+}
+
+/// The built in presets that have loaded so far, in the order of the book.
+#[derive(SystemParam)]
+pub struct Presets<'w> {
+    book: Res<'w, BookHandle>,
+    books: Res<'w, Assets<PresetBook>>,
+    presets: Res<'w, Assets<Preset>>,
+}
+
+impl Presets<'_> {
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId<Preset>, &Preset)> {
+        self.books
+            .get(&self.book.0)
+            .into_iter()
+            .flat_map(|book| book.presets.iter())
+            .filter_map(|handle| self.presets.get(handle).map(|preset| (handle.id(), preset)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    pub fn first(&self) -> Option<(AssetId<Preset>, &Preset)> {
+        let handle = self.books.get(&self.book.0)?.presets.first()?;
+        self.presets.get(handle).map(|preset| (handle.id(), preset))
+    }
+
+    /// Whether the book and every preset in it have loaded.
+    pub fn loaded(&self) -> bool {
+        self.books
+            .get(&self.book.0)
+            .is_some_and(|book| book.presets.len() == self.len())
+    }
+}
+
+/// The name of a preset's file without the extension, which is just as good as its name for
+/// finding it.
+pub fn file_stem(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()?.strip_suffix(".preset.json")
+}
+
+/// The preset the world starts with.
+#[derive(Debug, Resource, Clone, Default, PartialEq, Eq)]
+pub enum StartingPreset {
+    /// The first in the book.
+    #[default]
+    First,
+    /// The preset in the book with this name, see [`Preset::is_called`], or the first if there's
+    /// no such preset.
+    Named(String),
+    /// None at all, for when the world is started from something else.
+    None,
+}
+
+/// Sets the model, params and view from a preset, respawning the particles if it has its own
+/// layout or recommends a different number of them. When morphing, the model and params are eased
+/// in instead and nothing is respawned.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct ApplyPreset(pub AssetId<Preset>);
+
+/// The preset applied last, which is applied again whenever its file changes.
+#[derive(Debug, Resource, Clone, Copy)]
+struct CurrentPreset(AssetId<Preset>);
+
+fn apply_preset(
+    trigger: Trigger<ApplyPreset>,
+    presets: Res<Assets<Preset>>,
+    particle_count: Res<ParticleCount>,
+    morph: Res<MorphSettings>,
+    mut projections: Query<&mut Projection>,
+    mut commands: Commands,
+) {
+    let Some(preset) = presets.get(trigger.0) else {
+        return;
+    };
+
+    commands.insert_resource(CurrentPreset(trigger.0));
+
+    // The camera clamps this to the zoom bounds of the window on its own
+    if let Some(zoom) = preset.view.zoom {
+        projections.iter_mut().for_each(|mut projection| {
+            if let Projection::Orthographic(ref mut projection) = *projection {
+                projection.scale = zoom;
+            }
+        });
+    }
+
+    // Respawning would shatter whatever is being morphed, so the particles are left as they are
+    if morph.enabled() {
+        commands.trigger(Morph {
+            model: preset.model.clone(),
+            params: preset.params,
+        });
+        return;
+    }
+
+    commands.insert_resource(preset.model.clone());
+    commands.insert_resource(preset.params);
+
+    // Without a recommendation the number of particles is left as it is
+    let count = preset
+        .view
+        .particles
+        .map(|particles| ParticleCount(particles.min(MAX_PARTICLES)))
+        .filter(|count| count != &*particle_count);
+
+    if preset.spawner != SpawnerConfig::None {
+        commands.insert_resource(preset.spawner.clone());
+    }
+
+    if let Some(count) = count {
+        commands.insert_resource(count);
+    }
+
+    if preset.spawner != SpawnerConfig::None || count.is_some() {
+        commands.trigger(Respawn);
+    }
+}
+
+/// Starts the world off with the [`StartingPreset`], as soon as it's ready.
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn apply_first_preset(
+    mut applied: Local<bool>,
+    starting: Res<StartingPreset>,
+    presets: Presets,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if *applied {
+        return;
+    }
+
+    let preset = match &*starting {
+        StartingPreset::First => presets.first(),
+        StartingPreset::Named(name) => {
+            let called = |(id, preset): &(AssetId<Preset>, &Preset)| {
+                preset.is_called(name)
+                    || asset_server
+                        .get_path(*id)
+                        .is_some_and(|path| file_stem(path.path()) == Some(name.as_str()))
+            };
+
+            match presets.iter().find(called) {
+                Some(preset) => Some(preset),
+                // It may just not have loaded yet
+                None if !presets.loaded() => return,
+                None => {
+                    tracing::warn!(%name, "no such preset, starting with the first");
+                    presets.first()
+                }
+            }
+        }
+        StartingPreset::None => {
+            *applied = true;
+            commands.remove_resource::<RespawnOverrides>();
+            return;
+        }
+    };
+
+    if let Some((id, _)) = preset {
+        *applied = true;
+        commands.trigger(ApplyPreset(id));
+        commands.remove_resource::<RespawnOverrides>();
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn reapply_modified_preset(
+    mut events: EventReader<AssetEvent<Preset>>,
+    current: Option<Res<CurrentPreset>>,
+    presets: Res<Assets<Preset>>,
+    mut commands: Commands,
+) {
+    let Some(current) = current else {
+        return;
+    };
+
+    let modified = events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { id } if *id == current.0));
+
+    // Only the forces and params are reapplied, respawning on every save would make tuning harder
+    if let Some(preset) = presets.get(current.0).filter(|_| modified) {
+        commands.insert_resource(preset.model.clone());
+        commands.insert_resource(preset.params);
+    }
+}
+
+/// Reads a preset straight from `assets/presets/`, for tests that want real presets to work with.
+#[cfg(test)]
+pub fn load_preset_file(name: &str) -> Preset {
+    let path = format!(
+        "{}/assets/presets/{name}.preset.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    Preset::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Preset, RecommendedView, file_stem, load_preset_file};
+    use crate::particles::{
+        colour::{NUM_COLOURS, ParticleColour},
+        spawner::SpawnerConfig,
+    };
+
+    const PRESETS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/presets");
+
+    #[test]
+    fn book_presets_load() {
+        let book = std::fs::read(format!("{PRESETS_DIR}/fates.book.json")).unwrap();
+        let paths = serde_json::from_slice::<Vec<String>>(&book).unwrap();
+
+        assert!(!paths.is_empty());
+        for path in paths {
+            let preset = std::fs::read(format!("{PRESETS_DIR}/{path}")).unwrap();
+            let thumbnail =
+                serde_json::from_slice::<serde_json::Value>(&preset).unwrap()["thumbnail"]
+                    .as_str()
+                    .map(|thumbnail| format!("{PRESETS_DIR}/{thumbnail}"));
+
+            let preset = Preset::from_slice(&preset).unwrap();
+            assert!(preset.params.num_colours <= NUM_COLOURS, "{}", preset.name);
+            assert!(
+                thumbnail.is_some_and(|thumbnail| Path::new(&thumbnail).is_file()),
+                "{} has no thumbnail",
+                preset.name
+            );
+        }
+    }
+
+    #[test]
+    fn pads_small_matrices() {
+        let preset = load_preset_file("the_first_garden");
+
+        assert_eq!(preset.name, "The First Garden");
+        assert_eq!(preset.model.len(), NUM_COLOURS * NUM_COLOURS);
+        assert_eq!(
+            preset
+                .model
+                .weight(ParticleColour::from_index(1), ParticleColour::from_index(0)),
+            0.7
+        );
+        assert_eq!(
+            preset
+                .model
+                .weight(ParticleColour::from_index(4), ParticleColour::from_index(4)),
+            0.0
+        );
+        assert_eq!(preset.spawner, SpawnerConfig::None);
+    }
+
+    #[test]
+    fn reads_metadata() {
+        let preset = load_preset_file("jormungandr");
+
+        assert_eq!(preset.author.as_deref(), Some("Dylan Johnston"));
+        assert_eq!(preset.tags, ["snake", "chaotic"]);
+        assert_eq!(preset.view.zoom, Some(0.8));
+        assert_eq!(preset.view.particles, None);
+        assert!(!preset.description.is_empty());
+    }
+
+    #[test]
+    fn metadata_is_optional() {
+        let preset = br#"{
+            "name": "Bare",
+            "params": {
+                "friction": 2.0, "force_strength": 100.0, "peak_attraction_radius": 50.0,
+                "repulsion_radius": 25.0, "attraction_radius": 75.0, "decay_rate": 100.0,
+                "num_colours": 1
+            },
+            "weights": [[0.5]]
+        }"#;
+
+        let preset = Preset::from_slice(preset).unwrap();
+
+        assert_eq!(preset.description, "");
+        assert_eq!(preset.author, None);
+        assert!(preset.tags.is_empty());
+        assert_eq!(preset.view, RecommendedView::default());
+        assert!(preset.thumbnail.is_none());
+    }
+
+    #[test]
+    fn searches_everything_ignoring_case() {
+        let preset = load_preset_file("jormungandr");
+
+        assert!(preset.matches("", &[]));
+        assert!(preset.matches("JÖRMUN", &[]));
+        assert!(preset.matches("serpents", &[]));
+        assert!(preset.matches("dylan", &[]));
+        assert!(preset.matches(" snake ", &[]));
+        assert!(!preset.matches("garden", &[]));
+    }
+
+    #[test]
+    fn finds_presets_by_name() {
+        let preset = load_preset_file("jormungandr");
+
+        assert!(preset.is_called("Jörmungandr"));
+        assert!(preset.is_called("jörmungandr"));
+        assert!(!preset.is_called("jormungandr"));
+        assert!(load_preset_file("the_first_garden").is_called("the_first_garden"));
+
+        assert_eq!(
+            file_stem(Path::new("presets/jormungandr.preset.json")),
+            Some("jormungandr")
+        );
+        assert_eq!(file_stem(Path::new("presets/fates.book.json")), None);
+    }
+
+    #[test]
+    fn needs_every_tag() {
+        let preset = load_preset_file("jormungandr");
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert!(preset.matches("", &tags(&["snake"])));
+        assert!(preset.matches("", &tags(&["snake", "chaotic"])));
+        assert!(!preset.matches("", &tags(&["snake", "gentle"])));
+        assert!(!preset.matches("garden", &tags(&["snake"])));
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let preset = load_preset_file("jormungandr");
+        let json = preset.to_json().unwrap();
+        let read = Preset::from_slice(json.as_bytes()).unwrap();
+
+        assert_eq!(read.name, preset.name);
+        assert_eq!(read.description, preset.description);
+        assert_eq!(read.tags, preset.tags);
+        assert_eq!(read.view, preset.view);
+        assert_eq!(*read.model, *preset.model);
+        assert_eq!(read.params.num_colours, preset.params.num_colours);
+        assert_eq!(read.spawner, preset.spawner);
+    }
+
+    #[test]
+    fn rejects_ragged_matrices() {
+        let preset = br#"{
+            "name": "Ragged",
+            "params": {
+                "friction": 2.0, "force_strength": 100.0, "peak_attraction_radius": 50.0,
+                "repulsion_radius": 25.0, "attraction_radius": 75.0, "decay_rate": 100.0,
+                "num_colours": 2
+            },
+            "weights": [[0.1, 0.2], [0.3]]
+        }"#;
+
+        assert!(Preset::from_slice(preset).is_err());
+    }
+}
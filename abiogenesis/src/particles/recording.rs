@@ -0,0 +1,586 @@
+use std::io::{self, Read, Write};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::particles::{model::Model, simulation::SimulationParams};
+
+pub struct RecordingPlugin;
+impl Plugin for RecordingPlugin {
+    fn build(&self, _app: &mut App) {
+        // Recordings are files, which the browser doesn't have
+        #[cfg(not(target_arch = "wasm32"))]
+        native::build(_app);
+    }
+}
+
+const MAGIC: &[u8; 4] = b"ABTR";
+const FORMAT_VERSION: u8 = 1;
+
+/// Stored as JSON at the start of every recording so it describes the world it came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub params: SimulationParams,
+    pub model: Model,
+    /// The seed the particles were last laid out from. Decay is random, so it describes the start
+    /// of the world rather than reproducing the run, the frames do that.
+    pub seed: u64,
+    /// The volume positions are quantised across.
+    pub volume: Vec3,
+}
+
+/// One simulation step, the particles are in [`super::particle::ParticleIndex`] order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub positions: Vec<[u16; 3]>,
+    pub colours: Vec<u8>,
+    /// Particles that decayed into new ones this step, rather than moving.
+    pub decayed: Vec<u32>,
+}
+
+pub fn quantise(position: Vec3, volume: Vec3) -> [u16; 3] {
+    // Flat simulations have no depth, avoid dividing by it
+    let position = (position / volume.max(Vec3::ONE) + 0.5).clamp(Vec3::ZERO, Vec3::ONE);
+
+    (position * u16::MAX as f32)
+        .round()
+        .to_array()
+        .map(|it| it as u16)
+}
+
+pub fn dequantise(position: [u16; 3], volume: Vec3) -> Vec3 {
+    (Vec3::from_array(position.map(|it| it as f32)) / u16::MAX as f32 - 0.5) * volume
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0;
+
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+/// Small deltas in either direction become small varints.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Writes each frame as the change from the previous one, most particles barely move between
+/// steps so the deltas are one byte per axis.
+pub struct TrajectoryWriter<W: Write> {
+    writer: W,
+    previous: Frame,
+}
+
+impl<W: Write> TrajectoryWriter<W> {
+    pub fn new(mut writer: W, header: &RecordingHeader) -> io::Result<Self> {
+        let header = serde_json::to_vec(header)?;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer,
+            previous: Frame::default(),
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: Frame) -> io::Result<()> {
+        let writer = &mut self.writer;
+
+        write_varint(writer, frame.positions.len() as u64)?;
+        for (index, position) in frame.positions.iter().enumerate() {
+            let previous = self.previous.positions.get(index).unwrap_or(&[0; 3]);
+
+            for axis in 0..3 {
+                write_varint(
+                    writer,
+                    zigzag(position[axis] as i64 - previous[axis] as i64),
+                )?;
+            }
+        }
+
+        let colour_changes = frame
+            .colours
+            .iter()
+            .enumerate()
+            .filter(|(index, colour)| self.previous.colours.get(*index) != Some(*colour))
+            .collect::<Vec<_>>();
+
+        write_varint(writer, colour_changes.len() as u64)?;
+        for (index, colour) in colour_changes {
+            write_varint(writer, index as u64)?;
+            writer.write_all(&[*colour])?;
+        }
+
+        write_varint(writer, frame.decayed.len() as u64)?;
+        for index in &frame.decayed {
+            write_varint(writer, *index as u64)?;
+        }
+
+        self.previous = frame;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct TrajectoryReader<R: Read> {
+    reader: R,
+    previous: Frame,
+    header: RecordingHeader,
+}
+
+impl<R: Read> TrajectoryReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a trajectory recording",
+            ));
+        }
+
+        let mut version = [0];
+        reader.read_exact(&mut version)?;
+        if version[0] != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported recording version {}", version[0]),
+            ));
+        }
+
+        let mut length = [0; 4];
+        reader.read_exact(&mut length)?;
+
+        let mut header = vec![0; u32::from_le_bytes(length) as usize];
+        reader.read_exact(&mut header)?;
+
+        Ok(Self {
+            reader,
+            previous: Frame::default(),
+            header: serde_json::from_slice(&header)?,
+        })
+    }
+
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    /// Returns `None` once the recording has ended.
+    pub fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        let reader = &mut self.reader;
+
+        let count = match read_varint(reader) {
+            Ok(count) => count as usize,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let mut frame = Frame {
+            positions: Vec::with_capacity(count),
+            colours: self.previous.colours.clone(),
+            decayed: Vec::new(),
+        };
+
+        for index in 0..count {
+            let previous = self.previous.positions.get(index).unwrap_or(&[0; 3]);
+            let mut position = [0; 3];
+
+            for axis in 0..3 {
+                position[axis] = (previous[axis] as i64 + unzigzag(read_varint(reader)?)) as u16;
+            }
+
+            frame.positions.push(position);
+        }
+
+        frame.colours.resize(count, 0);
+        for _ in 0..read_varint(reader)? {
+            let index = read_varint(reader)? as usize;
+            let mut colour = [0];
+            reader.read_exact(&mut colour)?;
+
+            if let Some(slot) = frame.colours.get_mut(index) {
+                *slot = colour[0];
+            }
+        }
+
+        for _ in 0..read_varint(reader)? {
+            frame.decayed.push(read_varint(reader)? as u32);
+        }
+
+        self.previous = frame.clone();
+
+        Ok(Some(frame))
+    }
+}
+
+/// Driving the particles from a recording, only ever inserted natively.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Resource)]
+pub struct Replayer {
+    reader: TrajectoryReader<Box<dyn Read + Send + Sync>>,
+}
+
+/// Run condition for when a recording is driving the particles instead of the simulation.
+pub fn replaying(replayer: Option<Res<Replayer>>) -> bool {
+    replayer.is_some()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::{ToggleRecording, ToggleReplay};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{
+        fs::File,
+        io::{BufReader, BufWriter},
+    };
+
+    use bevy::prelude::*;
+
+    use crate::particles::{
+        colour::ParticleColour,
+        decay::ParticleDecayed,
+        model::Model,
+        particle::{Particle, ParticleIndex},
+        playback::simulation_running,
+        recording::{
+            Frame, RecordingHeader, Replayer, TrajectoryReader, TrajectoryWriter, dequantise,
+            quantise, replaying,
+        },
+        simulation::SimulationParams,
+        size::SimulationSize,
+        spawner::{ParticleAssets, SimulationSeed},
+    };
+
+    const RECORDING_PATH: &str = "abiogenesis_recording.abtr";
+
+    #[derive(Debug, Event, Clone, Copy, Reflect)]
+    pub struct ToggleRecording;
+
+    #[derive(Debug, Event, Clone, Copy, Reflect)]
+    pub struct ToggleReplay;
+
+    pub fn build(app: &mut App) {
+        app.add_observer(toggle_recording)
+            .add_observer(toggle_replay)
+            .add_systems(
+                FixedUpdate,
+                replay_frame.run_if(simulation_running.and(replaying)),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                record_frame.run_if(simulation_running.and(resource_exists::<Recorder>)),
+            );
+    }
+
+    #[derive(Resource)]
+    pub struct Recorder {
+        writer: TrajectoryWriter<BufWriter<File>>,
+        volume: Vec3,
+    }
+
+    #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+    fn toggle_recording(
+        _trigger: Trigger<ToggleRecording>,
+        recorder: Option<ResMut<Recorder>>,
+        params: Res<SimulationParams>,
+        model: Res<Model>,
+        seed: Res<SimulationSeed>,
+        simulation_size: SimulationSize,
+        mut commands: Commands,
+    ) {
+        if let Some(mut recorder) = recorder {
+            match recorder.writer.flush() {
+                Ok(()) => tracing::info!(path = RECORDING_PATH, "finished recording"),
+                Err(err) => tracing::warn!(?err, "failed to finish recording"),
+            }
+
+            commands.remove_resource::<Recorder>();
+            return;
+        }
+
+        let header = RecordingHeader {
+            params: *params,
+            model: model.clone(),
+            seed: **seed,
+            volume: simulation_size.volume(),
+        };
+
+        let writer = File::create(RECORDING_PATH)
+            .and_then(|file| TrajectoryWriter::new(BufWriter::new(file), &header));
+
+        match writer {
+            Ok(writer) => {
+                tracing::info!(path = RECORDING_PATH, "started recording");
+                commands.insert_resource(Recorder {
+                    writer,
+                    volume: header.volume,
+                });
+            }
+            Err(err) => tracing::warn!(?err, path = RECORDING_PATH, "failed to start recording"),
+        }
+    }
+
+    #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+    fn record_frame(
+        mut recorder: ResMut<Recorder>,
+        particle_index: Res<ParticleIndex>,
+        particles: Query<(&Transform, &ParticleColour), With<Particle>>,
+        mut decayed: EventReader<ParticleDecayed>,
+        mut commands: Commands,
+    ) {
+        let volume = recorder.volume;
+
+        let (positions, colours) = particle_index
+            .iter()
+            .filter_map(|entity| particles.get(*entity).ok())
+            .map(|(transform, colour)| {
+                (
+                    quantise(transform.translation, volume),
+                    colour.index() as u8,
+                )
+            })
+            .unzip();
+
+        let decayed = decayed
+            .read()
+            .filter_map(|ParticleDecayed(entity)| particle_index.iter().position(|it| it == entity))
+            .map(|index| index as u32)
+            .collect();
+
+        let frame = Frame {
+            positions,
+            colours,
+            decayed,
+        };
+
+        if let Err(err) = recorder.writer.write_frame(frame) {
+            tracing::warn!(?err, "failed to record frame, stopping the recording");
+            commands.remove_resource::<Recorder>();
+        }
+    }
+
+    #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+    fn toggle_replay(
+        _trigger: Trigger<ToggleReplay>,
+        replayer: Option<Res<Replayer>>,
+        mut commands: Commands,
+    ) {
+        if replayer.is_some() {
+            commands.remove_resource::<Replayer>();
+            return;
+        }
+
+        let reader = File::open(RECORDING_PATH).and_then(|file| {
+            TrajectoryReader::new(
+                Box::new(BufReader::new(file)) as Box<dyn std::io::Read + Send + Sync>
+            )
+        });
+
+        match reader {
+            Ok(reader) => {
+                tracing::info!(path = RECORDING_PATH, "replaying recording");
+
+                // Show the laws the recording was made with
+                commands.insert_resource(reader.header().params);
+                commands.insert_resource(reader.header().model.clone());
+                commands.insert_resource(Replayer { reader });
+            }
+            Err(err) => tracing::warn!(?err, path = RECORDING_PATH, "failed to open recording"),
+        }
+    }
+
+    #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+    fn replay_frame(
+        mut replayer: ResMut<Replayer>,
+        particle_index: Res<ParticleIndex>,
+        mut particles: Query<(&mut Transform, &ParticleColour), With<Particle>>,
+        particle_assets: Res<ParticleAssets>,
+        mut decayed: EventWriter<ParticleDecayed>,
+        mut commands: Commands,
+    ) {
+        let frame = match replayer.reader.read_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                tracing::info!("recording finished");
+                commands.remove_resource::<Replayer>();
+                return;
+            }
+            Err(err) => {
+                tracing::warn!(?err, "failed to read recording, stopping the replay");
+                commands.remove_resource::<Replayer>();
+                return;
+            }
+        };
+
+        let volume = replayer.reader.header().volume;
+
+        for (index, (position, colour)) in frame.positions.iter().zip(&frame.colours).enumerate() {
+            let position = dequantise(*position, volume);
+            let colour = ParticleColour::from_index(*colour as usize);
+
+            let Some(&entity) = particle_index.get(index) else {
+                commands.spawn((
+                    Particle,
+                    Transform::from_translation(position),
+                    colour,
+                    Mesh2d(particle_assets.mesh()),
+                ));
+                continue;
+            };
+
+            let Ok((mut transform, current)) = particles.get_mut(entity) else {
+                continue;
+            };
+
+            transform.translation = position;
+
+            if *current != colour {
+                commands.entity(entity).insert(colour);
+            }
+        }
+
+        // Replayed decay is announced just like the simulation's own
+        decayed.write_batch(
+            frame
+                .decayed
+                .iter()
+                .filter_map(|index| particle_index.get(*index as usize))
+                .map(|entity| ParticleDecayed(*entity)),
+        );
+
+        particle_index
+            .iter()
+            .skip(frame.positions.len())
+            .for_each(|entity| commands.entity(*entity).despawn());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn header() -> RecordingHeader {
+        RecordingHeader {
+            params: SimulationParams::DEFAULT,
+            model: Model::from_3x3([[0.5, -0.25, 0.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.25]]),
+            seed: 42,
+            volume: Vec3::new(1920.0, 1080.0, 0.0),
+        }
+    }
+
+    fn frames() -> Vec<Frame> {
+        vec![
+            Frame {
+                positions: vec![[100, 200, 32768], [65535, 0, 32768]],
+                colours: vec![0, 1],
+                decayed: vec![],
+            },
+            Frame {
+                positions: vec![[101, 198, 32768], [0, 65535, 32768], [5, 5, 5]],
+                colours: vec![0, 2, 3],
+                decayed: vec![1],
+            },
+            Frame {
+                positions: vec![[102, 197, 32768]],
+                colours: vec![0],
+                decayed: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn zigzag_round_trip() {
+        for value in [0, 1, -1, 2, -2, 65535, -65535, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut writer = TrajectoryWriter::new(Vec::new(), &header()).unwrap();
+        frames()
+            .into_iter()
+            .for_each(|frame| writer.write_frame(frame).unwrap());
+
+        let mut reader = TrajectoryReader::new(Cursor::new(writer.writer)).unwrap();
+        assert_eq!(reader.header().seed, 42);
+        assert_eq!(*reader.header().model, *header().model);
+
+        for frame in frames() {
+            assert_eq!(reader.read_frame().unwrap(), Some(frame));
+        }
+
+        assert_eq!(reader.read_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn still_particles_are_compact() {
+        let frame = Frame {
+            positions: vec![[30000, 20000, 0]; 1000],
+            colours: vec![1; 1000],
+            decayed: vec![],
+        };
+
+        let mut writer = TrajectoryWriter::new(Vec::new(), &header()).unwrap();
+        writer.write_frame(frame.clone()).unwrap();
+        let before = writer.writer.len();
+        writer.write_frame(frame).unwrap();
+
+        // One byte per axis, plus the counts
+        assert!(writer.writer.len() - before <= 3 * 1000 + 6);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(TrajectoryReader::new(Cursor::new(b"{\"not\": \"a recording\"}")).is_err());
+    }
+
+    #[test]
+    fn quantise_round_trip() {
+        let volume = Vec3::new(1920.0, 1080.0, 1080.0);
+        let position = Vec3::new(-500.25, 321.5, 12.0);
+
+        let error = (dequantise(quantise(position, volume), volume) - position).abs();
+        assert!(error.max_element() < 0.05);
+    }
+}
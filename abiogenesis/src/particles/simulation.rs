@@ -6,21 +6,43 @@ use serde::{Deserialize, Serialize};
 use crate::{
     math::{TorodialMath, remap},
     particles::{
+        biomes::BiomeMap,
         colour::ParticleColour,
-        model::{Model, PRESETS},
+        dimensions::Dimensions,
+        model::Model,
         particle::{Particle, Velocity},
+        playback::{SIMULATION_STEP, simulation_running},
+        recording::replaying,
         size::SimulationSize,
-        spatial_index::SpatialIndex,
+        spatial_index::{SpatialIndex, SpatialIndex3d},
     },
-    systems::AppSystems,
 };
 
 pub struct SimulationPlugin;
 impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<SimulationParams>()
-            .insert_resource(PRESETS[0].3)
-            .add_systems(Update, compute_forces.in_set(AppSystems::Update));
+            .insert_resource(SimulationParams::DEFAULT)
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Kept in 3D too, from where the particles fall on the plane, for the brushes,
+                    // following and organisms
+                    rebuild_spatial_index,
+                    compute_forces.run_if(
+                        resource_equals(Dimensions::Two)
+                            .and(simulation_running)
+                            .and(not(replaying)),
+                    ),
+                    (
+                        rebuild_spatial_index_3d,
+                        compute_forces_3d.run_if(simulation_running.and(not(replaying))),
+                    )
+                        .chain()
+                        .run_if(resource_equals(Dimensions::Three)),
+                )
+                    .chain(),
+            );
     }
 }
 
@@ -31,7 +53,7 @@ pub const PEAK_ATTRACTION_RADIUS_RANGE: RangeInclusive<f32> = 0.0..=200.0;
 pub const REPULSION_RADIUS_RANGE: RangeInclusive<f32> = 0.0..=200.0;
 pub const DECAY_RATE_RANGE: RangeInclusive<f32> = 0.0..=200.0;
 
-#[derive(Debug, Reflect, Resource, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Reflect, Resource, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[reflect(Resource)]
 pub struct SimulationParams {
     pub friction: f32,
@@ -63,24 +85,41 @@ impl Default for SimulationParams {
     }
 }
 
+/// Kept separate from the forces so the brushes and follow tool still find particles while paused.
 #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
-fn compute_forces(
-    mut particles: Query<(Entity, &mut Transform, &mut Velocity, &ParticleColour), With<Particle>>,
+pub fn rebuild_spatial_index(
+    particles: Query<(Entity, &Transform, &ParticleColour), With<Particle>>,
     mut spatial_index: ResMut<SpatialIndex>,
+) {
+    spatial_index.clear();
+    particles.iter().for_each(|(entity, transform, color)| {
+        spatial_index.insert(transform.translation.truncate(), (entity, *color));
+    });
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn rebuild_spatial_index_3d(
+    particles: Query<(Entity, &Transform, &ParticleColour), With<Particle>>,
+    mut spatial_index: ResMut<SpatialIndex3d>,
+) {
+    spatial_index.clear();
+    particles.iter().for_each(|(entity, transform, color)| {
+        spatial_index.insert(transform.translation, (entity, *color));
+    });
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn compute_forces(
+    mut particles: Query<(Entity, &mut Transform, &mut Velocity, &ParticleColour), With<Particle>>,
+    spatial_index: Res<SpatialIndex>,
     model: Res<Model>,
     params: Res<SimulationParams>,
+    biome_map: Res<BiomeMap>,
     simulation_size: SimulationSize,
-    time: Res<Time>,
 ) -> Result<()> {
     let bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
 
-    spatial_index.clear();
-    particles.iter().for_each(|(entity, transform, _, color)| {
-        spatial_index.insert(transform.translation.truncate(), (entity, *color));
-    });
-
-    let dt = time.delta_secs();
-    let friction_factor = (-params.friction * dt).exp();
+    let dt = SIMULATION_STEP;
 
     // https://github.com/TheBevyFlock/bevy_simple_subsecond_system/issues/26
     #[cfg(feature = "hot_reload")]
@@ -102,6 +141,8 @@ fn compute_forces(
 
         let too_crowded = false;
 
+        let laws = biome_map.laws(transform.translation.truncate(), bounds, &params, &model);
+
         let force = spatial_index
             .query(transform.translation.truncate(), params.attraction_radius)
             .filter(|(_, (it, _))| *it != entity)
@@ -111,7 +152,7 @@ fn compute_forces(
 
                 let magnitude = magnitude(
                     &params,
-                    model.weight(*a_color, *b_color),
+                    laws.model.weight(*a_color, *b_color),
                     displacement.length(),
                 );
 
@@ -120,23 +161,77 @@ fn compute_forces(
                     return Vec2::ZERO;
                 }
 
-                magnitude * params.force_strength * displacement.normalize()
+                magnitude * laws.force_strength * displacement.normalize()
             })
             .sum::<Vec2>();
 
-        **velocity += force * dt;
-        **velocity *= friction_factor;
+        **velocity += force.extend(0.0) * dt;
+        **velocity *= (-laws.friction * dt).exp();
         **velocity = velocity.clamp_length(0.0, 200.0);
 
         transform.translation = bounds
-            .toroidal_wrap(transform.translation.truncate() + **velocity * dt)
+            .toroidal_wrap(transform.translation.truncate() + velocity.truncate() * dt)
             .extend(0.0);
     });
 
     Ok(())
 }
 
-fn magnitude(params: &SimulationParams, factor: f32, distance: f32) -> f32 {
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn compute_forces_3d(
+    mut particles: Query<(Entity, &mut Transform, &mut Velocity, &ParticleColour), With<Particle>>,
+    spatial_index: Res<SpatialIndex3d>,
+    model: Res<Model>,
+    params: Res<SimulationParams>,
+    biome_map: Res<BiomeMap>,
+    simulation_size: SimulationSize,
+) -> Result<()> {
+    let bounds = Cuboid::from_size(simulation_size.volume());
+    let biome_bounds = Rect::from_center_size(Vec2::ZERO, simulation_size.dimensions());
+
+    let dt = SIMULATION_STEP;
+
+    #[cfg(feature = "hot_reload")]
+    let iter = particles.iter_mut();
+
+    #[cfg(not(feature = "hot_reload"))]
+    let iter = particles.par_iter_mut();
+
+    iter.for_each(|(entity, mut transform, mut velocity, a_color)| {
+        let laws = biome_map.laws(
+            transform.translation.truncate(),
+            biome_bounds,
+            &params,
+            &model,
+        );
+
+        let force = spatial_index
+            .query(transform.translation, params.attraction_radius)
+            .filter(|(_, (it, _))| *it != entity)
+            .map(|(b_position, (_, b_color))| {
+                let displacement = bounds.toroidal_displacement(transform.translation, b_position);
+
+                let magnitude = magnitude(
+                    &params,
+                    laws.model.weight(*a_color, *b_color),
+                    displacement.length(),
+                );
+
+                magnitude * laws.force_strength * displacement.normalize_or_zero()
+            })
+            .sum::<Vec3>();
+
+        **velocity += force * dt;
+        **velocity *= (-laws.friction * dt).exp();
+        **velocity = velocity.clamp_length(0.0, 200.0);
+
+        transform.translation = bounds.toroidal_wrap(transform.translation + **velocity * dt);
+    });
+
+    Ok(())
+}
+
+pub fn magnitude(params: &SimulationParams, factor: f32, distance: f32) -> f32 {
     if distance <= params.repulsion_radius {
         remap(distance, 0.0, params.repulsion_radius, -1.0, 0.0)
     } else if distance <= params.peak_attraction_radius {
@@ -1,6 +1,13 @@
 use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
 
-use crate::{particles::spatial_index::SpatialIndex, systems::AppSystems};
+use crate::{
+    particles::{
+        dimensions::Dimensions,
+        spatial_index::{SpatialIndex, SpatialIndex3d},
+    },
+    systems::AppSystems,
+};
 
 pub struct SimulationSizePlugin;
 impl Plugin for SimulationSizePlugin {
@@ -12,6 +19,7 @@ impl Plugin for SimulationSizePlugin {
 #[derive(SystemParam)]
 pub struct SimulationSize<'w> {
     window: Single<'w, &'static Window>,
+    mode: Res<'w, Dimensions>,
     // projection: Single<'w, &'static Projection>,
 }
 
@@ -22,16 +30,42 @@ impl SimulationSize<'_> {
         Vec2::new(width.max(1920.0), height.max(1080.0))
     }
 
+    // The 3D simulation is as deep as it is tall, in 2D the volume is flat
+    pub fn volume(&self) -> Vec3 {
+        let dimensions = self.dimensions();
+
+        match *self.mode {
+            Dimensions::Two => dimensions.extend(0.0),
+            Dimensions::Three => dimensions.extend(dimensions.y),
+        }
+    }
+
+    pub fn random_position(&self) -> Vec3 {
+        self.random_position_with(&mut rand::thread_rng())
+    }
+
+    pub fn random_position_with(&self, rng: &mut impl Rng) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(-0.5..0.5),
+            rng.gen_range(-0.5..0.5),
+            rng.gen_range(-0.5..0.5),
+        ) * self.volume()
+    }
+
     pub fn scale_bounds(&self) -> (f32, f32) {
         scale_bounds(self.window.width(), self.window.height())
     }
 }
 
-fn resize_simulation(mut spatial_index: ResMut<SpatialIndex>, simulation_size: SimulationSize) {
-    spatial_index.update_bounds(Rect::from_center_size(
-        Vec2::ZERO,
-        simulation_size.dimensions(),
-    ));
+fn resize_simulation(
+    mut spatial_index: ResMut<SpatialIndex>,
+    mut spatial_index_3d: ResMut<SpatialIndex3d>,
+    simulation_size: SimulationSize,
+) {
+    let dimensions = simulation_size.dimensions();
+
+    spatial_index.update_bounds(Rect::from_center_size(Vec2::ZERO, dimensions));
+    spatial_index_3d.update_bounds(Cuboid::from_size(dimensions.extend(dimensions.y)));
 }
 
 // Allows zooming out until you hit the screen edges
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::{
     particles::{colour::ParticleColour, size::SimulationSize},
-    spatial_hash::SpatialHashGrid,
+    spatial_hash::{SpatialHashGrid, SpatialHashGrid3d},
 };
 
 pub struct SpatialIndexPlugin;
@@ -12,12 +12,32 @@ impl Plugin for SpatialIndexPlugin {
     }
 }
 
+/// How many cells the simulation is split into across and down, a cell being a little wider than
+/// the furthest any force reaches at the smallest size of the simulation.
+pub const GRID_CELLS: (usize, usize) = (19, 10);
+
 #[derive(Debug, Resource, Deref, DerefMut)]
 pub struct SpatialIndex(SpatialHashGrid<(Entity, ParticleColour)>);
 
+impl SpatialIndex {
+    pub fn new(bounds: Rect) -> Self {
+        Self(SpatialHashGrid::new(bounds, GRID_CELLS))
+    }
+}
+
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct SpatialIndex3d(SpatialHashGrid3d<(Entity, ParticleColour)>);
+
 fn initialise_spatial_index(mut commands: Commands, simulation: SimulationSize) {
-    commands.insert_resource(SpatialIndex(SpatialHashGrid::new(
-        Rect::from_center_size(Vec2::ZERO, simulation.dimensions()),
-        (19, 10),
+    let dimensions = simulation.dimensions();
+
+    commands.insert_resource(SpatialIndex::new(Rect::from_center_size(
+        Vec2::ZERO,
+        dimensions,
+    )));
+
+    commands.insert_resource(SpatialIndex3d(SpatialHashGrid3d::new(
+        Cuboid::from_size(dimensions.extend(dimensions.y)),
+        (19, 10, 10),
     )));
 }
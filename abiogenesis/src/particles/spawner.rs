@@ -1,5 +1,7 @@
 use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use rand_distr::uniform;
+use serde::{Deserialize, Serialize};
 
 use crate::particles::{
     colour::*,
@@ -13,7 +15,9 @@ impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnParticle>()
             .insert_resource(SpawnerConfig::Uniform)
+            .insert_resource(ParticleCount(MAX_PARTICLES))
             .insert_resource(OldestParticle::default())
+            .insert_resource(SimulationSeed(rand::random()))
             .add_systems(Startup, (init_assets, spawn_particles_on_startup).chain())
             .add_systems(Update, spawn_particle)
             .add_systems(Update, update_colours_on_num_change)
@@ -32,6 +36,8 @@ pub fn spawn_particles_on_startup(mut commands: Commands) {
 #[derive(Debug, Resource)]
 pub struct ParticleAssets {
     mesh: Handle<Mesh>,
+    sphere: Handle<Mesh>,
+    materials_3d: [Handle<StandardMaterial>; NUM_COLOURS],
     red: Handle<ColorMaterial>,
     green: Handle<ColorMaterial>,
     blue: Handle<ColorMaterial>,
@@ -51,14 +57,28 @@ impl ParticleAssets {
             ParticleColour::Aqua => self.aqua.clone(),
         }
     }
+
+    pub fn material_3d(&self, color: ParticleColour) -> Handle<StandardMaterial> {
+        self.materials_3d[color.index()].clone()
+    }
+
+    pub fn mesh(&self) -> Handle<Mesh> {
+        self.mesh.clone()
+    }
+
+    pub fn sphere(&self) -> Handle<Mesh> {
+        self.sphere.clone()
+    }
 }
 
 fn init_assets(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut materials_3d: ResMut<Assets<StandardMaterial>>,
 ) {
     let mesh = meshes.add(Circle::new(1.0));
+    let sphere = meshes.add(Sphere::new(2.0).mesh().ico(1).unwrap());
     let red = materials.add(Color::from(RED));
     let green = materials.add(Color::from(GREEN));
     let blue = materials.add(Color::from(BLUE));
@@ -66,8 +86,14 @@ fn init_assets(
     let pink = materials.add(Color::from(PINK));
     let aqua = materials.add(Color::from(AQUA));
 
+    let materials_3d = std::array::from_fn(|index| {
+        materials_3d.add(Color::from(ParticleColour::from_index(index)))
+    });
+
     commands.insert_resource(ParticleAssets {
         mesh,
+        sphere,
+        materials_3d,
         red,
         green,
         blue,
@@ -77,7 +103,10 @@ fn init_assets(
     });
 }
 
-#[derive(Debug, Clone, Resource, Default, PartialEq)]
+/// Serialized with explicit tags, e.g. `{"kind": "custom", "shapes": [["Red", {"shape": "circle",
+/// ...}]]}`, as it's part of the shared state and has to keep reading the same across versions.
+#[derive(Debug, Clone, Resource, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "shapes", rename_all = "snake_case")]
 pub enum SpawnerConfig {
     None,
     #[default]
@@ -85,7 +114,8 @@ pub enum SpawnerConfig {
     Custom(Vec<(ParticleColour, SpawnShape)>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
 pub enum SpawnShape {
     Rect(Rect),
     Circle {
@@ -100,34 +130,37 @@ pub enum SpawnShape {
 }
 
 impl SpawnShape {
-    pub fn transform(&self) -> Transform {
-        match self {
-            SpawnShape::Rect(rect) => Transform::from_xyz(
-                rect.min.x + (rect.max.x - rect.min.x) * rand::random::<f32>(),
-                rect.min.y + (rect.max.y - rect.min.y) * rand::random::<f32>(),
-                0.0,
+    /// A random point in the shape, spread through `depth` like [`SimulationSize::volume`] so the
+    /// shape becomes a slab in 3D rather than a single sheet.
+    pub fn transform(&self, depth: f32, rng: &mut impl Rng) -> Transform {
+        let position = match self {
+            SpawnShape::Rect(rect) => Vec2::new(
+                rect.min.x + (rect.max.x - rect.min.x) * rng.gen_range(0.0..1.0),
+                rect.min.y + (rect.max.y - rect.min.y) * rng.gen_range(0.0..1.0),
             ),
             SpawnShape::Circle { position, radius } => {
-                let angle = 2.0 * std::f32::consts::PI * rand::random::<f32>();
+                let angle = 2.0 * std::f32::consts::PI * rng.gen_range(0.0..1.0);
                 let x = position.x + radius * angle.cos();
                 let y = position.y + radius * angle.sin();
 
-                Transform::from_xyz(x, y, 0.0)
+                Vec2::new(x, y)
             }
             SpawnShape::HollowCircle {
                 position,
                 inner_radius,
                 outer_radius,
             } => {
-                let angle = 2.0 * std::f32::consts::PI * rand::random::<f32>();
-                let radius = inner_radius + (outer_radius - inner_radius) * rand::random::<f32>();
+                let angle = 2.0 * std::f32::consts::PI * rng.gen_range(0.0..1.0);
+                let radius = inner_radius + (outer_radius - inner_radius) * rng.gen_range(0.0..1.0);
 
                 let x = position.x + radius * angle.cos();
                 let y = position.y + radius * angle.sin();
 
-                Transform::from_xyz(x, y, 0.0)
+                Vec2::new(x, y)
             }
-        }
+        };
+
+        Transform::from_translation(position.extend(depth * rng.gen_range(-0.5..0.5)))
     }
 }
 
@@ -141,42 +174,43 @@ fn respawn_particles(
     particle_assets: Res<ParticleAssets>,
     mut params: ResMut<SimulationParams>,
     spawner_config: Res<SpawnerConfig>,
+    particle_count: Res<ParticleCount>,
+    overrides: Option<Res<RespawnOverrides>>,
+    mut seed: ResMut<SimulationSeed>,
 ) -> Result<()> {
     params.decay_rate = 80.0;
 
+    let overrides = overrides.as_deref().copied().unwrap_or_default();
+    *seed = SimulationSeed(overrides.seed.unwrap_or_else(rand::random));
+    let mut rng = StdRng::seed_from_u64(**seed);
+    let count = overrides
+        .particles
+        .unwrap_or(**particle_count)
+        .min(MAX_PARTICLES);
+
     particles
         .iter()
         .for_each(|entity| commands.entity(entity).despawn());
 
-    let Vec2 {
-        x: width,
-        y: height,
-    } = simulation_size.dimensions();
-
     particle_indexes.clear();
 
-    let transform = move |color: ParticleColour| match &*spawner_config {
-        SpawnerConfig::None | SpawnerConfig::Uniform => Transform::from_xyz(
-            width * (rand::random::<f32>() - 0.5),
-            height * (rand::random::<f32>() - 0.5),
-            0.0,
-        ),
+    let depth = simulation_size.volume().z;
+    let mut transform = |color: ParticleColour| match &*spawner_config {
+        SpawnerConfig::None | SpawnerConfig::Uniform => {
+            Transform::from_translation(simulation_size.random_position_with(&mut rng))
+        }
         SpawnerConfig::Custom(items) => {
             for (inner_colour, shape) in items {
                 if color == *inner_colour {
-                    return shape.transform();
+                    return shape.transform(depth, &mut rng);
                 }
             }
 
-            Transform::from_xyz(
-                width * (rand::random::<f32>() - 0.5),
-                height * (rand::random::<f32>() - 0.5),
-                0.0,
-            )
+            Transform::from_translation(simulation_size.random_position_with(&mut rng))
         }
     };
 
-    (0..MAX_PARTICLES).for_each(|i| {
+    (0..count).for_each(|i| {
         let color = match i % params.num_colours {
             0 => ParticleColour::Red,
             1 => ParticleColour::Green,
@@ -198,6 +232,23 @@ fn respawn_particles(
     Ok(())
 }
 
+/// How many particles a respawn fills the world with, at most [`MAX_PARTICLES`].
+#[derive(Debug, Resource, Clone, Copy, Deref, PartialEq, Eq)]
+pub struct ParticleCount(pub usize);
+
+/// Seed for the layout of the last respawn, so recordings can describe where they started.
+#[derive(Debug, Resource, Clone, Copy, Deref, PartialEq, Eq)]
+pub struct SimulationSeed(pub u64);
+
+/// Pins the seed and number of particles of the respawns that start the world off, whatever the
+/// starting preset recommends, so runs started from the command line can be repeated exactly.
+/// It's removed once the starting preset or state has been applied, leaving later respawns alone.
+#[derive(Debug, Resource, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RespawnOverrides {
+    pub seed: Option<u64>,
+    pub particles: Option<usize>,
+}
+
 #[derive(Debug, Resource, Deref, DerefMut, Default)]
 pub struct OldestParticle(usize);
 
@@ -214,15 +265,21 @@ fn spawn_particle(
     mut spawn_particles: EventReader<SpawnParticle>,
     particle_index: Res<ParticleIndex>,
     mut oldest_particle: ResMut<OldestParticle>,
+    simulation_size: SimulationSize,
 ) -> Result<()> {
+    let depth = simulation_size.volume().z;
+
     for SpawnParticle {
         position,
         colour: color,
     } in spawn_particles.read()
     {
+        // Painted particles are spread through the volume in 3D, like a respawn
+        let position = position.extend(depth * (rand::random::<f32>() - 0.5));
+
         if particle_index.len() >= MAX_PARTICLES {
             commands.entity(particle_index[**oldest_particle]).insert((
-                Transform::from_translation(position.extend(0.0)),
+                Transform::from_translation(position),
                 Velocity::default(),
                 *color,
             ));
@@ -231,7 +288,7 @@ fn spawn_particle(
         } else {
             commands.spawn((
                 Particle,
-                Transform::from_translation(position.extend(0.0)),
+                Transform::from_translation(position),
                 *color,
                 Mesh2d(particle_assets.mesh.clone()),
             ));
@@ -266,3 +323,75 @@ fn update_colours_on_num_change(
             .collect::<Vec<_>>(),
     );
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{
+        ParticleAssets, ParticleCount, Respawn, SimulationSeed, SpawnShape, SpawnerConfig,
+        respawn_particles,
+    };
+    use crate::particles::{
+        colour::ParticleColour,
+        dimensions::Dimensions,
+        particle::{Particle, ParticleIndex},
+        simulation::SimulationParams,
+    };
+
+    fn respawn(dimensions: Dimensions) -> Vec<f32> {
+        let mut world = World::new();
+
+        world.insert_resource(ParticleIndex(Vec::new()));
+        world.insert_resource(dimensions);
+        world.insert_resource(SimulationParams::DEFAULT);
+        world.insert_resource(ParticleCount(100));
+        world.insert_resource(SimulationSeed(0));
+        world.insert_resource(SpawnerConfig::Custom(
+            (0..SimulationParams::DEFAULT.num_colours)
+                .map(|index| {
+                    let shape = SpawnShape::Circle {
+                        position: Vec2::ZERO,
+                        radius: 200.0,
+                    };
+                    (ParticleColour::from_index(index), shape)
+                })
+                .collect(),
+        ));
+        world.insert_resource(ParticleAssets {
+            mesh: default(),
+            sphere: default(),
+            materials_3d: default(),
+            red: default(),
+            green: default(),
+            blue: default(),
+            orange: default(),
+            pink: default(),
+            aqua: default(),
+        });
+        world.spawn(Window::default());
+
+        world.add_observer(respawn_particles);
+        world.trigger(Respawn);
+        world.flush();
+
+        world
+            .query_filtered::<&Transform, With<Particle>>()
+            .iter(&world)
+            .map(|transform| transform.translation.z)
+            .collect()
+    }
+
+    #[test]
+    fn custom_spawners_fill_the_volume() {
+        let depths = respawn(Dimensions::Three);
+
+        assert_eq!(depths.len(), 100);
+        assert!(depths.iter().any(|z| *z != depths[0]));
+    }
+
+    #[test]
+    fn custom_spawners_stay_flat_in_2d() {
+        assert!(respawn(Dimensions::Two).iter().all(|z| *z == 0.0));
+    }
+}
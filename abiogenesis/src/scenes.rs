@@ -1,13 +1,62 @@
 use bevy::prelude::*;
 
+use crate::snapshot::WorldSnapshot;
+
 pub struct ScenePlugin;
 
 impl Plugin for ScenePlugin {
-    fn build(&self, app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.init_state::<Scene>()
+            .add_computed_state::<Shaping>()
+            .enable_state_scoped_entities::<Shaping>()
+            .add_observer(change_scene)
+            .add_systems(OnExit(Scene::Sandbox), stash_sandbox)
+            .add_systems(OnEnter(Scene::Sandbox), restore_sandbox);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Reflect, States, PartialEq, Eq, Hash)]
+#[states(scoped_entities)]
+pub enum Scene {
+    #[default]
+    Title,
+    Sandbox,
+    Challenge,
+    Gallery,
+}
+
+/// The scenes where the world can be shaped by hand, which share the sidebar, toolbar and controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shaping;
+
+impl ComputedStates for Shaping {
+    type SourceStates = Scene;
+
+    fn compute(scene: Scene) -> Option<Self> {
+        matches!(scene, Scene::Sandbox | Scene::Challenge).then_some(Shaping)
+    }
 }
 
-// #[derive(Debug, Clone, Copy, Reflect, States, PartialEq, Eq, Hash)]
-// pub enum Scene {
-//     Sandbox,
-//     Challenges,
-// }
+#[derive(Debug, Event, Clone, Copy)]
+pub struct ChangeScene(pub Scene);
+
+fn change_scene(trigger: Trigger<ChangeScene>, mut next_scene: ResMut<NextState<Scene>>) {
+    next_scene.set(trigger.0);
+}
+
+/// The sandbox as it was left, so challenges and the gallery can take over the world and give it
+/// back afterwards.
+#[derive(Debug, Resource)]
+struct SandboxSnapshot(WorldSnapshot);
+
+fn stash_sandbox(world: &mut World) {
+    let snapshot = WorldSnapshot::capture(world);
+    world.insert_resource(SandboxSnapshot(snapshot));
+}
+
+fn restore_sandbox(world: &mut World) {
+    // Coming from the title screen there's nothing to restore, the sandbox carries on from there
+    if let Some(SandboxSnapshot(snapshot)) = world.remove_resource::<SandboxSnapshot>() {
+        snapshot.restore(world);
+    }
+}
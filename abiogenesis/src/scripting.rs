@@ -0,0 +1,717 @@
+//! Scripts for exhibitions and experiments, written in [Rhai](https://rhai.rs). A script's top
+//! level runs once when it loads, and again whenever the file changes, to set up what it wants
+//! to happen:
+//!
+//! ```rhai
+//! every(30.0, "season");
+//! on("respawn", "scatter");
+//!
+//! fn season() {
+//!     this.seasons = if "seasons" in this { this.seasons + 1 } else { 1 };
+//!     set_param("friction", if this.seasons % 2 == 0 { 1.0 } else { 3.0 });
+//! }
+//!
+//! fn scatter() {
+//!     for i in 0..50 { spawn(i * 10.0, 0.0, "red"); }
+//! }
+//! ```
+//!
+//! Timers count simulated time, so they stand still while paused. Functions share `this`, a map
+//! that lives as long as the script.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::{
+    browser_state::Received,
+    particles::{
+        automation::Automated,
+        colour::{NUM_COLOURS, ParticleColour},
+        model::{ClearParticles, Model, Randomise},
+        organisms::Clusters,
+        particle::Particle,
+        playback::SimulationClock,
+        simulation::SimulationParams,
+        spawner::{Respawn, SpawnParticle},
+    },
+    systems::AppSystems,
+};
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Script>()
+            .register_asset_loader(ScriptLoader)
+            .init_resource::<Scripting>()
+            .init_resource::<ScriptEvents>()
+            .add_observer(run_script)
+            .add_observer(stop_script)
+            .add_observer(|_: Trigger<Respawn>, mut events: ResMut<ScriptEvents>| {
+                events.0.push(ScriptEvent::Respawn);
+            })
+            .add_observer(
+                |_: Trigger<ClearParticles>, mut events: ResMut<ScriptEvents>| {
+                    events.0.push(ScriptEvent::Clear);
+                },
+            )
+            .add_observer(|_: Trigger<Randomise>, mut events: ResMut<ScriptEvents>| {
+                events.0.push(ScriptEvent::Randomise);
+            })
+            .add_observer(|_: Trigger<Received>, mut events: ResMut<ScriptEvents>| {
+                events.0.push(ScriptEvent::Received);
+            })
+            .add_systems(Startup, run_script_from_env)
+            .add_systems(Update, run_scripts.in_set(AppSystems::Update));
+    }
+}
+
+/// The source of a `.rhai` file in the assets, compiled once it's chosen to run.
+#[derive(Debug, Asset, TypePath)]
+pub struct Script {
+    pub source: String,
+}
+
+#[derive(Debug, Default)]
+struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    type Asset = Script;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Script, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let source = String::from_utf8(bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+        Ok(Script { source })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+/// Runs the script at this asset path, replacing any script already running.
+#[derive(Debug, Event, Clone)]
+pub struct RunScript(pub String);
+
+#[derive(Debug, Event, Clone, Copy)]
+pub struct StopScript;
+
+/// Something that happened which scripts can respond to with `on`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptEvent {
+    Respawn,
+    Clear,
+    Randomise,
+    /// A state arrived through Receive.
+    Received,
+}
+
+impl FromStr for ScriptEvent {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "respawn" => Ok(ScriptEvent::Respawn),
+            "clear" => Ok(ScriptEvent::Clear),
+            "randomise" => Ok(ScriptEvent::Randomise),
+            "received" => Ok(ScriptEvent::Received),
+            _ => Err(format!(
+                "unknown event {name:?}, expected respawn, clear, randomise or received"
+            )),
+        }
+    }
+}
+
+/// Events since the scripts last ran.
+#[derive(Debug, Resource, Default)]
+struct ScriptEvents(Vec<ScriptEvent>);
+
+/// Asks for something the script can't just write into the [`Sandbox`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Request {
+    Spawn(Vec2, ParticleColour),
+    Respawn,
+    Clear,
+    Randomise,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptTimer {
+    every: f32,
+    next: f32,
+    function: String,
+}
+
+/// Everything a script can see of the world, and everything it has asked for. It's filled in
+/// before the script runs and read back afterwards, the functions bound into the engine only
+/// ever touch this.
+#[derive(Debug)]
+struct Sandbox {
+    time: f32,
+    model: Model,
+    params: SimulationParams,
+    laws_changed: bool,
+    particles: [usize; NUM_COLOURS],
+    /// The mass of each organism in each colour, largest first.
+    organisms: Vec<[usize; NUM_COLOURS]>,
+    requests: Vec<Request>,
+    timers: Vec<ScriptTimer>,
+    handlers: Vec<(ScriptEvent, String)>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            model: Model::from_rows(&[]).unwrap(),
+            params: SimulationParams::DEFAULT,
+            laws_changed: false,
+            particles: [0; NUM_COLOURS],
+            organisms: Vec::new(),
+            requests: Vec::new(),
+            timers: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A law by the name it's saved under, see [`Automated`].
+fn law(name: &str) -> ScriptResult<Automated> {
+    match name {
+        "friction" => Ok(Automated::Friction),
+        "force_strength" => Ok(Automated::ForceStrength),
+        "attraction_radius" => Ok(Automated::AttractionRadius),
+        "peak_attraction_radius" => Ok(Automated::PeakAttractionRadius),
+        "repulsion_radius" => Ok(Automated::RepulsionRadius),
+        "decay_rate" => Ok(Automated::DecayRate),
+        _ => Err(format!("unknown param {name:?}").into()),
+    }
+}
+
+fn colour(name: &str) -> ScriptResult<ParticleColour> {
+    Ok(name.parse::<ParticleColour>()?)
+}
+
+/// Scripts write `2` as readily as `2.0`, either will do.
+fn number(value: Dynamic) -> ScriptResult<f32> {
+    value
+        .as_float()
+        .map(|value| value as f32)
+        .or_else(|_| value.as_int().map(|value| value as f32))
+        .map_err(|type_name| format!("expected a number, found {type_name}").into())
+}
+
+/// Far more than any sensible script needs in one go, but stops a runaway loop freezing the
+/// exhibition.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+fn lock(sandbox: &Mutex<Sandbox>) -> MutexGuard<'_, Sandbox> {
+    sandbox.lock().unwrap()
+}
+
+fn engine(sandbox: &Arc<Mutex<Sandbox>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .on_print(|text| tracing::info!(target: "script", "{text}"))
+        .on_debug(|text, _, position| tracing::debug!(target: "script", ?position, "{text}"));
+
+    let shared = sandbox.clone();
+    engine.register_fn("time", move || lock(&shared).time as f64);
+
+    let shared = sandbox.clone();
+    engine.register_fn("param", move |name: &str| -> ScriptResult<f64> {
+        let sandbox = lock(&shared);
+        Ok(law(name)?.get(&sandbox.model, &sandbox.params) as f64)
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "set_param",
+        move |name: &str, value: Dynamic| -> ScriptResult<()> {
+            let (law, value) = (law(name)?, number(value)?);
+            let sandbox = &mut *lock(&shared);
+            law.set(&mut sandbox.model, &mut sandbox.params, value);
+            sandbox.laws_changed = true;
+            Ok(())
+        },
+    );
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "weight",
+        move |source: &str, target: &str| -> ScriptResult<f64> {
+            Ok(lock(&shared).model.weight(colour(source)?, colour(target)?) as f64)
+        },
+    );
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "set_weight",
+        move |source: &str, target: &str, value: Dynamic| -> ScriptResult<()> {
+            let weight = Automated::Weight {
+                source: colour(source)?,
+                target: colour(target)?,
+            };
+            let value = number(value)?;
+            let sandbox = &mut *lock(&shared);
+            weight.set(&mut sandbox.model, &mut sandbox.params, value);
+            sandbox.laws_changed = true;
+            Ok(())
+        },
+    );
+
+    let shared = sandbox.clone();
+    engine.register_fn("colours", move || lock(&shared).params.num_colours as i64);
+
+    let shared = sandbox.clone();
+    engine.register_fn("set_colours", move |colours: i64| -> ScriptResult<()> {
+        if !(1..=NUM_COLOURS as i64).contains(&colours) {
+            return Err(format!("colours must be from 1 to {NUM_COLOURS}").into());
+        }
+
+        let mut sandbox = lock(&shared);
+        sandbox.params.num_colours = colours as usize;
+        sandbox.laws_changed = true;
+        Ok(())
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "spawn",
+        move |x: Dynamic, y: Dynamic, name: &str| -> ScriptResult<()> {
+            let request = Request::Spawn(Vec2::new(number(x)?, number(y)?), colour(name)?);
+            lock(&shared).requests.push(request);
+            Ok(())
+        },
+    );
+
+    for (name, request) in [
+        ("respawn", Request::Respawn),
+        ("clear", Request::Clear),
+        ("randomise", Request::Randomise),
+    ] {
+        let shared = sandbox.clone();
+        engine.register_fn(name, move || lock(&shared).requests.push(request));
+    }
+
+    let shared = sandbox.clone();
+    engine.register_fn("particles", move || {
+        lock(&shared).particles.iter().sum::<usize>() as i64
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn("particles", move |name: &str| -> ScriptResult<i64> {
+        Ok(lock(&shared).particles[colour(name)?.index()] as i64)
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn("organisms", move || lock(&shared).organisms.len() as i64);
+
+    let shared = sandbox.clone();
+    engine.register_fn("largest_organism", move || {
+        lock(&shared)
+            .organisms
+            .first()
+            .map_or(0, |mass| mass.iter().sum::<usize>()) as i64
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn("organism_mass", move |name: &str| -> ScriptResult<i64> {
+        let index = colour(name)?.index();
+        Ok(lock(&shared)
+            .organisms
+            .iter()
+            .map(|mass| mass[index])
+            .sum::<usize>() as i64)
+    });
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "every",
+        move |seconds: Dynamic, function: &str| -> ScriptResult<()> {
+            let every = number(seconds)?;
+            if every <= 0.0 {
+                return Err("timers need a positive number of seconds".into());
+            }
+
+            let mut sandbox = lock(&shared);
+            let next = sandbox.time + every;
+            sandbox.timers.push(ScriptTimer {
+                every,
+                next,
+                function: function.to_string(),
+            });
+            Ok(())
+        },
+    );
+
+    let shared = sandbox.clone();
+    engine.register_fn(
+        "on",
+        move |event: &str, function: &str| -> ScriptResult<()> {
+            let event = event.parse::<ScriptEvent>()?;
+            lock(&shared).handlers.push((event, function.to_string()));
+            Ok(())
+        },
+    );
+
+    engine
+}
+
+/// The running script, along with the engine it runs in.
+#[derive(Resource)]
+pub struct Scripting {
+    engine: Engine,
+    sandbox: Arc<Mutex<Sandbox>>,
+    handle: Option<Handle<Script>>,
+    /// Whether the script's current source has been compiled and its top level run.
+    loaded: bool,
+    ast: Option<AST>,
+    /// Shared between the script's functions, as `this`.
+    this: Dynamic,
+}
+
+impl Default for Scripting {
+    fn default() -> Self {
+        let sandbox = Arc::new(Mutex::new(Sandbox::default()));
+
+        Self {
+            engine: engine(&sandbox),
+            sandbox,
+            handle: None,
+            loaded: false,
+            ast: None,
+            this: Dynamic::from_map(default()),
+        }
+    }
+}
+
+impl Scripting {
+    fn sandbox(&self) -> MutexGuard<'_, Sandbox> {
+        lock(&self.sandbox)
+    }
+
+    /// Compiles `source` and runs its top level, forgetting everything the last script set up.
+    /// A script that doesn't compile leaves nothing running.
+    fn load(&mut self, source: &str) -> Result<(), String> {
+        self.ast = None;
+        self.this = Dynamic::from_map(default());
+
+        {
+            let mut sandbox = self.sandbox();
+            sandbox.timers.clear();
+            sandbox.handlers.clear();
+        }
+
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|error| error.to_string())?;
+
+        let result = self.engine.run_ast(&ast);
+        self.ast = Some(ast);
+
+        result.map_err(|error| error.to_string())
+    }
+
+    fn call(&mut self, function: &str) -> Result<(), String> {
+        let Some(ast) = &self.ast else {
+            return Ok(());
+        };
+
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+
+        self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, function, ())
+            .map(|_| ())
+            .map_err(|error| format!("{function}: {error}"))
+    }
+
+    /// The functions to call now `time` has come, in response to `events`.
+    fn due(&mut self, time: f32, events: &[ScriptEvent]) -> Vec<String> {
+        let mut sandbox = self.sandbox();
+        let mut due = Vec::new();
+
+        for timer in &mut sandbox.timers {
+            if time >= timer.next {
+                due.push(timer.function.clone());
+                timer.next = time + timer.every;
+            } else if time < timer.next - timer.every {
+                // The clock went back, start counting again from there
+                timer.next = time + timer.every;
+            }
+        }
+
+        for event in events {
+            due.extend(
+                sandbox
+                    .handlers
+                    .iter()
+                    .filter(|(handled, _)| handled == event)
+                    .map(|(_, function)| function.clone()),
+            );
+        }
+
+        due
+    }
+}
+
+/// The parts of the world scripts read and change.
+#[derive(SystemParam)]
+struct ScriptWorld<'w, 's> {
+    clock: Res<'w, SimulationClock>,
+    model: ResMut<'w, Model>,
+    params: ResMut<'w, SimulationParams>,
+    particles: Query<'w, 's, &'static ParticleColour, With<Particle>>,
+    clusters: Res<'w, Clusters>,
+    spawn_particles: EventWriter<'w, SpawnParticle>,
+    commands: Commands<'w, 's>,
+}
+
+impl ScriptWorld<'_, '_> {
+    fn fill(&self, sandbox: &mut Sandbox) {
+        sandbox.time = **self.clock;
+        sandbox.model = self.model.clone();
+        sandbox.params = *self.params;
+        sandbox.laws_changed = false;
+
+        sandbox.particles = [0; NUM_COLOURS];
+        self.particles
+            .iter()
+            .for_each(|colour| sandbox.particles[colour.index()] += 1);
+
+        sandbox.organisms = self.clusters.iter().map(|cluster| cluster.mass).collect();
+    }
+
+    fn apply(&mut self, sandbox: &mut Sandbox) {
+        // Only touching the laws when they changed, so nothing watching them redraws for nothing
+        if sandbox.laws_changed {
+            *self.model = sandbox.model.clone();
+            *self.params = sandbox.params;
+        }
+
+        for request in sandbox.requests.drain(..) {
+            match request {
+                Request::Spawn(position, colour) => {
+                    self.spawn_particles
+                        .write(SpawnParticle { position, colour });
+                }
+                Request::Respawn => self.commands.trigger(Respawn),
+                Request::Clear => self.commands.trigger(ClearParticles),
+                Request::Randomise => self.commands.trigger(Randomise),
+            }
+        }
+    }
+}
+
+fn run_script(
+    trigger: Trigger<RunScript>,
+    asset_server: Res<AssetServer>,
+    mut scripting: ResMut<Scripting>,
+) {
+    tracing::info!(path = %trigger.0, "running script");
+
+    scripting.handle = Some(asset_server.load(&trigger.0));
+    scripting.loaded = false;
+}
+
+fn stop_script(_: Trigger<StopScript>, mut scripting: ResMut<Scripting>) {
+    scripting.handle = None;
+    scripting.ast = None;
+}
+
+/// Starts the script named by `ABIOGENESIS_SCRIPT`, a path within the assets.
+fn run_script_from_env(mut commands: Commands) {
+    if let Ok(path) = std::env::var("ABIOGENESIS_SCRIPT") {
+        commands.trigger(RunScript(path));
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn run_scripts(
+    mut scripting: ResMut<Scripting>,
+    scripts: Res<Assets<Script>>,
+    mut script_events: EventReader<AssetEvent<Script>>,
+    mut events: ResMut<ScriptEvents>,
+    mut world: ScriptWorld,
+) {
+    let events = std::mem::take(&mut events.0);
+
+    let Some(handle) = scripting.handle.clone() else {
+        return;
+    };
+
+    // Saving the file reloads it, and its top level runs again from scratch
+    if script_events.read().any(|event| event.is_modified(&handle)) {
+        scripting.loaded = false;
+    }
+
+    let source = scripts
+        .get(&handle)
+        .filter(|_| !scripting.loaded)
+        .map(|script| script.source.clone());
+
+    let time = **world.clock;
+    let due = scripting.due(time, &events);
+
+    if source.is_none() && due.is_empty() {
+        return;
+    }
+
+    world.fill(&mut scripting.sandbox());
+
+    if let Some(source) = source {
+        scripting.loaded = true;
+        if let Err(error) = scripting.load(&source) {
+            tracing::warn!(%error, "script failed to load");
+        }
+    }
+
+    for function in due {
+        if let Err(error) = scripting.call(&function) {
+            tracing::warn!(%error, "script failed");
+        }
+    }
+
+    world.apply(&mut scripting.sandbox());
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Request, ScriptEvent, Scripting};
+    use crate::particles::colour::ParticleColour;
+    use bevy::math::Vec2;
+
+    #[test]
+    fn reads_and_writes_the_laws() {
+        let mut scripting = Scripting::default();
+        scripting
+            .load(
+                r#"
+                set_param("friction", param("friction") + 1);
+                set_weight("red", "Green", -0.5);
+                set_colours(4);
+                "#,
+            )
+            .unwrap();
+
+        let sandbox = scripting.sandbox();
+        assert!(sandbox.laws_changed);
+        assert_eq!(sandbox.params.friction, 3.0);
+        assert_eq!(sandbox.params.num_colours, 4);
+        assert_eq!(
+            sandbox
+                .model
+                .weight(ParticleColour::Red, ParticleColour::Green),
+            -0.5
+        );
+    }
+
+    #[test]
+    fn rejects_what_does_not_exist() {
+        let mut scripting = Scripting::default();
+
+        assert!(scripting.load(r#"set_param("gravity", 1.0);"#).is_err());
+        assert!(
+            scripting
+                .load(r#"set_weight("red", "mauve", 1.0);"#)
+                .is_err()
+        );
+        assert!(scripting.load(r#"on("sunrise", "wake");"#).is_err());
+        assert!(scripting.load("this is not rhai").is_err());
+    }
+
+    #[test]
+    fn runaway_scripts_stop() {
+        let mut scripting = Scripting::default();
+        assert!(scripting.load("loop {}").is_err());
+    }
+
+    #[test]
+    fn asks_for_what_it_cannot_do_itself() {
+        let mut scripting = Scripting::default();
+        scripting
+            .load(r#"spawn(1, 2.5, "aqua"); respawn(); clear(); randomise();"#)
+            .unwrap();
+
+        assert_eq!(
+            scripting.sandbox().requests,
+            vec![
+                Request::Spawn(Vec2::new(1.0, 2.5), ParticleColour::Aqua),
+                Request::Respawn,
+                Request::Clear,
+                Request::Randomise,
+            ]
+        );
+    }
+
+    #[test]
+    fn timers_and_events_call_functions() {
+        let mut scripting = Scripting::default();
+        scripting
+            .load(
+                r#"
+                every(10, "tick");
+                on("respawn", "reset");
+
+                fn tick() {
+                    this.ticks = if "ticks" in this { this.ticks + 1 } else { 1 };
+                    set_param("force_strength", this.ticks * 10);
+                }
+
+                fn reset() {
+                    this.ticks = 0;
+                }
+                "#,
+            )
+            .unwrap();
+
+        assert!(scripting.due(5.0, &[]).is_empty());
+
+        for time in [10.0, 20.0] {
+            for function in scripting.due(time, &[]) {
+                scripting.call(&function).unwrap();
+            }
+        }
+        assert_eq!(scripting.sandbox().params.force_strength, 20.0);
+
+        let due = scripting.due(25.0, &[ScriptEvent::Respawn]);
+        assert_eq!(due, vec!["reset".to_string()]);
+        due.iter()
+            .for_each(|function| scripting.call(function).unwrap());
+
+        for function in scripting.due(30.0, &[]) {
+            scripting.call(&function).unwrap();
+        }
+        assert_eq!(scripting.sandbox().params.force_strength, 10.0);
+
+        // Reloading forgets the old timers and `this`
+        scripting.load("").unwrap();
+        assert!(scripting.due(100.0, &[ScriptEvent::Respawn]).is_empty());
+    }
+}
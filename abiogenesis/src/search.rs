@@ -0,0 +1,391 @@
+//! A batch mode for finding interesting models without clicking Reshape all day, run with
+//! `abiogenesis search`. Candidates are sampled from a seed, run headless and scored, and the best
+//! are written out as presets ready for the Book of Fates.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    args::value,
+    particles::{
+        colour::NUM_COLOURS,
+        generators::random_laws,
+        headless::{HeadlessWorld, Interestingness, measure},
+        model::{Model, Reshape},
+        presets::{Preset, RecommendedView},
+        simulation::SimulationParams,
+        spawner::SpawnerConfig,
+    },
+};
+
+const USAGE: &str = "usage: abiogenesis search [--seed N] [--candidates N] [--seconds N] [--top K] \
+                     [--particles N] [--out DIR] [--book FILE]";
+
+/// The fraction of each run left for the particles to settle before anything is measured.
+const WARM_UP: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchSettings {
+    pub seed: u64,
+    /// How many models to try.
+    pub candidates: usize,
+    /// How long to run each one for, in simulated seconds.
+    pub seconds: f32,
+    /// How many of the best to keep.
+    pub top: usize,
+    pub particles: usize,
+    /// Where to write the presets.
+    pub out: PathBuf,
+    /// A book to add the presets to, the presets have to be somewhere beneath it.
+    pub book: Option<PathBuf>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            candidates: 1000,
+            seconds: 20.0,
+            top: 10,
+            // Fewer than the real thing so thousands of candidates finish in a sensible time
+            particles: 1500,
+            out: PathBuf::from("assets/presets/discovered"),
+            book: None,
+        }
+    }
+}
+
+impl SearchSettings {
+    /// Reads settings from the arguments after `search`, anything left out keeps its default.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut settings = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--seed" => settings.seed = value(&flag, args.next())?,
+                "--candidates" => settings.candidates = value(&flag, args.next())?,
+                "--seconds" => settings.seconds = value(&flag, args.next())?,
+                "--top" => settings.top = value(&flag, args.next())?,
+                "--particles" => settings.particles = value(&flag, args.next())?,
+                "--out" => settings.out = value(&flag, args.next())?,
+                "--book" => settings.book = Some(value(&flag, args.next())?),
+                _ => return Err(format!("unknown argument {flag:?}\n{USAGE}")),
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+/// A model to try, along with how it came about so it can be found again.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub reshape: Reshape,
+    pub model: Model,
+    pub params: SimulationParams,
+}
+
+impl Candidate {
+    /// Any generator but mutation, over anywhere from two colours to all of them.
+    pub fn sample(rng: &mut impl Rng) -> Self {
+        let (reshape, model, params) = random_laws(2..=NUM_COLOURS, rng);
+
+        Candidate {
+            reshape,
+            model,
+            params,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Scored {
+    pub candidate: Candidate,
+    pub interestingness: Interestingness,
+}
+
+impl Scored {
+    fn into_preset(self, rank: usize) -> Preset {
+        let Interestingness {
+            clusters,
+            lifetime,
+            coherence,
+            ..
+        } = self.interestingness;
+
+        let Reshape { generator, seed } = self.candidate.reshape;
+
+        Preset {
+            name: format!("Discovery {seed:016x}"),
+            description: format!(
+                "Turned up by search as number {rank}, scoring {:.2}. Around {clusters:.1} \
+                 organisms at a time, living {lifetime:.1}s on average with a coherence of \
+                 {coherence:.2}. Reshaped by {} from seed {seed}.",
+                self.interestingness.score(),
+                generator.name(),
+            ),
+            author: None,
+            tags: vec!["discovered".to_string(), generator.name().to_lowercase()],
+            view: RecommendedView::default(),
+            thumbnail: None,
+            model: self.candidate.model,
+            params: self.candidate.params,
+            spawner: SpawnerConfig::None,
+        }
+    }
+}
+
+/// Samples and scores every candidate across all the threads there are, best first.
+pub fn search(settings: &SearchSettings) -> Vec<Scored> {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let candidates = (0..settings.candidates)
+        .map(|_| Candidate::sample(&mut rng))
+        .collect::<Vec<_>>();
+
+    let mut scored = in_parallel(
+        &candidates,
+        |candidate| Scored {
+            candidate: candidate.clone(),
+            interestingness: run(candidate, settings),
+        },
+        |done, total| {
+            if done % 100 == 0 {
+                eprintln!("{done}/{total}");
+            }
+        },
+    );
+
+    scored.sort_by(|a, b| {
+        b.interestingness
+            .score()
+            .total_cmp(&a.interestingness.score())
+    });
+
+    scored
+}
+
+/// Maps `f` over `items` across all the threads there are, keeping their order. `progress` hears
+/// how many are done out of how many there are after each one finishes.
+pub fn in_parallel<T: Sync, R: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> R + Sync,
+    progress: impl Fn(usize, usize) + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+
+                    let result = f(item);
+                    results.lock().unwrap().push((index, result));
+
+                    let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    progress(done, items.len());
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn run(candidate: &Candidate, settings: &SearchSettings) -> Interestingness {
+    let mut world = HeadlessWorld::new(
+        settings.particles,
+        candidate.params.num_colours,
+        candidate.reshape.seed,
+    );
+
+    measure(
+        &mut world,
+        &candidate.model,
+        &candidate.params,
+        settings.seconds,
+        settings.seconds * WARM_UP,
+    )
+}
+
+/// Writes the best `settings.top` that scored anything at all as presets, and adds them to the
+/// book if there is one. Returns the paths written.
+pub fn write_presets(
+    settings: &SearchSettings,
+    scored: Vec<Scored>,
+) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(&settings.out).map_err(|error| error.to_string())?;
+
+    let mut paths = Vec::new();
+    for (rank, scored) in scored
+        .into_iter()
+        .filter(|scored| scored.interestingness.score() > 0.0)
+        .take(settings.top)
+        .enumerate()
+    {
+        let path = settings.out.join(format!(
+            "discovery_{:016x}.preset.json",
+            scored.candidate.reshape.seed
+        ));
+
+        let json = scored
+            .into_preset(rank + 1)
+            .to_json()
+            .map_err(|error| error.to_string())?;
+
+        std::fs::write(&path, json).map_err(|error| format!("{}: {error}", path.display()))?;
+        paths.push(path);
+    }
+
+    if let Some(book) = &settings.book {
+        add_to_book(book, &paths)?;
+    }
+
+    Ok(paths)
+}
+
+/// Appends `presets` to the book at `book`, skipping any it already has.
+fn add_to_book(book: &Path, presets: &[PathBuf]) -> Result<(), String> {
+    let directory = book.parent().unwrap_or(Path::new(""));
+
+    let bytes = std::fs::read(book).map_err(|error| format!("{}: {error}", book.display()))?;
+    let mut entries =
+        serde_json::from_slice::<Vec<String>>(&bytes).map_err(|error| error.to_string())?;
+
+    for preset in presets {
+        let entry = preset
+            .strip_prefix(directory)
+            .map_err(|_| format!("{} isn't beneath the book", preset.display()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&entries).map_err(|error| error.to_string())?;
+    std::fs::write(book, json + "\n").map_err(|error| format!("{}: {error}", book.display()))
+}
+
+/// The whole of `abiogenesis search`, given the arguments after `search`.
+pub fn main(args: impl IntoIterator<Item = String>) -> AppExit {
+    let settings = match SearchSettings::from_args(args) {
+        Ok(settings) => settings,
+        Err(error) => {
+            eprintln!("{error}");
+            return AppExit::error();
+        }
+    };
+
+    eprintln!(
+        "searching {} candidates from seed {} for {}s each",
+        settings.candidates, settings.seed, settings.seconds
+    );
+
+    let scored = search(&settings);
+
+    for scored in scored.iter().take(settings.top) {
+        eprintln!(
+            "{:016x} {:<18} {:.3} {:?}",
+            scored.candidate.reshape.seed,
+            scored.candidate.reshape.generator.name(),
+            scored.interestingness.score(),
+            scored.interestingness,
+        );
+    }
+
+    match write_presets(&settings, scored) {
+        Ok(paths) => {
+            paths.iter().for_each(|path| println!("{}", path.display()));
+            AppExit::Success
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            AppExit::error()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::{Candidate, SearchSettings, search};
+    use crate::particles::{colour::NUM_COLOURS, generators::Generator};
+
+    fn args(args: &str) -> Result<SearchSettings, String> {
+        SearchSettings::from_args(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn reads_arguments() {
+        assert_eq!(args(""), Ok(SearchSettings::default()));
+
+        let settings =
+            args("--seed 42 --candidates 5000 --seconds 7.5 --top 3 --out found").unwrap();
+        assert_eq!(settings.seed, 42);
+        assert_eq!(settings.candidates, 5000);
+        assert_eq!(settings.seconds, 7.5);
+        assert_eq!(settings.top, 3);
+        assert_eq!(settings.out, PathBuf::from("found"));
+        assert_eq!(settings.book, None);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(args("--seed").is_err());
+        assert!(args("--seed many").is_err());
+        assert!(args("--speed 10").is_err());
+    }
+
+    #[test]
+    fn candidates_are_reproducible() {
+        for seed in 0..20 {
+            let a = Candidate::sample(&mut StdRng::seed_from_u64(seed));
+            let b = Candidate::sample(&mut StdRng::seed_from_u64(seed));
+
+            assert_eq!(a.reshape, b.reshape);
+            assert_eq!(*a.model, *b.model);
+            assert_ne!(a.reshape.generator, Generator::Mutate);
+            assert!((2..=NUM_COLOURS).contains(&a.params.num_colours));
+        }
+    }
+
+    #[test]
+    fn best_first() {
+        let settings = SearchSettings {
+            candidates: 4,
+            seconds: 1.0,
+            particles: 100,
+            ..SearchSettings::default()
+        };
+
+        let scored = search(&settings);
+
+        assert_eq!(scored.len(), 4);
+        assert!(
+            scored.is_sorted_by(|a, b| { a.interestingness.score() >= b.interestingness.score() })
+        );
+    }
+}
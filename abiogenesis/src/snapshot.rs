@@ -0,0 +1,516 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize, de::Error};
+use serde_json::{Map, Value};
+
+use crate::{
+    camera::{FollowParticle, OrbitCamera},
+    particles::{
+        biomes::BiomeMap,
+        colour::{NUM_COLOURS, ParticleColour},
+        dimensions::Dimensions,
+        model::Model,
+        particle::{Particle, ParticleIndex, Velocity},
+        simulation::SimulationParams,
+        spawner::{ParticleAssets, SpawnerConfig},
+    },
+};
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use std::cell::RefCell;
+
+    use bevy::prelude::*;
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    use crate::snapshot::{LoadSnapshot, SaveSnapshot, WorldSnapshot};
+
+    #[wasm_bindgen]
+    extern "C" {
+        fn wasm_download_snapshot(snapshot: String);
+        fn wasm_upload_snapshot();
+    }
+
+    thread_local! {
+        static PENDING_SNAPSHOT: RefCell<Option<WorldSnapshot>> = RefCell::new(None);
+    }
+
+    #[wasm_bindgen]
+    pub fn import_snapshot_from_js(snapshot: String) {
+        match snapshot.parse::<WorldSnapshot>() {
+            Ok(snapshot) => {
+                PENDING_SNAPSHOT.with_borrow_mut(|cell| {
+                    *cell = Some(snapshot);
+                });
+            }
+            Err(err) => {
+                tracing::warn!(?err, "failed to parse snapshot");
+            }
+        }
+    }
+
+    pub fn async_load(mut commands: Commands) {
+        let snapshot = PENDING_SNAPSHOT.with_borrow_mut(|cell| cell.take());
+
+        if let Some(snapshot) = snapshot {
+            commands.queue(|world: &mut World| snapshot.restore(world));
+        }
+    }
+
+    pub fn load(_trigger: Trigger<LoadSnapshot>) {
+        wasm_upload_snapshot();
+    }
+
+    pub fn save(_trigger: Trigger<SaveSnapshot>, mut commands: Commands) {
+        commands.queue(|world: &mut World| {
+            match serde_json::to_string(&WorldSnapshot::capture(world)) {
+                Ok(snapshot) => wasm_download_snapshot(snapshot),
+                Err(err) => tracing::warn!(?err, "failed to serialize snapshot"),
+            }
+        });
+    }
+}
+
+mod native {
+    use bevy::prelude::*;
+
+    use crate::snapshot::{LoadSnapshot, SaveSnapshot, WorldSnapshot};
+
+    const SNAPSHOT_PATH: &str = "abiogenesis_snapshot.json";
+
+    pub fn load(_trigger: Trigger<LoadSnapshot>, mut commands: Commands) {
+        let snapshot = match std::fs::read_to_string(SNAPSHOT_PATH) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                tracing::warn!(?err, path = SNAPSHOT_PATH, "failed to read snapshot");
+                return;
+            }
+        };
+
+        match snapshot.parse::<WorldSnapshot>() {
+            Ok(snapshot) => commands.queue(|world: &mut World| snapshot.restore(world)),
+            Err(err) => tracing::warn!(?err, "failed to parse snapshot"),
+        }
+    }
+
+    pub fn save(_trigger: Trigger<SaveSnapshot>, mut commands: Commands) {
+        commands.queue(|world: &mut World| {
+            let snapshot = match serde_json::to_string(&WorldSnapshot::capture(world)) {
+                Ok(snapshot) => snapshot,
+                Err(err) => {
+                    tracing::warn!(?err, "failed to serialize snapshot");
+                    return;
+                }
+            };
+
+            match std::fs::write(SNAPSHOT_PATH, snapshot) {
+                Ok(()) => tracing::info!(path = SNAPSHOT_PATH, "saved snapshot"),
+                Err(err) => tracing::warn!(?err, path = SNAPSHOT_PATH, "failed to write snapshot"),
+            }
+        });
+    }
+}
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            apply_pending_zoom.run_if(resource_exists::<PendingZoom>),
+        );
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(PreUpdate, wasm::async_load)
+            .add_observer(wasm::load)
+            .add_observer(wasm::save);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_observer(native::load).add_observer(native::save);
+    }
+}
+
+#[derive(Debug, Default, Event, Copy, Clone)]
+pub struct SaveSnapshot;
+
+#[derive(Debug, Default, Event, Copy, Clone)]
+pub struct LoadSnapshot;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ParticleSnapshot {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub colour: ParticleColour,
+}
+
+/// The version of the [`WorldSnapshot`] format written by this build. Like the shared state, a
+/// change to the format bumps it and adds a migration from the previous version to
+/// [`MIGRATIONS`], so saved snapshots keep loading.
+const SNAPSHOT_VERSION: u64 = 1;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), serde_json::Error>;
+
+/// Each migration upgrades the snapshot from the version at its index to the next.
+const MIGRATIONS: [Migration; SNAPSHOT_VERSION as usize] = [v0_to_v1];
+
+/// Everything needed to recreate a scene exactly, including the particles themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    version: u64,
+    pub params: SimulationParams,
+    pub model: Model,
+    pub spawner: SpawnerConfig,
+    pub dimensions: Dimensions,
+    pub biomes: BiomeMap,
+    /// The orthographic scale in 2D, or the orbit distance in 3D.
+    pub zoom: f32,
+    /// Index into `particles` of the particle the camera is following.
+    pub follow: Option<usize>,
+    pub particles: Vec<ParticleSnapshot>,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let particle_index = world.resource::<ParticleIndex>().0.clone();
+
+        let mut particles =
+            world.query_filtered::<(&Transform, &Velocity, &ParticleColour), With<Particle>>();
+        let particles = particle_index
+            .iter()
+            .filter_map(|entity| particles.get(world, *entity).ok())
+            .map(|(transform, velocity, colour)| ParticleSnapshot {
+                position: transform.translation,
+                velocity: **velocity,
+                colour: *colour,
+            })
+            .collect();
+
+        let follow = world
+            .get_resource::<FollowParticle>()
+            .and_then(|follow| particle_index.iter().position(|entity| *entity == **follow));
+
+        let zoom = world
+            .query::<(&Projection, Option<&OrbitCamera>)>()
+            .iter(world)
+            .find_map(|(projection, orbit)| match (projection, orbit) {
+                (Projection::Orthographic(projection), _) => Some(projection.scale),
+                (_, Some(orbit)) => Some(orbit.distance),
+                _ => None,
+            })
+            .unwrap_or(1.0);
+
+        Self {
+            version: SNAPSHOT_VERSION,
+            params: *world.resource::<SimulationParams>(),
+            model: world.resource::<Model>().clone(),
+            spawner: world.resource::<SpawnerConfig>().clone(),
+            dimensions: *world.resource::<Dimensions>(),
+            biomes: world.resource::<BiomeMap>().clone(),
+            zoom,
+            follow,
+            particles,
+        }
+    }
+
+    pub fn restore(self, world: &mut World) {
+        let mut particles = world.query_filtered::<Entity, With<Particle>>();
+        let existing = particles.iter(world).collect::<Vec<_>>();
+        existing.into_iter().for_each(|entity| {
+            world.despawn(entity);
+        });
+        world.resource_mut::<ParticleIndex>().clear();
+
+        world.insert_resource(self.params);
+        world.insert_resource(self.model);
+        world.insert_resource(self.spawner);
+        world.resource_mut::<BiomeMap>().replace(self.biomes);
+
+        // Only touch the dimensions when they differ, otherwise the camera would be rebuilt for nothing
+        if *world.resource::<Dimensions>() != self.dimensions {
+            world.insert_resource(self.dimensions);
+        }
+
+        let mesh = world.resource::<ParticleAssets>().mesh();
+        let entities = self
+            .particles
+            .iter()
+            .map(|particle| {
+                world
+                    .spawn((
+                        Particle,
+                        Transform::from_translation(particle.position),
+                        Velocity(particle.velocity),
+                        particle.colour,
+                        Mesh2d(mesh.clone()),
+                    ))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+
+        match self.follow.and_then(|index| entities.get(index)) {
+            Some(&entity) => world.insert_resource(FollowParticle(entity)),
+            None => {
+                world.remove_resource::<FollowParticle>();
+            }
+        }
+
+        // The camera may be about to be swapped for the other dimension, so wait for it
+        world.insert_resource(PendingZoom(self.zoom));
+    }
+}
+
+impl FromStr for WorldSnapshot {
+    type Err = serde_json::Error;
+
+    /// Parses a snapshot of any version, migrating it to the current one and fitting its model to
+    /// this build's number of colours.
+    fn from_str(snapshot: &str) -> Result<Self, Self::Err> {
+        let Value::Object(mut snapshot) = serde_json::from_str(snapshot)? else {
+            return Err(serde_json::Error::custom("snapshot is not an object"));
+        };
+
+        // The first version had no version tag at all
+        let version = match snapshot.get("version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| serde_json::Error::custom("version is not an integer"))?,
+        };
+
+        if version > SNAPSHOT_VERSION {
+            return Err(serde_json::Error::custom(format!(
+                "snapshot version {version} is newer than the supported version {SNAPSHOT_VERSION}"
+            )));
+        }
+
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut snapshot)?;
+        }
+
+        let mut snapshot = serde_json::from_value::<WorldSnapshot>(Value::Object(snapshot))?;
+
+        let size = snapshot.model.len().isqrt();
+        if size == 0 || size * size != snapshot.model.len() {
+            return Err(serde_json::Error::custom(format!(
+                "expected a square matrix of weights, found {}",
+                snapshot.model.len()
+            )));
+        }
+
+        snapshot.model = snapshot.model.resized(size);
+        snapshot.params.num_colours = snapshot.params.num_colours.min(NUM_COLOURS);
+
+        Ok(snapshot)
+    }
+}
+
+/// Version 0 had no version tag, and until the spawner was tagged for the shared state it was
+/// written the default way, like `"Uniform"` or `{"Custom": [["Red", {"Circle": {...}}]]}`.
+/// Snapshots saved since are already tagged and left as they are.
+fn v0_to_v1(snapshot: &mut Map<String, Value>) -> Result<(), serde_json::Error> {
+    snapshot.insert("version".into(), 1.into());
+
+    let Some(spawner) = snapshot.get_mut("spawner") else {
+        return Err(serde_json::Error::custom("missing spawner"));
+    };
+
+    let tagged = match spawner.take() {
+        Value::String(kind) => Value::Object(Map::from_iter([(
+            "kind".to_string(),
+            snake_case(&kind).into(),
+        )])),
+        Value::Object(mut custom) if custom.contains_key("Custom") => {
+            let shapes = match custom.remove("Custom") {
+                Some(Value::Array(shapes)) => shapes,
+                _ => return Err(serde_json::Error::custom("custom spawner has no shapes")),
+            };
+
+            let shapes = shapes
+                .into_iter()
+                .map(|entry| match entry {
+                    Value::Array(mut entry) if entry.len() == 2 => {
+                        entry[1] = tag_shape(entry[1].take())?;
+                        Ok(Value::Array(entry))
+                    }
+                    _ => Err(serde_json::Error::custom(
+                        "spawn shape isn't a colour and shape",
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Value::Object(Map::from_iter([
+                ("kind".to_string(), "custom".into()),
+                ("shapes".to_string(), Value::Array(shapes)),
+            ]))
+        }
+        already_tagged => already_tagged,
+    };
+
+    *spawner = tagged;
+    Ok(())
+}
+
+/// `{"Circle": {"radius": 40.0, ...}}` to `{"shape": "circle", "radius": 40.0, ...}`.
+fn tag_shape(shape: Value) -> Result<Value, serde_json::Error> {
+    let Value::Object(shape) = shape else {
+        return Err(serde_json::Error::custom("spawn shape is not an object"));
+    };
+
+    let Some((kind, Value::Object(mut fields))) = shape.into_iter().next() else {
+        return Err(serde_json::Error::custom("spawn shape has no fields"));
+    };
+
+    fields.insert("shape".into(), snake_case(&kind).into());
+    Ok(Value::Object(fields))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+#[derive(Debug, Resource, Clone, Copy)]
+struct PendingZoom(f32);
+
+fn apply_pending_zoom(
+    zoom: Res<PendingZoom>,
+    mut cameras: Query<(&mut Projection, Option<&mut OrbitCamera>)>,
+    mut commands: Commands,
+) {
+    cameras
+        .iter_mut()
+        .for_each(|(mut projection, orbit)| match (&mut *projection, orbit) {
+            (Projection::Orthographic(projection), _) => projection.scale = zoom.0,
+            (_, Some(mut orbit)) => orbit.distance = zoom.0,
+            _ => {}
+        });
+
+    commands.remove_resource::<PendingZoom>();
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::{Rect, Vec2};
+
+    use super::{SNAPSHOT_VERSION, WorldSnapshot};
+    use crate::particles::{
+        colour::{NUM_COLOURS, ParticleColour},
+        spawner::{SpawnShape, SpawnerConfig},
+    };
+
+    const WEIGHTS: &str = "[0.5, -0.25, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.25]";
+
+    fn snapshot(version: Option<u64>, spawner: &str) -> String {
+        snapshot_with(version, spawner, WEIGHTS)
+    }
+
+    fn snapshot_with(version: Option<u64>, spawner: &str, weights: &str) -> String {
+        let version = version.map_or(String::new(), |version| format!(r#""version": {version},"#));
+
+        format!(
+            r#"{{
+                {version}
+                "params": {{
+                    "friction": 2.0, "force_strength": 100.0, "peak_attraction_radius": 50.0,
+                    "repulsion_radius": 25.0, "attraction_radius": 75.0, "decay_rate": 80.0,
+                    "num_colours": 3
+                }},
+                "model": {{"weights": {weights}}},
+                "spawner": {spawner},
+                "dimensions": "Two",
+                "biomes": {{"cells": [], "offset": [0.0, 0.0]}},
+                "zoom": 1.0,
+                "follow": null,
+                "particles": [
+                    {{"position": [1.0, 2.0, 0.0], "velocity": [0.0, 0.0, 0.0], "colour": "Red"}}
+                ]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn untagged_spawners_are_migrated() {
+        let unversioned = snapshot(
+            None,
+            r#"{"Custom": [
+                ["Aqua", {"HollowCircle": {"position": [0.0, 0.0], "inner_radius": 200.0, "outer_radius": 250.0}}],
+                ["Red", {"Rect": {"min": [-100.0, -50.0], "max": [100.0, 50.0]}}]
+            ]}"#,
+        )
+        .parse::<WorldSnapshot>()
+        .unwrap();
+
+        assert_eq!(unversioned.version, SNAPSHOT_VERSION);
+        assert_eq!(
+            unversioned.spawner,
+            SpawnerConfig::Custom(vec![
+                (
+                    ParticleColour::Aqua,
+                    SpawnShape::HollowCircle {
+                        position: Vec2::ZERO,
+                        inner_radius: 200.0,
+                        outer_radius: 250.0,
+                    },
+                ),
+                (
+                    ParticleColour::Red,
+                    SpawnShape::Rect(Rect::new(-100.0, -50.0, 100.0, 50.0)),
+                ),
+            ])
+        );
+
+        let uniform = snapshot(None, r#""Uniform""#)
+            .parse::<WorldSnapshot>()
+            .unwrap();
+        assert_eq!(uniform.spawner, SpawnerConfig::Uniform);
+    }
+
+    #[test]
+    fn tagged_spawners_load_with_or_without_a_version() {
+        for version in [None, Some(SNAPSHOT_VERSION)] {
+            let snapshot = snapshot(version, r#"{"kind": "none"}"#)
+                .parse::<WorldSnapshot>()
+                .unwrap();
+
+            assert_eq!(snapshot.spawner, SpawnerConfig::None);
+            assert_eq!(snapshot.particles.len(), 1);
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let newer = snapshot(Some(SNAPSHOT_VERSION + 1), r#"{"kind": "none"}"#);
+        assert!(newer.parse::<WorldSnapshot>().is_err());
+    }
+
+    #[test]
+    fn models_are_fitted_to_the_colours() {
+        let snapshot = snapshot(Some(SNAPSHOT_VERSION), r#"{"kind": "none"}"#)
+            .parse::<WorldSnapshot>()
+            .unwrap();
+
+        assert_eq!(snapshot.model.len(), NUM_COLOURS * NUM_COLOURS);
+        assert_eq!(
+            snapshot
+                .model
+                .weight(ParticleColour::from_index(2), ParticleColour::from_index(0)),
+            -1.0
+        );
+    }
+
+    #[test]
+    fn malformed_models_are_refused() {
+        for weights in ["[]", "[0.5, 0.5, 0.5]"] {
+            let snapshot = snapshot_with(Some(SNAPSHOT_VERSION), r#"{"kind": "none"}"#, weights);
+            assert!(snapshot.parse::<WorldSnapshot>().is_err(), "{weights}");
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use bevy::math::{Rect, Vec2};
+use bevy::math::{IVec3, Rect, Vec2, Vec3, primitives::Cuboid};
 use itertools::Itertools;
 
 #[derive(Debug, Clone)]
@@ -111,6 +111,118 @@ impl<T> SpatialHashGrid<T> {
     }
 }
 
+/// The 3D counterpart of [`SpatialHashGrid`], bucketing a toroidal volume centered on the origin.
+#[derive(Debug, Clone)]
+pub struct SpatialHashGrid3d<T> {
+    pub cells: Vec<Vec<(Vec3, T)>>,
+    pub bounds: Cuboid,
+    pub cell_count: (usize, usize, usize),
+    pub cell_size: Vec3,
+}
+
+impl<T> SpatialHashGrid3d<T> {
+    pub fn new(bounds: Cuboid, (x, y, z): (usize, usize, usize)) -> Self {
+        let mut cells = Vec::with_capacity(x * y * z);
+        cells.resize_with(x * y * z, Vec::new);
+
+        SpatialHashGrid3d {
+            cells,
+            bounds,
+            cell_count: (x, y, z),
+            cell_size: 2.0 * bounds.half_size / Vec3::new(x as f32, y as f32, z as f32),
+        }
+    }
+
+    pub fn update_bounds(&mut self, bounds: Cuboid) {
+        let (x, y, z) = self.cell_count;
+
+        self.bounds = bounds;
+        self.cell_size = 2.0 * bounds.half_size / Vec3::new(x as f32, y as f32, z as f32);
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, pos: Vec3, item: T) {
+        let grid_pos = self.wrap_coordinates(self.world_to_grid(pos));
+
+        let index = self.grid_to_index(grid_pos);
+        if let Some(cell) = self.cells.get_mut(index) {
+            cell.push((pos, item));
+        }
+    }
+
+    pub fn query(&self, pos: Vec3, radius: f32) -> impl Iterator<Item = (Vec3, &T)> {
+        self.get_query_cells(pos, radius)
+            .map(|cell_index| self.grid_to_index(cell_index))
+            .flat_map(|index| self.cells[index].iter())
+            .filter_map(move |(item_pos, item)| {
+                if self.toroidal_distance(*item_pos, pos) <= radius {
+                    Some((*item_pos, item))
+                } else {
+                    None
+                }
+            })
+    }
+
+    pub fn toroidal_distance(&self, a: Vec3, b: Vec3) -> f32 {
+        let size = 2.0 * self.bounds.half_size;
+        let delta = (a - b).abs();
+
+        delta.min(size - delta).length()
+    }
+
+    pub fn world_to_grid(&self, pos: Vec3) -> (i32, i32, i32) {
+        let grid = ((pos + self.bounds.half_size) / self.cell_size).floor();
+
+        (grid.x as i32, grid.y as i32, grid.z as i32)
+    }
+
+    pub fn wrap_coordinates(&self, (x, y, z): (i32, i32, i32)) -> (i32, i32, i32) {
+        let (width, height, depth) = (
+            self.cell_count.0 as i32,
+            self.cell_count.1 as i32,
+            self.cell_count.2 as i32,
+        );
+
+        (
+            x.rem_euclid(width),
+            y.rem_euclid(height),
+            z.rem_euclid(depth),
+        )
+    }
+
+    pub fn grid_to_index(&self, (x, y, z): (i32, i32, i32)) -> usize {
+        ((z * self.cell_count.1 as i32 + y) * self.cell_count.0 as i32 + x) as usize
+    }
+
+    pub fn get_query_cells(&self, pos: Vec3, radius: f32) -> impl Iterator<Item = (i32, i32, i32)> {
+        let (grid_x, grid_y, grid_z) = self.world_to_grid(pos);
+
+        gen move {
+            let radius = (radius / self.cell_size).ceil().as_ivec3();
+
+            // Wide queries are capped at the grid size so no cell is visited twice
+            let span = (2 * radius + 1).min(IVec3::new(
+                self.cell_count.0 as i32,
+                self.cell_count.1 as i32,
+                self.cell_count.2 as i32,
+            ));
+
+            for x in (grid_x - radius.x)..(grid_x - radius.x + span.x) {
+                for y in (grid_y - radius.y)..(grid_y - radius.y + span.y) {
+                    for z in (grid_z - radius.z)..(grid_z - radius.z + span.z) {
+                        yield self.wrap_coordinates((x, y, z));
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bevy::math::Rect;
@@ -121,7 +233,7 @@ mod tests {
         let _ = bevy::log::tracing_subscriber::fmt().try_init();
     }
 
-    fn just_values<T>(iter: impl Iterator<Item = (Vec2, T)>) -> Vec<T> {
+    fn just_values<P, T>(iter: impl Iterator<Item = (P, T)>) -> Vec<T> {
         iter.map(|(_, value)| value).collect()
     }
 
@@ -326,4 +438,61 @@ mod tests {
             vec![&4, &1, &2, &3,]
         );
     }
+
+    #[test]
+    fn test_3d_insert_and_query_basic() {
+        let mut grid = SpatialHashGrid3d::<i32>::new(Cuboid::from_length(100.0), (10, 10, 10));
+
+        grid.insert(Vec3::ZERO, 42);
+
+        let results: Vec<&i32> = just_values(grid.query(Vec3::ZERO, 10.0));
+        assert_eq!(results, vec![&42]);
+    }
+
+    #[test]
+    fn test_3d_grid_to_index_conversion() {
+        let grid = SpatialHashGrid3d::<i32>::new(Cuboid::from_length(100.0), (10, 10, 10));
+
+        assert_eq!(grid.grid_to_index((0, 0, 0)), 0);
+        assert_eq!(grid.grid_to_index((1, 0, 0)), 1);
+        assert_eq!(grid.grid_to_index((0, 1, 0)), 10);
+        assert_eq!(grid.grid_to_index((0, 0, 1)), 100);
+        assert_eq!(grid.grid_to_index((9, 9, 9)), 999);
+    }
+
+    #[test]
+    fn test_3d_query_within_radius() {
+        let mut grid = SpatialHashGrid3d::new(Cuboid::from_length(100.0), (10, 10, 10));
+
+        grid.insert(Vec3::ZERO, 1);
+        grid.insert(Vec3::new(0.0, 0.0, 10.0), 2); // Within radius
+        grid.insert(Vec3::new(0.0, 0.0, 20.0), 3); // Outside radius
+
+        let results: Vec<&i32> = just_values(grid.query(Vec3::ZERO, 15.0));
+        assert!(results.contains(&&1));
+        assert!(results.contains(&&2));
+        assert!(!results.contains(&&3));
+    }
+
+    #[test]
+    fn test_3d_toroidal_wrapping_depth() {
+        let mut grid = SpatialHashGrid3d::new(Cuboid::from_length(100.0), (10, 10, 10));
+
+        // Insert item at the far edge of the volume
+        grid.insert(Vec3::new(0.0, 0.0, 45.0), 1);
+
+        // Query from the near edge - should find the item due to wrapping
+        let results: Vec<&i32> = just_values(grid.query(Vec3::new(0.0, 0.0, -45.0), 20.0));
+        assert_eq!(results, vec![&1]);
+    }
+
+    #[test]
+    fn test_3d_toroidal_wrapping_corner() {
+        let mut grid = SpatialHashGrid3d::new(Cuboid::from_length(100.0), (10, 10, 10));
+
+        grid.insert(Vec3::splat(45.0), 1);
+
+        let results: Vec<&i32> = just_values(grid.query(Vec3::splat(-45.0), 20.0));
+        assert_eq!(results, vec![&1]);
+    }
 }
@@ -3,39 +3,55 @@ use std::time::Duration;
 use bevy::{prelude::*, window::WindowResized};
 use bevy_tweening::{Animator, Tween};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::particles::recording::{ToggleRecording, ToggleReplay};
 use crate::{
     browser_state::{Export, Import},
     controls,
     observe::observe,
     particles::{
+        dimensions::ToggleDimensions,
         model::{ClearParticles, Randomise},
+        organisms::ToggleOrganisms,
         simulation::SimulationParams,
         spawner::Respawn,
     },
+    scenes::{ChangeScene, Scene, Shaping},
+    snapshot::{LoadSnapshot, SaveSnapshot},
     systems::AppSystems,
     ui::{
+        automation::AutomationUIPlugin,
         button::control_button,
-        examples::examples,
+        challenges::{ChallengesUIPlugin, challenges},
+        examples::{ExamplesPlugin, examples},
+        gallery::GalleryPlugin,
         lenses::{LeftLens, LensPlugin},
-        menu_button::{hide_ui, show_ui_button},
+        menu_button::{StartWithUiHidden, hide, hide_on_start, hide_ui, show_ui_button},
         model_matrix::{update_matrix_size, update_model_matrix},
         parameters::parameters,
+        playback::{PlaybackBarPlugin, playback_bar},
+        registry::{RegistryPlugin, organism_registry},
         title_screen::TitleScreenPlugin,
         toolbar::ToolBarPlugin,
     },
 };
 
+mod automation;
 mod button;
 mod challenges;
 mod colours;
+mod context_menu;
 mod dropdown;
 mod examples;
+mod gallery;
 mod icon;
 mod lenses;
-mod menu_button;
+pub mod menu_button;
 mod mixins;
 mod model_matrix;
 mod parameters;
+mod playback;
+mod registry;
 mod slider;
 mod title_screen;
 pub mod toolbar;
@@ -45,8 +61,23 @@ pub struct UIPlugin;
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ToolBarPlugin)
+            .add_plugins(PlaybackBarPlugin)
+            .add_plugins(RegistryPlugin)
+            .add_plugins(ChallengesUIPlugin)
             .add_plugins(LensPlugin)
             .add_plugins(TitleScreenPlugin)
+            .add_plugins(GalleryPlugin)
+            .add_plugins(ExamplesPlugin)
+            .add_plugins(AutomationUIPlugin)
+            .add_observer(hide)
+            .add_systems(
+                OnEnter(Shaping),
+                (
+                    respawn_ui,
+                    hide_on_start.run_if(resource_exists::<StartWithUiHidden>),
+                )
+                    .chain(),
+            )
             .add_systems(Update, update_model_matrix.in_set(AppSystems::Update))
             .add_systems(Update, update_matrix_size.in_set(AppSystems::Update))
             .add_systems(PreUpdate, calculate_ui_scale);
@@ -108,7 +139,9 @@ pub fn respawn_ui(
                     },
                     children![
                         examples(),
+                        challenges(),
                         parameters(params.num_colours),
+                        organism_registry(),
                         (
                             Node {
                                 width: Val::Percent(100.0),
@@ -118,27 +151,82 @@ pub fn respawn_ui(
                                 row_gap: Val::Px(8.0),
                                 ..default()
                             },
-                            children![
-                                hide_ui(),
-                                control_button("Receive", Import, icons.load("icons/import.png")),
-                                control_button("Bestow", Export, icons.load("icons/export.png")),
-                                control_button(
-                                    "Annihilate",
-                                    ClearParticles,
-                                    icons.load("icons/nuclear-explosion.png")
+                            // More buttons than a single children! list can hold, so split them
+                            Children::spawn((
+                                (
+                                    Spawn(hide_ui()),
+                                    Spawn(control_button(
+                                        "Receive",
+                                        Import,
+                                        icons.load("icons/import.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Bestow",
+                                        Export,
+                                        icons.load("icons/export.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Annihilate",
+                                        ClearParticles,
+                                        icons.load("icons/nuclear-explosion.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Regenerate",
+                                        Respawn,
+                                        icons.load("icons/plant.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Reshape",
+                                        Randomise,
+                                        icons.load("icons/dice.png")
+                                    )),
                                 ),
-                                control_button(
-                                    "Regenerate",
-                                    Respawn,
-                                    icons.load("icons/plant.png")
+                                (
+                                    Spawn(control_button(
+                                        "Preserve",
+                                        SaveSnapshot,
+                                        icons.load("icons/amber.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Resurrect",
+                                        LoadSnapshot,
+                                        icons.load("icons/ankh.png")
+                                    )),
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    Spawn(control_button(
+                                        "Inscribe",
+                                        ToggleRecording,
+                                        icons.load("icons/quill.png")
+                                    )),
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    Spawn(control_button(
+                                        "Recount",
+                                        ToggleReplay,
+                                        icons.load("icons/scroll.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Discern",
+                                        ToggleOrganisms,
+                                        icons.load("icons/discern.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Contemplate",
+                                        ChangeScene(Scene::Gallery),
+                                        icons.load("icons/gallery.png")
+                                    )),
+                                    Spawn(control_button(
+                                        "Transcend",
+                                        ToggleDimensions,
+                                        icons.load("icons/cube.png")
+                                    )),
                                 ),
-                                control_button("Reshape", Randomise, icons.load("icons/dice.png")),
-                            ],
+                            )),
                         ),
                     ]
                 )]
             ),
-            toolbar::toolbar()
+            toolbar::toolbar(),
+            playback_bar(),
         ],
     ));
 }
@@ -146,6 +234,7 @@ pub fn respawn_ui(
 fn full_screen_container() -> impl Bundle {
     (
         UIRoot,
+        StateScoped(Shaping),
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
@@ -156,11 +245,17 @@ fn full_screen_container() -> impl Bundle {
         },
         observe(controls::drag_screen),
         observe(controls::scroll_wheel_zoom),
+        observe(controls::orbit_camera_drag),
+        observe(controls::orbit_camera_zoom),
         observe(controls::select_follow_particle),
-        observe(controls::particle_brush_start),
-        observe(controls::particle_brush_drag),
-        observe(controls::eraser_brush_start),
-        observe(controls::eraser_brush_drag),
+        (
+            observe(controls::particle_brush_start),
+            observe(controls::particle_brush_drag),
+            observe(controls::eraser_brush_start),
+            observe(controls::eraser_brush_drag),
+            observe(controls::terraform_brush_start),
+            observe(controls::terraform_brush_drag),
+        ),
         observe(toolbar::smite_start_hover),
         observe(toolbar::smite_hover),
         observe(toolbar::smite_end_hover),
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{
+    observe::observe,
+    particles::automation::{Automated, AutomationEdit, Automations, Curve, EditAutomation, Wave},
+    ui::context_menu::{ContextMenu, column, context_menu, item},
+};
+
+pub struct AutomationUIPlugin;
+
+impl Plugin for AutomationUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            show_automations.run_if(
+                resource_changed::<Automations>.or(any_match_filter::<Added<AutomationIndicator>>),
+            ),
+        );
+    }
+}
+
+const AUTOMATED_COLOUR: Color = Color::Srgba(Srgba::rgb(0.45, 0.8, 1.0));
+
+/// Outlines a control while what it changes is automated.
+#[derive(Debug, Component, Clone, Copy)]
+#[require(Outline)]
+pub struct AutomationIndicator(pub Automated);
+
+/// Lets `target` be automated from the control's context menu, outlining it while it is.
+pub fn automatable(target: Automated) -> impl Bundle {
+    (AutomationIndicator(target), observe(open_automation_menu))
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn open_automation_menu(
+    mut trigger: Trigger<Pointer<Click>>,
+    indicators: Query<&AutomationIndicator>,
+    automations: Res<Automations>,
+    menus: Query<Entity, With<ContextMenu>>,
+    ui_scale: Res<UiScale>,
+    mut commands: Commands,
+) {
+    if !matches!(trigger.button, PointerButton::Secondary) {
+        return;
+    }
+
+    let Ok(AutomationIndicator(target)) = indicators.get(trigger.target()) else {
+        return;
+    };
+
+    trigger.propagate(false);
+
+    menus
+        .iter()
+        .for_each(|menu| commands.entity(menu).despawn());
+
+    commands.spawn(context_menu(
+        "Automation Context Menu",
+        trigger.pointer_location.position / **ui_scale,
+        Spawn(automate_column(*target, &automations)),
+    ));
+}
+
+/// The ways `target` can be automated, given how it's automated already.
+pub fn automate_column(target: Automated, automations: &Automations) -> impl Bundle {
+    let edit = |edit| EditAutomation { target, edit };
+    let curve = automations.get(target);
+
+    let mut items = Wave::ALL
+        .into_iter()
+        .map(|wave| {
+            let selected =
+                matches!(curve, Some(Curve::Oscillator { wave: current, .. }) if *current == wave);
+            item(
+                wave.name().to_string(),
+                edit(AutomationEdit::Oscillate(wave)),
+                selected,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(Curve::Oscillator { period, .. }) = curve {
+        items.extend([
+            item(
+                format!("Faster ({period:.0}s)"),
+                edit(AutomationEdit::ScalePeriod(0.5)),
+                false,
+            ),
+            item(
+                "Slower".to_string(),
+                edit(AutomationEdit::ScalePeriod(2.0)),
+                false,
+            ),
+            item(
+                "Deeper".to_string(),
+                edit(AutomationEdit::ScaleDepth(1.5)),
+                false,
+            ),
+            item(
+                "Shallower".to_string(),
+                edit(AutomationEdit::ScaleDepth(1.0 / 1.5)),
+                false,
+            ),
+        ]);
+    }
+
+    let (keyframes, period) = match curve {
+        Some(Curve::Keyframes { keyframes, period }) => (keyframes.len(), *period),
+        _ => (0, None),
+    };
+
+    items.push(item(
+        format!("Keyframe now ({keyframes} so far)"),
+        edit(AutomationEdit::AddKeyframe),
+        keyframes > 0,
+    ));
+
+    if keyframes > 1 {
+        items.push(item(
+            "Loop".to_string(),
+            edit(AutomationEdit::ToggleLoop),
+            period.is_some(),
+        ));
+    }
+
+    if let Some(period) = period {
+        items.extend([
+            item(
+                format!("Loop faster ({period:.0}s)"),
+                edit(AutomationEdit::ScalePeriod(0.5)),
+                false,
+            ),
+            item(
+                "Loop slower".to_string(),
+                edit(AutomationEdit::ScalePeriod(2.0)),
+                false,
+            ),
+        ]);
+    }
+
+    if curve.is_some() {
+        items.push(item(
+            "Stop".to_string(),
+            edit(AutomationEdit::Remove),
+            false,
+        ));
+    }
+
+    column(format!("Automate {}", target.name()), items)
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn show_automations(
+    automations: Res<Automations>,
+    mut indicators: Query<(&AutomationIndicator, &mut Outline)>,
+) {
+    for (AutomationIndicator(target), mut outline) in &mut indicators {
+        *outline = if automations.get(*target).is_some() {
+            Outline::new(Val::Px(2.0), Val::Px(1.0), AUTOMATED_COLOUR)
+        } else {
+            Outline::default()
+        };
+    }
+}
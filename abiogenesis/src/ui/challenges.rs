@@ -1,73 +1,248 @@
 use bevy::{ecs::spawn::SpawnIter, prelude::*};
 
 use crate::{
-    observe::observe,
-    particles::{
-        model::{Model, NUM_PRESETS, PRESETS},
-        simulation::SimulationParams,
+    challenges::{
+        AbandonChallenge, ActiveChallenge, CHALLENGES, ChallengeRecord, NUM_CHALLENGES,
+        StartChallenge,
     },
+    observe::observe,
+    scenes::Scene,
     ui::{
-        colours::UI_BACKGROUND_FOCUSED,
+        colours::{UI_BACKGROUND, UI_BACKGROUND_FOCUSED},
         dropdown::{ToggleState, dropdown},
         icon::Icon,
         mixins,
     },
 };
 
+pub struct ChallengesUIPlugin;
+
+impl Plugin for ChallengesUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_challenge_entries.run_if(
+                resource_changed::<ChallengeRecord>.or(any_match_filter::<Added<ChallengeEntry>>),
+            ),
+        )
+        .add_systems(
+            Update,
+            update_banner
+                .run_if(in_state(Scene::Challenge).and(resource_changed::<ActiveChallenge>)),
+        );
+    }
+}
+
 const VERTICAL_PADDING: f32 = 4.0;
 const FONT_SIZE: f32 = 24.0;
 const TOTAL_HEIGHT: f32 =
-    (NUM_PRESETS as f32) * FONT_SIZE + 2.0 * VERTICAL_PADDING * NUM_PRESETS as f32;
+    (NUM_CHALLENGES as f32) * FONT_SIZE + 2.0 * VERTICAL_PADDING * NUM_CHALLENGES as f32;
+
+const COMPLETE_COLOUR: Color = Color::srgb(1.0, 0.85, 0.4);
 
-// pub fn challenges() -> impl Bundle {
-//     dropdown(
-//         Icon("icons/open-book.png"),
-//         "Tasks of Genesis",
-//         TOTAL_HEIGHT,
-//         contents(),
-//     )
-// }
+pub fn challenges() -> impl Bundle {
+    dropdown(
+        Icon("icons/star.png"),
+        "Tasks of Genesis",
+        TOTAL_HEIGHT,
+        contents(),
+    )
+}
 
 const DROPDOWN_PADDING: f32 = 8.0;
 
-// fn contents() -> impl Bundle {
-//     (
-//         Node {
-//             flex_direction: FlexDirection::Column,
-//             align_items: AlignItems::Start,
-//             justify_content: JustifyContent::Start,
-//             width: Val::Percent(100.0),
-//             padding: UiRect::all(Val::Px(DROPDOWN_PADDING)),
-//             ..default()
-//         },
-//         Children::spawn(SpawnIter(PRESETS.iter().map(|(name, model, params)| {
-//             (
-//                 Node {
-//                     padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
-//                     align_items: AlignItems::Center,
-//                     width: Val::Percent(100.0),
-//                     ..default()
-//                 },
-//                 BorderRadius::all(Val::Px(4.0)),
-//                 mixins::hover_colour(Color::NONE, UI_BACKGROUND_FOCUSED),
-//                 children![(Text::from(*name), Pickable::IGNORE)],
-//                 observe({
-//                     let model = model.clone();
-
-//                     move |mut trigger: Trigger<Pointer<Click>>,
-//                           mut commands: Commands,
-//                           mut res_model: ResMut<Model>,
-//                           mut res_params: ResMut<SimulationParams>| {
-//                         trigger.propagate(false);
-
-//                         // This will bubble up to the dropdown, which will close itself
-//                         commands.trigger_targets(ToggleState, trigger.target());
-
-//                         *res_model = model.clone();
-//                         *res_params = *params;
-//                     }
-//                 }),
-//             )
-//         }))),
-//     )
-// }
+#[derive(Debug, Component, Clone, Copy)]
+struct ChallengeEntry(usize);
+
+fn contents() -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            justify_content: JustifyContent::Start,
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(DROPDOWN_PADDING)),
+            ..default()
+        },
+        Children::spawn(SpawnIter(CHALLENGES.iter().enumerate().map(
+            |(index, challenge)| {
+                (
+                    Node {
+                        padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
+                        align_items: AlignItems::Center,
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(4.0)),
+                    mixins::hover_colour(Color::NONE, UI_BACKGROUND_FOCUSED),
+                    mixins::tooltip(challenge.goal.describe()),
+                    children![(
+                        ChallengeEntry(index),
+                        Text::from(challenge.name),
+                        Pickable::IGNORE
+                    )],
+                    observe(
+                        move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                            trigger.propagate(false);
+
+                            // This will bubble up to the dropdown, which will close itself
+                            commands.trigger_targets(ToggleState, trigger.target());
+                            commands.trigger(StartChallenge(index));
+                        },
+                    ),
+                )
+            },
+        ))),
+    )
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_challenge_entries(
+    record: Res<ChallengeRecord>,
+    mut entries: Query<(&ChallengeEntry, &mut TextColor)>,
+) {
+    entries.iter_mut().for_each(|(entry, mut colour)| {
+        colour.0 = if record.completed.contains(CHALLENGES[entry.0].name) {
+            COMPLETE_COLOUR
+        } else {
+            Color::WHITE
+        };
+    });
+}
+
+#[derive(Debug, Component, Clone, Copy)]
+struct ChallengeBanner(usize);
+
+#[derive(Debug, Component)]
+struct ProgressFill;
+
+#[derive(Debug, Component)]
+struct StatusText;
+
+const BANNER_WIDTH: f32 = 360.0;
+
+fn banner(index: usize) -> impl Bundle {
+    let challenge = &CHALLENGES[index];
+
+    let locked = challenge
+        .locked
+        .iter()
+        .map(|law| law.name())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    (
+        ChallengeBanner(index),
+        StateScoped(Scene::Challenge),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.0),
+            left: Val::Percent(50.0),
+            width: Val::Px(BANNER_WIDTH),
+            margin: UiRect::left(Val::Px(-BANNER_WIDTH / 2.0)),
+            padding: UiRect::all(Val::Px(12.0)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(16.0)),
+        mixins::hover_colour(UI_BACKGROUND, UI_BACKGROUND_FOCUSED),
+        mixins::tooltip("Forsake"),
+        observe(
+            |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.trigger(AbandonChallenge);
+            },
+        ),
+        children![
+            (
+                Text::new(challenge.name),
+                TextFont::from_font_size(20.0),
+                Pickable::IGNORE,
+            ),
+            (
+                Text::new(challenge.goal.describe()),
+                TextFont::from_font_size(14.0),
+                Pickable::IGNORE,
+            ),
+            (
+                Text::new(format!("Immutable: {locked}")),
+                TextFont::from_font_size(12.0),
+                TextColor(Color::WHITE.with_alpha(0.6)),
+                Pickable::IGNORE,
+            ),
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(6.0),
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(8.0)),
+                BackgroundColor(Color::WHITE.with_alpha(0.2)),
+                Pickable::IGNORE,
+                children![(
+                    ProgressFill,
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    BackgroundColor(Color::WHITE.with_alpha(0.8)),
+                    Pickable::IGNORE,
+                )],
+            ),
+            (
+                StatusText,
+                Text::new(""),
+                TextFont::from_font_size(14.0),
+                Pickable::IGNORE,
+            ),
+        ],
+    )
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_banner(
+    active: Res<ActiveChallenge>,
+    banners: Query<(Entity, &ChallengeBanner)>,
+    mut fills: Query<(&mut Node, &mut BackgroundColor), With<ProgressFill>>,
+    mut status: Query<(&mut Text, &mut TextColor), With<StatusText>>,
+    mut commands: Commands,
+) {
+    // Starting another challenge replaces the banner rather than updating it
+    for (entity, banner) in &banners {
+        if active.index != banner.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if banners.iter().all(|(_, banner)| banner.0 != active.index) {
+        commands.spawn(banner(active.index));
+        return;
+    }
+
+    let colour = if active.complete {
+        COMPLETE_COLOUR
+    } else {
+        Color::WHITE.with_alpha(0.8)
+    };
+
+    fills.iter_mut().for_each(|(mut node, mut background)| {
+        node.width = Val::Percent(active.progress() * 100.0);
+        background.0 = colour;
+    });
+
+    status.iter_mut().for_each(|(mut text, mut text_colour)| {
+        text.0 = if active.complete {
+            "Fulfilled".to_string()
+        } else {
+            format!(
+                "{:.0}s of {:.0}s",
+                active.held,
+                active.challenge().goal.seconds()
+            )
+        };
+        text_colour.0 = colour;
+    });
+}
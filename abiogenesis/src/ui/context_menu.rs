@@ -0,0 +1,103 @@
+use bevy::{
+    ecs::spawn::{SpawnIter, SpawnableList},
+    prelude::*,
+};
+
+use crate::{
+    observe::observe,
+    scenes::Shaping,
+    ui::{colours::UI_BACKGROUND_FOCUSED, mixins},
+};
+
+#[derive(Debug, Component)]
+pub struct ContextMenu;
+
+const MENU_WIDTH: f32 = 200.0;
+
+/// A menu of `columns` at `position`, replacing any menu already open. It covers the screen so
+/// clicking anywhere, including on an item, closes it.
+pub fn context_menu<L: SpawnableList<ChildOf> + Send + Sync + 'static>(
+    name: &'static str,
+    position: Vec2,
+    columns: L,
+) -> impl Bundle {
+    (
+        ContextMenu,
+        Name::from(name),
+        StateScoped(Shaping),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        GlobalZIndex(90),
+        observe(|trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+            commands.entity(trigger.target()).despawn();
+        }),
+        children![(
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(position.y),
+                left: Val::Px(position.x),
+                column_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.8)),
+            BorderRadius::all(Val::Px(8.0)),
+            Children::spawn(columns),
+        )],
+    )
+}
+
+pub fn column<B: Bundle>(title: impl Into<String>, items: Vec<B>) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(MENU_WIDTH),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        Children::spawn((
+            Spawn((
+                Node {
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                    ..default()
+                },
+                Text::new(title),
+                TextFont::from_font_size(14.0),
+                TextColor(Color::WHITE.with_alpha(0.6)),
+                Pickable::IGNORE,
+            )),
+            SpawnIter(items.into_iter()),
+        )),
+    )
+}
+
+pub fn item(label: String, event: impl Event + Clone, selected: bool) -> impl Bundle {
+    let colour = if selected {
+        UI_BACKGROUND_FOCUSED
+    } else {
+        Color::NONE
+    };
+
+    (
+        Node {
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(4.0)),
+        mixins::hover_colour(colour, UI_BACKGROUND_FOCUSED),
+        children![(
+            Text::new(label),
+            TextFont::from_font_size(18.0),
+            Pickable::IGNORE,
+        )],
+        observe(
+            move |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                commands.trigger(event.clone());
+            },
+        ),
+    )
+}
@@ -52,6 +52,7 @@ pub fn dropdown(
         BackgroundColor(UI_BACKGROUND),
         children![header(icon, title), contents],
         observe(toggle_state),
+        observe(resize_dropdown),
     )
 }
 
@@ -125,6 +126,27 @@ fn header(icon: Icon, title: &'static str) -> impl Bundle {
 #[event(auto_propagate, traversal = &'static ChildOf)]
 pub struct ToggleState;
 
+/// Triggered on a dropdown, or anything inside it, when the height of its contents changes.
+#[derive(Debug, Event, Clone, Copy)]
+#[event(auto_propagate, traversal = &'static ChildOf)]
+pub struct ResizeDropdown(pub f32);
+
+fn resize_dropdown(
+    mut trigger: Trigger<ResizeDropdown>,
+    mut dropdowns: Query<(&mut Dropdown, &DropdownState, &mut Node)>,
+) {
+    let Ok((mut dropdown, dropdown_state, mut node)) = dropdowns.get_mut(trigger.target()) else {
+        return;
+    };
+
+    trigger.propagate(false);
+    dropdown.content_height = trigger.0;
+
+    if *dropdown_state == DropdownState::Open {
+        node.height = Val::Px(HEADER_HEIGHT + HEADER_PADDING * 4.0 + dropdown.content_height);
+    }
+}
+
 fn toggle_state(
     trigger: Trigger<ToggleState>,
     mut commands: Commands,
@@ -1,36 +1,141 @@
-use bevy::{ecs::spawn::SpawnIter, prelude::*};
+use std::collections::BTreeSet;
+
+use bevy::{
+    ecs::spawn::SpawnIter,
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
 
 use crate::{
+    library::{
+        DeletePreset, DismissReceived, Library, LoadPreset, MovePreset, ReceivedState,
+        RenamePreset, SavePreset, SaveReceived,
+    },
     observe::observe,
     particles::{
-        model::{Model, NUM_PRESETS, PRESETS},
-        simulation::SimulationParams,
-        spawner::{Respawn, SpawnerConfig},
+        morph::MorphSettings,
+        presets::{ApplyPreset, Preset, PresetBook, Presets},
     },
+    scenes::Shaping,
     ui::{
-        colours::UI_BACKGROUND_FOCUSED,
-        dropdown::{ToggleState, dropdown},
+        colours::{UI_BACKGROUND, UI_BACKGROUND_FOCUSED},
+        dropdown::{ResizeDropdown, ToggleState, dropdown},
         icon::Icon,
         mixins,
     },
 };
 
+pub struct ExamplesPlugin;
+
+impl Plugin for ExamplesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PresetFilter>()
+            .add_observer(start_renaming)
+            .add_observer(toggle_searching)
+            .add_observer(toggle_tag)
+            .add_observer(set_morph)
+            .add_systems(
+                Update,
+                update_book.run_if(
+                    on_event::<AssetEvent<Preset>>
+                        .or(on_event::<AssetEvent<PresetBook>>)
+                        .or(resource_changed::<Library>)
+                        .or(resource_changed::<PresetFilter>)
+                        .or(resource_changed::<MorphSettings>)
+                        .or(resource_exists_and_changed::<Renaming>)
+                        .or(resource_removed::<Renaming>)
+                        .or(resource_added::<Searching>)
+                        .or(resource_removed::<Searching>)
+                        .or(any_match_filter::<Added<UserPresetList>>),
+                ),
+            )
+            .add_systems(Update, type_name.run_if(resource_exists::<Renaming>))
+            .add_systems(Update, type_search.run_if(resource_exists::<Searching>))
+            .add_systems(
+                Update,
+                (
+                    spawn_received_prompt.run_if(resource_added::<ReceivedState>),
+                    despawn_received_prompt.run_if(resource_removed::<ReceivedState>),
+                ),
+            );
+    }
+}
+
 const VERTICAL_PADDING: f32 = 4.0;
 const FONT_SIZE: f32 = 24.0;
-const TOTAL_HEIGHT: f32 =
-    (NUM_PRESETS as f32) * FONT_SIZE + 2.0 * VERTICAL_PADDING * NUM_PRESETS as f32;
+const ROW_HEIGHT: f32 = FONT_SIZE + 2.0 * VERTICAL_PADDING;
+
+const USER_FONT_SIZE: f32 = 20.0;
+const ICON_SIZE: f32 = 16.0;
+
+const TAG_FONT_SIZE: f32 = 14.0;
+const TAGS_PER_ROW: usize = 4;
+
+const CARD_WIDTH: f32 = 240.0;
 
 pub fn examples() -> impl Bundle {
     dropdown(
         Icon("icons/open-book.png"),
         "Book of Fates",
-        TOTAL_HEIGHT,
+        // Sized once the presets have loaded
+        0.0,
         contents(),
     )
 }
 
 const DROPDOWN_PADDING: f32 = 8.0;
 
+#[derive(Debug, Component)]
+struct SearchBox;
+
+#[derive(Debug, Component)]
+struct MorphControls;
+
+#[derive(Debug, Component)]
+struct TagList;
+
+#[derive(Debug, Component)]
+struct BuiltinPresetList;
+
+#[derive(Debug, Component)]
+struct UserPresetList;
+
+/// The user preset whose name is being typed, and what's been typed so far.
+#[derive(Debug, Resource, Clone)]
+struct Renaming {
+    index: usize,
+    name: String,
+}
+
+#[derive(Debug, Event, Clone, Copy)]
+struct StartRenaming(usize);
+
+/// What the built in presets are narrowed down to, see [`Preset::matches`].
+#[derive(Debug, Resource, Default)]
+struct PresetFilter {
+    search: String,
+    tags: Vec<String>,
+}
+
+/// Typing goes into the search box while this is around.
+#[derive(Debug, Resource)]
+struct Searching;
+
+#[derive(Debug, Event, Clone, Copy)]
+struct ToggleSearching;
+
+#[derive(Debug, Event, Clone)]
+struct ToggleTag(String);
+
+#[derive(Debug, Event, Clone, Copy)]
+struct SetMorph(MorphSettings);
+
+/// The morph durations to pick from, in seconds, where none swaps presets in at once.
+const MORPH_DURATIONS: [f32; 4] = [0.0, 2.0, 5.0, 10.0];
+
 fn contents() -> impl Bundle {
     (
         Node {
@@ -41,9 +146,363 @@ fn contents() -> impl Bundle {
             padding: UiRect::all(Val::Px(DROPDOWN_PADDING)),
             ..default()
         },
-        Children::spawn(SpawnIter(PRESETS.clone().into_iter().map(
-            |(name, model, spawner_config, params)| {
-                (
+        children![
+            (
+                SearchBox,
+                Node {
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+            (
+                MorphControls,
+                Node {
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+            (
+                TagList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+            (
+                BuiltinPresetList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+            user_presets_header(),
+            (
+                UserPresetList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
+fn user_presets_header() -> impl Bundle {
+    (
+        Node {
+            height: Val::Px(ROW_HEIGHT),
+            padding: UiRect::horizontal(Val::Px(8.0)),
+            align_items: AlignItems::Center,
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        children![
+            (
+                Node {
+                    flex_grow: 1.0,
+                    ..default()
+                },
+                Text::new("Your Fates"),
+                TextFont::from_font_size(USER_FONT_SIZE),
+                TextColor(Color::WHITE.with_alpha(0.6)),
+                Pickable::IGNORE,
+            ),
+            icon_button("icons/bookmark.png", "Inscribe the present", SavePreset),
+        ],
+    )
+}
+
+fn icon_button(
+    icon: &'static str,
+    tooltip: &'static str,
+    event: impl Event + Clone,
+) -> impl Bundle {
+    (
+        Node {
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(4.0)),
+        mixins::hover_colour(Color::NONE, UI_BACKGROUND_FOCUSED),
+        mixins::tooltip(tooltip),
+        children![(
+            Node {
+                width: Val::Px(ICON_SIZE),
+                height: Val::Px(ICON_SIZE),
+                ..default()
+            },
+            Icon(icon),
+            Pickable::IGNORE,
+        )],
+        observe(
+            move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.trigger(event.clone());
+            },
+        ),
+    )
+}
+
+fn search_field(search: &str, searching: bool) -> impl Bundle {
+    let (text, text_colour, colour) = match (searching, search.is_empty()) {
+        (true, _) => (format!("{search}_"), Color::WHITE, UI_BACKGROUND_FOCUSED),
+        (false, true) => (
+            "Seek a fate...".to_string(),
+            Color::WHITE.with_alpha(0.6),
+            Color::NONE,
+        ),
+        (false, false) => (search.to_string(), Color::WHITE, Color::NONE),
+    };
+
+    (
+        Node {
+            height: Val::Px(ROW_HEIGHT),
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
+            align_items: AlignItems::Center,
+            width: Val::Percent(100.0),
+            overflow: Overflow::clip_x(),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(4.0)),
+        mixins::hover_colour(colour, UI_BACKGROUND_FOCUSED),
+        children![(
+            Text::new(text),
+            TextFont::from_font_size(USER_FONT_SIZE),
+            TextColor(text_colour),
+            Pickable::IGNORE,
+        )],
+        observe(
+            |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.trigger(ToggleSearching);
+            },
+        ),
+    )
+}
+
+fn tag_chip(tag: String, selected: bool) -> impl Bundle {
+    chip(tag.clone(), selected, ToggleTag(tag))
+}
+
+fn chip(label: String, selected: bool, event: impl Event + Clone) -> impl Bundle {
+    let colour = if selected {
+        UI_BACKGROUND_FOCUSED
+    } else {
+        Color::NONE
+    };
+
+    (
+        Node {
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BorderColor(Color::WHITE.with_alpha(0.4)),
+        BorderRadius::all(Val::Px(8.0)),
+        mixins::hover_colour(colour, UI_BACKGROUND_FOCUSED),
+        children![(
+            Text::new(label),
+            TextFont::from_font_size(TAG_FONT_SIZE),
+            Pickable::IGNORE,
+        )],
+        observe(
+            move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.trigger(event.clone());
+            },
+        ),
+    )
+}
+
+fn morph_controls(settings: &MorphSettings) -> impl Bundle {
+    let durations = MORPH_DURATIONS
+        .into_iter()
+        .map(|seconds| {
+            let label = if seconds > 0.0 {
+                format!("{seconds}s")
+            } else {
+                "Instant".to_string()
+            };
+
+            chip(
+                label,
+                settings.seconds == seconds,
+                SetMorph(MorphSettings {
+                    seconds,
+                    ..*settings
+                }),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Easing only matters while there's something to ease
+    let easing = settings.enabled().then(|| {
+        chip(
+            settings.easing_name().to_string(),
+            false,
+            SetMorph(MorphSettings {
+                easing: settings.next_easing(),
+                ..*settings
+            }),
+        )
+    });
+
+    (
+        Node {
+            height: Val::Px(ROW_HEIGHT),
+            padding: UiRect::horizontal(Val::Px(8.0)),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            ..default()
+        },
+        Children::spawn((
+            Spawn((
+                Text::new("Morph"),
+                TextFont::from_font_size(USER_FONT_SIZE),
+                TextColor(Color::WHITE.with_alpha(0.6)),
+                Pickable::IGNORE,
+            )),
+            SpawnIter(durations.into_iter()),
+            SpawnIter(easing.into_iter()),
+        )),
+    )
+}
+
+/// Shown while hovering over a built in preset.
+fn preset_card(preset: &Preset) -> impl Bundle {
+    let thumbnail = preset.thumbnail.clone().map(|image| {
+        (
+            Node {
+                width: Val::Px(CARD_WIDTH),
+                height: Val::Px(CARD_WIDTH * 9.0 / 16.0),
+                ..default()
+            },
+            ImageNode::new(image),
+            BorderRadius::all(Val::Px(4.0)),
+            Pickable::IGNORE,
+        )
+    });
+
+    let author = preset.author.clone().map(|author| {
+        (
+            Text::new(format!("by {author}")),
+            TextFont::from_font_size(14.0),
+            TextColor(Color::WHITE.with_alpha(0.6)),
+            Pickable::IGNORE,
+        )
+    });
+
+    let description = Some(preset.description.clone())
+        .filter(|description| !description.is_empty())
+        .map(|description| {
+            (
+                Text::new(description),
+                TextFont::from_font_size(16.0),
+                Pickable::IGNORE,
+            )
+        });
+
+    let tags = Some(preset.tags.join(" · "))
+        .filter(|tags| !tags.is_empty())
+        .map(|tags| {
+            (
+                Text::new(tags),
+                TextFont::from_font_size(TAG_FONT_SIZE),
+                TextColor(Color::WHITE.with_alpha(0.6)),
+                Pickable::IGNORE,
+            )
+        });
+
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            max_width: Val::Px(CARD_WIDTH),
+            ..default()
+        },
+        Pickable::IGNORE,
+        Children::spawn((
+            SpawnIter(thumbnail.into_iter()),
+            Spawn((
+                Text::new(preset.name.clone()),
+                TextFont::from_font_size(20.0),
+                Pickable::IGNORE,
+            )),
+            SpawnIter(author.into_iter()),
+            SpawnIter(description.into_iter()),
+            SpawnIter(tags.into_iter()),
+        )),
+    )
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_book(
+    presets: Presets,
+    library: Res<Library>,
+    filter: Res<PresetFilter>,
+    searching: Option<Res<Searching>>,
+    renaming: Option<Res<Renaming>>,
+    morph: Res<MorphSettings>,
+    search_box: Single<Entity, With<SearchBox>>,
+    morph_controls: Single<Entity, With<MorphControls>>,
+    tag_list: Single<Entity, With<TagList>>,
+    builtin_list: Single<Entity, With<BuiltinPresetList>>,
+    user_list: Single<Entity, With<UserPresetList>>,
+    mut commands: Commands,
+) {
+    commands
+        .entity(*search_box)
+        .despawn_related::<Children>()
+        .with_child(search_field(&filter.search, searching.is_some()));
+
+    commands
+        .entity(*morph_controls)
+        .despawn_related::<Children>()
+        .with_child(morph_controls(&morph));
+
+    let tags = presets
+        .iter()
+        .flat_map(|(_, preset)| preset.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    commands
+        .entity(*tag_list)
+        .despawn_related::<Children>()
+        .with_children(|list| {
+            for row in tags.chunks(TAGS_PER_ROW) {
+                list.spawn(Node {
+                    height: Val::Px(ROW_HEIGHT),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|row_entity| {
+                    for tag in row {
+                        row_entity.spawn(tag_chip(tag.clone(), filter.tags.contains(tag)));
+                    }
+                });
+            }
+        });
+
+    let matching = presets
+        .iter()
+        .filter(|(_, preset)| preset.matches(&filter.search, &filter.tags))
+        .collect::<Vec<_>>();
+
+    commands
+        .entity(*builtin_list)
+        .despawn_related::<Children>()
+        .with_children(|list| {
+            for &(id, preset) in &matching {
+                let preset = preset.clone();
+
+                list.spawn((
                     Node {
                         padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
                         align_items: AlignItems::Center,
@@ -52,28 +511,238 @@ fn contents() -> impl Bundle {
                     },
                     BorderRadius::all(Val::Px(4.0)),
                     mixins::hover_colour(Color::NONE, UI_BACKGROUND_FOCUSED),
-                    children![(Text::from(name), Pickable::IGNORE)],
-                    observe({
-                        move |mut trigger: Trigger<Pointer<Click>>,
-                              mut commands: Commands,
-                              mut res_model: ResMut<Model>,
-                              mut res_params: ResMut<SimulationParams>| {
+                    children![(Text::new(preset.name.clone()), Pickable::IGNORE)],
+                    mixins::hover_card(move || preset_card(&preset)),
+                    observe(
+                        move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
                             trigger.propagate(false);
 
                             // This will bubble up to the dropdown, which will close itself
                             commands.trigger_targets(ToggleState, trigger.target());
+                            commands.trigger(ApplyPreset(id));
+                        },
+                    ),
+                ));
+            }
+        });
 
-                            *res_model = model.clone();
-                            *res_params = params.clone();
+    commands
+        .entity(*user_list)
+        .despawn_related::<Children>()
+        .with_children(|list| {
+            for (index, preset) in library.presets.iter().enumerate() {
+                let (name, colour) = match renaming.as_deref() {
+                    Some(renaming) if renaming.index == index => {
+                        (format!("{}_", renaming.name), UI_BACKGROUND_FOCUSED)
+                    }
+                    _ => (preset.name.clone(), Color::NONE),
+                };
 
-                            if spawner_config != SpawnerConfig::None {
-                                commands.insert_resource(spawner_config.clone());
-                                commands.trigger(Respawn);
-                            }
-                        }
-                    }),
-                )
-            },
-        ))),
-    )
+                list.spawn((
+                    Node {
+                        height: Val::Px(ROW_HEIGHT),
+                        padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
+                        align_items: AlignItems::Center,
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(4.0)),
+                    mixins::hover_colour(colour, UI_BACKGROUND_FOCUSED),
+                    children![
+                        (
+                            Node {
+                                flex_grow: 1.0,
+                                overflow: Overflow::clip_x(),
+                                ..default()
+                            },
+                            Text::new(name),
+                            TextFont::from_font_size(USER_FONT_SIZE),
+                            Pickable::IGNORE,
+                        ),
+                        icon_button("icons/up.png", "Ascend", MovePreset { index, offset: -1 }),
+                        icon_button("icons/down.png", "Descend", MovePreset { index, offset: 1 }),
+                        icon_button("icons/quill.png", "Rename", StartRenaming(index)),
+                        icon_button("icons/erase.png", "Erase", DeletePreset(index)),
+                    ],
+                    observe(
+                        move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                            trigger.propagate(false);
+
+                            // This will bubble up to the dropdown, which will close itself
+                            commands.trigger_targets(ToggleState, trigger.target());
+                            commands.trigger(LoadPreset(index));
+                        },
+                    ),
+                ));
+            }
+        });
+
+    // The search box, the morph controls, the tags, one row for each preset and the header of the
+    // user's own
+    let rows = 2 + tags.chunks(TAGS_PER_ROW).len() + matching.len() + 1 + library.presets.len();
+    commands.trigger_targets(ResizeDropdown(rows as f32 * ROW_HEIGHT), *user_list);
+}
+
+fn toggle_searching(
+    _trigger: Trigger<ToggleSearching>,
+    searching: Option<Res<Searching>>,
+    mut commands: Commands,
+) {
+    if searching.is_some() {
+        commands.remove_resource::<Searching>();
+    } else {
+        commands.remove_resource::<Renaming>();
+        commands.insert_resource(Searching);
+    }
+}
+
+fn set_morph(trigger: Trigger<SetMorph>, mut commands: Commands) {
+    commands.insert_resource(trigger.0);
+}
+
+fn toggle_tag(trigger: Trigger<ToggleTag>, mut filter: ResMut<PresetFilter>) {
+    let tag = &trigger.0;
+
+    match filter.tags.iter().position(|it| it == tag) {
+        Some(index) => {
+            filter.tags.remove(index);
+        }
+        None => filter.tags.push(tag.clone()),
+    }
+}
+
+fn start_renaming(
+    trigger: Trigger<StartRenaming>,
+    library: Res<Library>,
+    renaming: Option<Res<Renaming>>,
+    mut commands: Commands,
+) {
+    let index = trigger.0;
+
+    // Pressing rename again finishes renaming
+    if let Some(renaming) = renaming.filter(|renaming| renaming.index == index) {
+        commands.trigger(RenamePreset {
+            index,
+            name: renaming.name.clone(),
+        });
+        commands.remove_resource::<Renaming>();
+        return;
+    }
+
+    if let Some(preset) = library.presets.get(index) {
+        commands.remove_resource::<Searching>();
+        commands.insert_resource(Renaming {
+            index,
+            name: preset.name.clone(),
+        });
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn type_name(
+    mut keys: EventReader<KeyboardInput>,
+    mut renaming: ResMut<Renaming>,
+    mut commands: Commands,
+) {
+    for key in keys.read() {
+        if key.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &key.logical_key {
+            Key::Character(text) => renaming.name.push_str(text),
+            Key::Space => renaming.name.push(' '),
+            Key::Backspace => {
+                renaming.name.pop();
+            }
+            Key::Enter => {
+                commands.trigger(RenamePreset {
+                    index: renaming.index,
+                    name: renaming.name.clone(),
+                });
+                commands.remove_resource::<Renaming>();
+                return;
+            }
+            Key::Escape => {
+                commands.remove_resource::<Renaming>();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn type_search(
+    mut keys: EventReader<KeyboardInput>,
+    mut filter: ResMut<PresetFilter>,
+    mut commands: Commands,
+) {
+    for key in keys.read() {
+        if key.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &key.logical_key {
+            Key::Character(text) => filter.search.push_str(text),
+            Key::Space => filter.search.push(' '),
+            Key::Backspace => {
+                filter.search.pop();
+            }
+            Key::Enter => {
+                commands.remove_resource::<Searching>();
+                return;
+            }
+            Key::Escape => {
+                filter.search.clear();
+                commands.remove_resource::<Searching>();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+struct ReceivedPrompt;
+
+const PROMPT_WIDTH: f32 = 320.0;
+
+fn spawn_received_prompt(mut commands: Commands) {
+    commands.spawn((
+        ReceivedPrompt,
+        StateScoped(Shaping),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(96.0),
+            left: Val::Percent(50.0),
+            width: Val::Px(PROMPT_WIDTH),
+            margin: UiRect::left(Val::Px(-PROMPT_WIDTH / 2.0)),
+            padding: UiRect::all(Val::Px(12.0)),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(16.0)),
+        BackgroundColor(UI_BACKGROUND),
+        children![
+            (
+                Node {
+                    flex_grow: 1.0,
+                    ..default()
+                },
+                Text::new("A fate was received"),
+                TextFont::from_font_size(16.0),
+                Pickable::IGNORE,
+            ),
+            icon_button("icons/bookmark.png", "Save to Book", SaveReceived),
+            icon_button("icons/erase.png", "Dismiss", DismissReceived),
+        ],
+    ));
+}
+
+fn despawn_received_prompt(prompts: Query<Entity, With<ReceivedPrompt>>, mut commands: Commands) {
+    prompts
+        .iter()
+        .for_each(|prompt| commands.entity(prompt).despawn());
 }
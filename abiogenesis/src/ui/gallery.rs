@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_tweening::{Animator, Delay, Sequence, Tween};
+
+use crate::{
+    observe::observe,
+    particles::presets::{ApplyPreset, Presets},
+    scenes::{ChangeScene, Scene},
+    ui::lenses::TextColourLens,
+};
+
+pub struct GalleryPlugin;
+
+impl Plugin for GalleryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(Scene::Gallery),
+            (spawn_gallery, show_next_preset).chain(),
+        )
+        .add_systems(
+            Update,
+            show_next_preset.run_if(
+                in_state(Scene::Gallery).and(on_timer(Duration::from_secs_f32(PRESET_DURATION))),
+            ),
+        );
+    }
+}
+
+/// Seconds each preset is shown for before moving on to the next.
+const PRESET_DURATION: f32 = 20.0;
+
+#[derive(Debug, Component)]
+struct Caption;
+
+fn spawn_gallery(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(Scene::Gallery),
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::End,
+            padding: UiRect::bottom(Val::Px(48.0)),
+            ..default()
+        },
+        // Any click returns to the sandbox, just as it was left
+        observe(|_: Trigger<Pointer<Click>>, mut commands: Commands| {
+            commands.trigger(ChangeScene(Scene::Sandbox));
+        }),
+        children![(
+            Caption,
+            Text::new(""),
+            TextFont::from_font_size(32.0),
+            TextColor(Color::WHITE.with_alpha(0.0)),
+            Pickable::IGNORE,
+        )],
+    ));
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn show_next_preset(
+    mut next: Local<usize>,
+    caption: Single<Entity, With<Caption>>,
+    presets: Presets,
+    mut commands: Commands,
+) {
+    let num_presets = presets.len();
+    let Some((id, preset)) = presets.iter().nth(*next % num_presets.max(1)) else {
+        return;
+    };
+    *next += 1;
+
+    commands.trigger(ApplyPreset(id));
+
+    commands.entity(*caption).insert((
+        Text::new(preset.name.clone()),
+        Animator::new(
+            Sequence::from_single(Tween::new(
+                EaseFunction::SmoothStepIn,
+                Duration::from_secs_f32(2.0),
+                TextColourLens {
+                    start: Color::WHITE.with_alpha(0.0),
+                    end: Color::WHITE.with_alpha(0.8),
+                },
+            ))
+            .then(Delay::new(Duration::from_secs_f32(PRESET_DURATION - 4.0)))
+            .then(Tween::new(
+                EaseFunction::SmoothStepOut,
+                Duration::from_secs_f32(2.0),
+                TextColourLens {
+                    start: Color::WHITE.with_alpha(0.8),
+                    end: Color::WHITE.with_alpha(0.0),
+                },
+            )),
+        ),
+    ));
+}
@@ -11,6 +11,7 @@ use crate::{
         icon::Icon,
         lenses::{BottomLens, LeftLens, TopLens},
         mixins,
+        playback::PlaybackBar,
         toolbar::ToolBar,
     },
 };
@@ -36,46 +37,75 @@ pub fn hide_ui() -> impl Bundle {
             Pickable::IGNORE
         )],
         observe(
-            |mut trigger: Trigger<Pointer<Click>>,
-             mut commands: Commands,
-             sidebar: Single<Entity, With<Sidebar>>,
-             toolbar: Single<Entity, With<ToolBar>>,
-             show_ui: Single<Entity, With<ShowUIButton>>| {
+            |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
                 trigger.propagate(false);
+                commands.trigger(HideUi);
+            },
+        ),
+    )
+}
 
-                commands.entity(*sidebar).insert(Animator::new(Tween::new(
-                    EaseFunction::SmootherStepIn,
-                    Duration::from_secs_f32(1.),
-                    LeftLens {
-                        start: 0.,
-                        end: -250.,
-                    },
-                )));
+/// Slides the sidebar, toolbar and playback bar off screen, leaving just the button to bring them
+/// back.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct HideUi;
 
-                commands.entity(*toolbar).insert(Animator::new(Tween::new(
-                    EaseFunction::SmootherStepIn,
-                    Duration::from_secs_f32(1.),
-                    BottomLens {
-                        start: 0.0,
-                        end: -100.,
-                    },
-                )));
+/// Hides the UI as soon as it appears, for starting straight into an exhibition.
+#[derive(Debug, Resource, Clone, Copy)]
+pub struct StartWithUiHidden;
 
-                commands.entity(*show_ui).insert(Animator::new(
-                    Sequence::from_single(Delay::new(Duration::from_secs_f32(0.750))).then(
-                        Tween::new(
-                            EaseFunction::SmootherStepOut,
-                            Duration::from_secs_f32(1.),
-                            TopLens {
-                                start: -50.,
-                                end: 0.,
-                            },
-                        ),
-                    ),
-                ));
+pub fn hide(
+    _: Trigger<HideUi>,
+    mut commands: Commands,
+    sidebar: Single<Entity, With<Sidebar>>,
+    toolbar: Single<Entity, With<ToolBar>>,
+    playback_bar: Single<Entity, With<PlaybackBar>>,
+    show_ui: Single<Entity, With<ShowUIButton>>,
+) {
+    commands.entity(*sidebar).insert(Animator::new(Tween::new(
+        EaseFunction::SmootherStepIn,
+        Duration::from_secs_f32(1.),
+        LeftLens {
+            start: 0.,
+            end: -250.,
+        },
+    )));
+
+    commands.entity(*toolbar).insert(Animator::new(Tween::new(
+        EaseFunction::SmootherStepIn,
+        Duration::from_secs_f32(1.),
+        BottomLens {
+            start: 0.0,
+            end: -100.,
+        },
+    )));
+
+    commands
+        .entity(*playback_bar)
+        .insert(Animator::new(Tween::new(
+            EaseFunction::SmootherStepIn,
+            Duration::from_secs_f32(1.),
+            TopLens {
+                start: 0.0,
+                end: -100.,
             },
-        ),
-    )
+        )));
+
+    commands.entity(*show_ui).insert(Animator::new(
+        Sequence::from_single(Delay::new(Duration::from_secs_f32(0.750))).then(Tween::new(
+            EaseFunction::SmootherStepOut,
+            Duration::from_secs_f32(1.),
+            TopLens {
+                start: -50.,
+                end: 0.,
+            },
+        )),
+    ));
+}
+
+pub fn hide_on_start(mut commands: Commands) {
+    commands.trigger(HideUi);
+    commands.remove_resource::<StartWithUiHidden>();
 }
 
 #[derive(Component)]
@@ -109,6 +139,7 @@ pub fn show_ui_button() -> impl Bundle {
              mut commands: Commands,
              sidebar: Single<Entity, With<Sidebar>>,
              toolbar: Single<Entity, With<ToolBar>>,
+             playback_bar: Single<Entity, With<PlaybackBar>>,
              show_ui: Single<Entity, With<ShowUIButton>>| {
                 trigger.propagate(false);
 
@@ -138,6 +169,19 @@ pub fn show_ui_button() -> impl Bundle {
                     ),
                 ));
 
+                commands.entity(*playback_bar).insert(Animator::new(
+                    Sequence::from_single(Delay::new(Duration::from_secs_f32(0.4))).then(
+                        Tween::new(
+                            EaseFunction::SmootherStepOut,
+                            Duration::from_secs_f32(1.),
+                            TopLens {
+                                start: -100.,
+                                end: 0.0,
+                            },
+                        ),
+                    ),
+                ));
+
                 commands.entity(*show_ui).insert(Animator::new(Tween::new(
                     EaseFunction::SmootherStepIn,
                     Duration::from_secs_f32(0.5),
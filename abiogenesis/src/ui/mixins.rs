@@ -5,7 +5,7 @@ mod hover_colour;
 pub use hover_colour::mixin as hover_colour;
 
 mod tooltip;
-pub use tooltip::tooltip;
+pub use tooltip::{hover_card, tooltip};
 
 mod block_all_interactions;
 pub use block_all_interactions::mixin as block_all_interactions;
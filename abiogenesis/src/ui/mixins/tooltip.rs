@@ -3,8 +3,21 @@ use bevy::prelude::*;
 use crate::observe::observe;
 
 pub fn tooltip(text: impl Into<String>) -> impl Bundle {
+    let text = text.into();
+
+    hover_card(move || {
+        (
+            Text::new(text.clone()),
+            TextFont::from_font_size(18.0),
+            Pickable::IGNORE,
+        )
+    })
+}
+
+/// A tooltip with anything in it, built afresh each time it's shown.
+pub fn hover_card<B: Bundle>(contents: impl Fn() -> B + Send + Sync + 'static) -> impl Bundle {
     (
-        observe(tooltip_hover_start(text.into())),
+        observe(tooltip_hover_start(contents)),
         observe(tooltip_hover),
         observe(tooltip_hover_end),
         observe(tooltip_touch_end),
@@ -14,7 +27,7 @@ pub fn tooltip(text: impl Into<String>) -> impl Bundle {
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Tooltip;
 
-fn tooltip_bundle(text: String, position: Vec2) -> impl Bundle {
+fn tooltip_bundle(contents: impl Bundle, position: Vec2) -> impl Bundle {
     (
         Tooltip,
         Node {
@@ -27,18 +40,16 @@ fn tooltip_bundle(text: String, position: Vec2) -> impl Bundle {
         Pickable::IGNORE,
         BackgroundColor(Color::BLACK.with_alpha(0.8)),
         BorderRadius::all(Val::Px(8.0)),
-        children![(
-            Text::new(text),
-            TextFont::from_font_size(18.0),
-            Pickable::IGNORE
-        )],
+        children![contents],
     )
 }
 
-fn tooltip_hover_start(text: String) -> impl Fn(Trigger<Pointer<Over>>, Commands, Res<UiScale>) {
+fn tooltip_hover_start<B: Bundle>(
+    contents: impl Fn() -> B + Send + Sync + 'static,
+) -> impl Fn(Trigger<Pointer<Over>>, Commands, Res<UiScale>) {
     move |trigger, mut commands, ui_scale| {
         commands.spawn(tooltip_bundle(
-            text.clone(),
+            contents(),
             trigger.pointer_location.position / **ui_scale,
         ));
     }
@@ -4,15 +4,18 @@ use bevy::{
 };
 
 mod circle;
+mod context_menu;
 mod model_box;
 mod num_colours;
 
 use circle::*;
+use context_menu::*;
 use model_box::*;
 use num_colours::*;
 
 use crate::{
     math::remap,
+    observe::observe,
     particles::{
         colour::{ParticleColour::*, *},
         model::Model,
@@ -87,6 +90,7 @@ pub fn model_matrix(num_colours: usize) -> impl Bundle {
             column_gap: Val::Px(4.0),
             ..default()
         },
+        observe(open_context_menu),
         // children![] has a maximum limit of children
         Children::spawn(SpawnWith(move |spawner: &mut RelatedSpawner<ChildOf>| {
             spawner.spawn(change_num_colours());
@@ -0,0 +1,126 @@
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+    particles::{
+        automation::{Automated, Automations},
+        generators::Generator,
+        model::{CopiedRow, LastGenerator, MatrixOperation, Reshape},
+    },
+    ui::{
+        automation::automate_column,
+        context_menu::{ContextMenu, column, context_menu, item},
+        model_matrix::model_box::ModelIndex,
+    },
+};
+
+/// Right clicking anywhere on the model matrix opens a menu of operations on the whole matrix,
+/// along with copying and pasting the row that was clicked on, automating the weight that was
+/// clicked on, and the generators to reshape it with.
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+pub fn open_context_menu(
+    mut trigger: Trigger<Pointer<Click>>,
+    indexes: Query<&ModelIndex>,
+    menus: Query<Entity, With<ContextMenu>>,
+    copied_row: Option<Res<CopiedRow>>,
+    last_generator: Res<LastGenerator>,
+    automations: Res<Automations>,
+    ui_scale: Res<UiScale>,
+    mut commands: Commands,
+) {
+    if !matches!(trigger.button, PointerButton::Secondary) {
+        return;
+    }
+
+    trigger.propagate(false);
+
+    menus
+        .iter()
+        .for_each(|menu| commands.entity(menu).despawn());
+
+    let position = trigger.pointer_location.position / **ui_scale;
+
+    let mut items = vec![
+        ("Symmetrise".to_string(), MatrixOperation::Symmetrise),
+        (
+            "Antisymmetrise".to_string(),
+            MatrixOperation::Antisymmetrise,
+        ),
+        ("Transpose".to_string(), MatrixOperation::Transpose),
+        ("Negate".to_string(), MatrixOperation::Negate),
+        ("Amplify".to_string(), MatrixOperation::Scale(1.5)),
+        ("Dampen".to_string(), MatrixOperation::Scale(0.5)),
+        (
+            "Zero the diagonal".to_string(),
+            MatrixOperation::ZeroDiagonal,
+        ),
+        ("Rotate colours".to_string(), MatrixOperation::RotateColours),
+    ];
+
+    let index = indexes.get(trigger.target).ok();
+
+    if let Some(index) = index {
+        let source = index.source;
+
+        items.push((
+            format!("Copy {source}'s row"),
+            MatrixOperation::CopyRow(source),
+        ));
+
+        if copied_row.is_some() {
+            items.push((
+                format!("Paste onto {source}'s row"),
+                MatrixOperation::PasteRow(source),
+            ));
+        }
+    }
+
+    let operations = items
+        .into_iter()
+        .map(|(label, operation)| item(label, operation, false))
+        .collect::<Vec<_>>();
+
+    // The seed is rolled now rather than on click, every menu gets a fresh one anyway
+    let mut generators = Generator::ALL
+        .into_iter()
+        .map(|generator| {
+            let reshape = Reshape {
+                generator,
+                seed: rand::random(),
+            };
+
+            item(
+                generator.name().to_string(),
+                reshape,
+                generator == **last_generator,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(seed) = last_generator.seed {
+        let reshape = Reshape {
+            generator: **last_generator,
+            seed,
+        };
+
+        generators.push(item(format!("Same seed again ({seed})"), reshape, false));
+    }
+
+    let automate = index.map(|index| {
+        let target = Automated::Weight {
+            source: index.source,
+            target: index.target,
+        };
+
+        automate_column(target, &automations)
+    });
+
+    commands.spawn(context_menu(
+        "Matrix Context Menu",
+        position,
+        (
+            Spawn(column("Transform", operations)),
+            Spawn(column("Reshape", generators)),
+            SpawnIter(automate.into_iter()),
+        ),
+    ));
+}
@@ -6,8 +6,8 @@ use bevy_tweening::{Animator, Tween, lens::UiPositionLens};
 
 use crate::{
     observe::observe,
-    particles::{colour::ParticleColour, model::Model},
-    ui::mixins,
+    particles::{automation::Automated, colour::ParticleColour, model::Model},
+    ui::{automation::AutomationIndicator, mixins},
 };
 
 #[derive(Debug, Component, Clone, Copy, Reflect)]
@@ -25,6 +25,8 @@ pub fn model_box(source: ParticleColour, target: ParticleColour) -> impl Bundle
 
     (
         ModelIndex { source, target },
+        // The matrix's context menu automates it
+        AutomationIndicator(Automated::Weight { source, target }),
         Node {
             padding: UiRect::all(Val::Px(8.0)),
             justify_content: JustifyContent::Center,
@@ -62,6 +64,11 @@ fn drag_start(
     indexes: Query<&ModelIndex>,
     mut commands: Commands,
 ) {
+    // Right click opens the context menu instead
+    if !matches!(trigger.button, PointerButton::Primary) {
+        return;
+    }
+
     let Ok(index) = indexes.get(trigger.target) else {
         return;
     };
@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 
 use crate::{
-    particles::simulation::{
-        ATTRACTION_RADIUS_RANGE, DECAY_RATE_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE,
-        PEAK_ATTRACTION_RADIUS_RANGE, REPULSION_RADIUS_RANGE, SimulationParams,
+    particles::{
+        automation::Automated,
+        simulation::{
+            ATTRACTION_RADIUS_RANGE, DECAY_RATE_RANGE, FORCE_STRENGTH_RANGE, FRICTION_RANGE,
+            PEAK_ATTRACTION_RADIUS_RANGE, REPULSION_RADIUS_RANGE, SimulationParams,
+        },
     },
     ui::{
+        automation::automatable,
         dropdown::dropdown,
         icon::Icon,
         model_matrix::{MODEL_MATRIX_SIZE, model_matrix},
@@ -44,42 +48,62 @@ fn content(num_colours: usize) -> impl Bundle {
         },
         children![
             model_matrix(num_colours),
-            Slider {
-                name: "Friction",
-                range: FRICTION_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.friction },
-            }
-            .into_bundle(),
-            Slider {
-                name: "Force",
-                range: FORCE_STRENGTH_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.force_strength },
-            }
-            .into_bundle(),
-            Slider {
-                name: "Attraction Radius",
-                range: ATTRACTION_RADIUS_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.attraction_radius },
-            }
-            .into_bundle(),
-            Slider {
-                name: "Peak Attraction Radius",
-                range: PEAK_ATTRACTION_RADIUS_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.peak_attraction_radius },
-            }
-            .into_bundle(),
-            Slider {
-                name: "Repulsion Radius",
-                range: REPULSION_RADIUS_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.repulsion_radius },
-            }
-            .into_bundle(),
-            Slider {
-                name: "Entropy",
-                range: DECAY_RATE_RANGE,
-                lens: |resource: &mut SimulationParams| { &mut resource.decay_rate },
-            }
-            .into_bundle(),
+            (
+                Slider {
+                    name: "Friction",
+                    range: FRICTION_RANGE,
+                    lens: |resource: &mut SimulationParams| { &mut resource.friction },
+                }
+                .into_bundle(),
+                automatable(Automated::Friction),
+            ),
+            (
+                Slider {
+                    name: "Force",
+                    range: FORCE_STRENGTH_RANGE,
+                    lens: |resource: &mut SimulationParams| { &mut resource.force_strength },
+                }
+                .into_bundle(),
+                automatable(Automated::ForceStrength),
+            ),
+            (
+                Slider {
+                    name: "Attraction Radius",
+                    range: ATTRACTION_RADIUS_RANGE,
+                    lens: |resource: &mut SimulationParams| { &mut resource.attraction_radius },
+                }
+                .into_bundle(),
+                automatable(Automated::AttractionRadius),
+            ),
+            (
+                Slider {
+                    name: "Peak Attraction Radius",
+                    range: PEAK_ATTRACTION_RADIUS_RANGE,
+                    lens: |resource: &mut SimulationParams| {
+                        &mut resource.peak_attraction_radius
+                    },
+                }
+                .into_bundle(),
+                automatable(Automated::PeakAttractionRadius),
+            ),
+            (
+                Slider {
+                    name: "Repulsion Radius",
+                    range: REPULSION_RADIUS_RANGE,
+                    lens: |resource: &mut SimulationParams| { &mut resource.repulsion_radius },
+                }
+                .into_bundle(),
+                automatable(Automated::RepulsionRadius),
+            ),
+            (
+                Slider {
+                    name: "Entropy",
+                    range: DECAY_RATE_RANGE,
+                    lens: |resource: &mut SimulationParams| { &mut resource.decay_rate },
+                }
+                .into_bundle(),
+                automatable(Automated::DecayRate),
+            ),
         ],
     )
 }
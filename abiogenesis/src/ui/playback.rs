@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::{Animator, Tween};
+
+use crate::{
+    observe::observe,
+    particles::{
+        history::{History, Rewind},
+        playback::{Hasten, Linger, Playback, StepSimulation, TogglePause},
+    },
+    ui::{
+        colours::{UI_BACKGROUND, UI_BACKGROUND_FOCUSED},
+        icon::Icon,
+        lenses::TopLens,
+        mixins,
+    },
+};
+
+pub struct PlaybackBarPlugin;
+
+impl Plugin for PlaybackBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_playback_bar
+                .run_if(resource_changed::<Playback>.or(any_match_filter::<Added<PlaybackBar>>)),
+        )
+        .add_systems(Update, update_timeline.run_if(resource_changed::<History>));
+    }
+}
+
+#[derive(Component)]
+pub struct PlaybackBar;
+
+#[derive(Component)]
+struct PauseIcon;
+
+#[derive(Component)]
+struct SpeedText;
+
+#[derive(Component)]
+struct TimelineHandle;
+
+/// How many steps a shift click of Advance takes.
+const STEPS_WITH_SHIFT: usize = 10;
+
+const TIMELINE_WIDTH: f32 = 160.0;
+const HANDLE_SIZE: f32 = 16.0;
+
+pub fn playback_bar() -> impl Bundle {
+    (
+        PlaybackBar,
+        Node {
+            position_type: PositionType::Absolute,
+            height: Val::Px(50.0),
+            top: Val::Px(-100.),
+            right: Val::Px(0.0),
+            margin: UiRect::new(Val::Auto, Val::Px(16.0), Val::Px(16.0), Val::Auto),
+            padding: UiRect::horizontal(Val::Px(8.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(UI_BACKGROUND),
+        BorderRadius::all(Val::Px(25.0)),
+        mixins::block_all_interactions(),
+        Animator::new(Tween::new(
+            EaseFunction::SmootherStepOut,
+            Duration::from_secs_f32(1.5),
+            TopLens {
+                start: -100.,
+                end: 0.0,
+            },
+        )),
+        children![
+            playback_button("Linger", Linger, Icon("icons/slower.png"), ()),
+            playback_button("Hold", TogglePause, Icon("icons/pause.png"), PauseIcon),
+            round_button(
+                "Advance, ten with shift",
+                Icon("icons/step.png"),
+                (),
+                observe(advance),
+            ),
+            playback_button("Hasten", Hasten, Icon("icons/faster.png"), ()),
+            (
+                SpeedText,
+                Node {
+                    width: Val::Px(48.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                Text::new("1x"),
+                TextFont::from_font_size(16.0),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Pickable::IGNORE,
+            ),
+            timeline(),
+        ],
+    )
+}
+
+fn timeline() -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Relative,
+            align_items: AlignItems::Center,
+            width: Val::Px(TIMELINE_WIDTH),
+            height: Val::Px(HANDLE_SIZE),
+            margin: UiRect::horizontal(Val::Px(8.0)),
+            ..default()
+        },
+        mixins::tooltip("Recall"),
+        children![
+            (
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Px(6.0),
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(8.0)),
+                BackgroundColor(Color::WHITE.with_alpha(0.5)),
+                Pickable::IGNORE,
+            ),
+            (
+                TimelineHandle,
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(HANDLE_SIZE),
+                    height: Val::Px(HANDLE_SIZE),
+                    left: Val::Px(TIMELINE_WIDTH - HANDLE_SIZE),
+                    ..default()
+                },
+                BorderRadius::all(Val::Percent(50.0)),
+                BackgroundColor(Color::WHITE.with_alpha(0.8)),
+                mixins::cursor_grab_icon(),
+                observe(scrub),
+            )
+        ],
+    )
+}
+
+fn playback_button(
+    text: &'static str,
+    event: impl Event + Clone,
+    icon: Icon,
+    marker: impl Bundle,
+) -> impl Bundle {
+    round_button(
+        text,
+        icon,
+        marker,
+        observe(
+            move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.trigger(event.clone());
+            },
+        ),
+    )
+}
+
+fn round_button(
+    text: &'static str,
+    icon: Icon,
+    marker: impl Bundle,
+    on_click: impl Bundle,
+) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(40.0),
+            height: Val::Px(40.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Percent(50.0)),
+        mixins::hover_colour(UI_BACKGROUND, UI_BACKGROUND_FOCUSED),
+        mixins::tooltip(text),
+        on_click,
+        children![(
+            marker,
+            Node {
+                width: Val::Px(24.0),
+                height: Val::Px(24.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+            icon,
+        )],
+    )
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn advance(
+    mut trigger: Trigger<Pointer<Click>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+) {
+    trigger.propagate(false);
+
+    let steps = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        STEPS_WITH_SHIFT
+    } else {
+        1
+    };
+
+    commands.trigger(StepSimulation(steps));
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_playback_bar(
+    playback: Res<Playback>,
+    pause_icon: Single<Entity, With<PauseIcon>>,
+    mut speed_text: Single<&mut Text, With<SpeedText>>,
+    mut commands: Commands,
+) {
+    commands.entity(*pause_icon).insert(if playback.paused {
+        Icon("icons/play.png")
+    } else {
+        Icon("icons/pause.png")
+    });
+
+    speed_text.0 = format!("{}x", playback.speed);
+}
+
+fn handle_position(history: &History) -> f32 {
+    let Some(cursor) = history.cursor() else {
+        return 1.0;
+    };
+
+    cursor as f32 / (history.len().max(2) - 1) as f32
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_timeline(history: Res<History>, mut handle: Single<&mut Node, With<TimelineHandle>>) {
+    handle.left = Val::Px(handle_position(&history) * (TIMELINE_WIDTH - HANDLE_SIZE));
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn scrub(
+    mut trigger: Trigger<Pointer<Drag>>,
+    history: Res<History>,
+    mut position: Local<Option<f32>>,
+    mut commands: Commands,
+) {
+    trigger.propagate(false);
+
+    if history.len() < 2 {
+        return;
+    }
+
+    // Keep the fraction of a frame the pointer has moved so slow drags still scrub
+    let last = (history.len() - 1) as f32;
+    let current = match (*position, history.cursor()) {
+        (Some(position), Some(cursor)) if position.round() as usize == cursor => position,
+        (_, cursor) => cursor.map_or(last, |cursor| cursor as f32),
+    };
+
+    let next = (current + trigger.delta.x / (TIMELINE_WIDTH - HANDLE_SIZE) * last).clamp(0.0, last);
+
+    *position = Some(next);
+
+    if history.cursor() != Some(next.round() as usize) {
+        commands.trigger(Rewind(next.round() as usize));
+    }
+}
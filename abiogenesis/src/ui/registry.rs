@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::{
+    observe::observe,
+    particles::{
+        lineage::{FollowOrganism, Organism, OrganismRegistry},
+        playback::SimulationClock,
+    },
+    ui::{colours::UI_BACKGROUND_FOCUSED, dropdown::dropdown, icon::Icon, mixins},
+};
+
+pub struct RegistryPlugin;
+
+impl Plugin for RegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegistrySort>().add_systems(
+            Update,
+            update_registry.run_if(
+                resource_changed::<OrganismRegistry>
+                    .or(resource_changed::<RegistrySort>)
+                    .or(resource_changed_or_removed::<FollowOrganism>)
+                    .or(any_match_filter::<Added<RegistryList>>),
+            ),
+        );
+    }
+}
+
+const MAX_ROWS: usize = 8;
+const FONT_SIZE: f32 = 16.0;
+const VERTICAL_PADDING: f32 = 4.0;
+const ROW_HEIGHT: f32 = FONT_SIZE * 1.25 + 2.0 * VERTICAL_PADDING;
+const DROPDOWN_PADDING: f32 = 8.0;
+const TOTAL_HEIGHT: f32 = (MAX_ROWS + 1) as f32 * ROW_HEIGHT + 2.0 * DROPDOWN_PADDING;
+
+/// The widths of the id, mass, age and speed columns.
+const COLUMNS: [f32; 4] = [56.0, 56.0, 56.0, 56.0];
+
+#[derive(Debug, Resource, Default, Clone, Copy, PartialEq, Eq)]
+enum RegistrySort {
+    #[default]
+    Mass,
+    Age,
+    Speed,
+}
+
+impl RegistrySort {
+    fn sort(&self, organisms: &mut [&Organism], now: f32) {
+        match self {
+            RegistrySort::Mass => {
+                organisms.sort_by_key(|organism| std::cmp::Reverse(organism.cluster.total_mass()))
+            }
+            RegistrySort::Age => organisms.sort_by(|a, b| b.age(now).total_cmp(&a.age(now))),
+            RegistrySort::Speed => organisms.sort_by(|a, b| b.speed().total_cmp(&a.speed())),
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+struct RegistryList;
+
+#[derive(Debug, Component, Clone, Copy)]
+struct SortButton(RegistrySort);
+
+pub fn organism_registry() -> impl Bundle {
+    dropdown(
+        Icon("icons/registry.png"),
+        "Organism Registry",
+        TOTAL_HEIGHT,
+        contents(),
+    )
+}
+
+fn contents() -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(DROPDOWN_PADDING)),
+            ..default()
+        },
+        children![
+            (
+                Node {
+                    height: Val::Px(ROW_HEIGHT),
+                    align_items: AlignItems::Center,
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    ..default()
+                },
+                children![
+                    cell(COLUMNS[0], "Id"),
+                    sort_button(COLUMNS[1], "Mass", RegistrySort::Mass),
+                    sort_button(COLUMNS[2], "Age", RegistrySort::Age),
+                    sort_button(COLUMNS[3], "Speed", RegistrySort::Speed),
+                ],
+            ),
+            (
+                RegistryList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
+fn cell(width: f32, text: impl Into<String>) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(width),
+            ..default()
+        },
+        Text::new(text),
+        TextFont::from_font_size(FONT_SIZE),
+        Pickable::IGNORE,
+    )
+}
+
+fn sort_button(width: f32, text: &'static str, sort: RegistrySort) -> impl Bundle {
+    (
+        SortButton(sort),
+        Node {
+            width: Val::Px(width),
+            ..default()
+        },
+        BorderRadius::all(Val::Px(4.0)),
+        mixins::hover_colour(Color::NONE, UI_BACKGROUND_FOCUSED),
+        children![(
+            Text::new(text),
+            TextFont::from_font_size(FONT_SIZE),
+            Pickable::IGNORE
+        )],
+        observe(
+            move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                trigger.propagate(false);
+                commands.insert_resource(sort);
+            },
+        ),
+    )
+}
+
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_registry(
+    registry: Res<OrganismRegistry>,
+    sort: Res<RegistrySort>,
+    clock: Res<SimulationClock>,
+    follow_organism: Option<Res<FollowOrganism>>,
+    list: Single<Entity, With<RegistryList>>,
+    sort_buttons: Query<(&SortButton, &Children)>,
+    mut texts: Query<&mut TextColor>,
+    mut commands: Commands,
+) {
+    for (button, children) in &sort_buttons {
+        let alpha = if button.0 == *sort { 1.0 } else { 0.5 };
+        let mut colours = texts.iter_many_mut(children);
+        while let Some(mut colour) = colours.fetch_next() {
+            colour.0 = Color::WHITE.with_alpha(alpha);
+        }
+    }
+
+    let mut organisms = registry.living().iter().collect::<Vec<_>>();
+    sort.sort(&mut organisms, **clock);
+
+    let followed = follow_organism.map(|follow| follow.0);
+
+    commands
+        .entity(*list)
+        .despawn_related::<Children>()
+        .with_children(|list| {
+            for organism in organisms.into_iter().take(MAX_ROWS) {
+                let id = organism.id;
+                let background = if followed == Some(id) {
+                    UI_BACKGROUND_FOCUSED
+                } else {
+                    Color::NONE
+                };
+
+                list.spawn((
+                    Node {
+                        height: Val::Px(ROW_HEIGHT),
+                        align_items: AlignItems::Center,
+                        padding: UiRect::axes(Val::Px(8.0), Val::Px(VERTICAL_PADDING)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(4.0)),
+                    mixins::hover_colour(background, UI_BACKGROUND_FOCUSED),
+                    children![
+                        cell(COLUMNS[0], format!("#{id}")),
+                        cell(COLUMNS[1], organism.cluster.total_mass().to_string()),
+                        cell(COLUMNS[2], format!("{:.0}s", organism.age(**clock))),
+                        cell(COLUMNS[3], format!("{:.0}", organism.speed())),
+                    ],
+                    observe(
+                        move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                            trigger.propagate(false);
+                            commands.trigger(FollowOrganism(id));
+                        },
+                    ),
+                ));
+            }
+        });
+}
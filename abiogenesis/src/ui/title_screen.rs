@@ -3,18 +3,14 @@ use std::time::Duration;
 use bevy::{prelude::*, window::WindowResized};
 use bevy_tweening::{Animator, Delay, Sequence, Tween, TweenCompleted};
 
-use crate::{
-    math::remap,
-    observe::observe,
-    ui::{lenses::TextColourLens, respawn_ui},
-};
+use crate::{math::remap, observe::observe, scenes::Scene, ui::lenses::TextColourLens};
 
 pub struct TitleScreenPlugin;
 
 impl Plugin for TitleScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_title_screen)
-            .add_systems(Update, calculate_ui_scale);
+        app.add_systems(OnEnter(Scene::Title), spawn_title_screen)
+            .add_systems(Update, calculate_ui_scale.run_if(in_state(Scene::Title)));
     }
 }
 
@@ -68,6 +64,7 @@ struct TitleScreen;
 fn title_screen(title_size: f32, subtitle_size: f32) -> impl Bundle {
     (
         TitleScreen,
+        StateScoped(Scene::Title),
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
@@ -112,13 +109,9 @@ fn title_screen(title_size: f32, subtitle_size: f32) -> impl Bundle {
                     ),
                     observe(
                         |trigger: Trigger<TweenCompleted>,
-                         title_screen: Single<Entity, With<TitleScreen>>,
-                         mut commands: Commands| {
+                         mut next_scene: ResMut<NextState<Scene>>| {
                             match trigger.user_data {
-                                0 => {
-                                    commands.run_system_cached(respawn_ui);
-                                    commands.entity(*title_screen).despawn();
-                                }
+                                0 => next_scene.set(Scene::Sandbox),
                                 other => {
                                     tracing::warn!(?other, "unrecognized tween completed event");
                                 }
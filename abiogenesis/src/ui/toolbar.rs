@@ -6,7 +6,11 @@ use bevy_tweening::{Animator, Delay, Sequence, Tween};
 use crate::{
     camera::FollowParticle,
     observe::observe,
-    particles::{colour::ParticleColour, simulation::SimulationParams},
+    particles::{
+        biomes::{BIOMES, SelectedBiome},
+        colour::ParticleColour,
+        simulation::SimulationParams,
+    },
     ui::{
         colours::{UI_BACKGROUND, UI_BACKGROUND_FOCUSED},
         icon::Icon,
@@ -21,6 +25,7 @@ pub enum Tool {
     Camera,
     Particle(ParticleColour),
     Smite,
+    Terraform,
 }
 
 impl Tool {
@@ -28,12 +33,13 @@ impl Tool {
         match self {
             Tool::Camera => 0,
             Tool::Smite => 1,
-            Tool::Particle(ParticleColour::Red) => 2,
-            Tool::Particle(ParticleColour::Green) => 3,
-            Tool::Particle(ParticleColour::Blue) => 4,
-            Tool::Particle(ParticleColour::Orange) => 5,
-            Tool::Particle(ParticleColour::Pink) => 6,
-            Tool::Particle(ParticleColour::Aqua) => 7,
+            Tool::Terraform => 2,
+            Tool::Particle(ParticleColour::Red) => 3,
+            Tool::Particle(ParticleColour::Green) => 4,
+            Tool::Particle(ParticleColour::Blue) => 5,
+            Tool::Particle(ParticleColour::Orange) => 6,
+            Tool::Particle(ParticleColour::Pink) => 7,
+            Tool::Particle(ParticleColour::Aqua) => 8,
         }
     }
 }
@@ -47,7 +53,11 @@ impl Plugin for ToolBarPlugin {
                 Update,
                 update_camera.run_if(resource_changed_or_removed::<FollowParticle>),
             )
-            .add_systems(Update, update_toolbar_on_colour_change);
+            .add_systems(Update, update_toolbar_on_colour_change)
+            .add_systems(
+                Update,
+                update_biome_swatch.run_if(resource_changed::<SelectedBiome>),
+            );
     }
 }
 
@@ -60,7 +70,7 @@ pub fn toolbar() -> impl Bundle {
         Node {
             display: Display::Flex,
             position_type: PositionType::Absolute,
-            width: Val::Px(5. * 50.),
+            width: Val::Px(6. * 50.),
             height: Val::Px(50.0),
             margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Auto, Val::Px(16.0)),
             align_self: AlignSelf::Center,
@@ -86,6 +96,7 @@ pub fn toolbar() -> impl Bundle {
             selection(),
             camera_tool(),
             eraser_tool(),
+            terraform_tool(),
             particle_tool(ParticleColour::Red, false),
             particle_tool(ParticleColour::Green, false),
             particle_tool(ParticleColour::Blue, false),
@@ -187,6 +198,57 @@ fn eraser_tool() -> impl Bundle {
     )
 }
 
+#[derive(Component)]
+struct BiomeSwatch;
+
+fn terraform_tool() -> impl Bundle {
+    (
+        Tool::Terraform,
+        tool(),
+        mixins::tooltip("Terraform"),
+        observe(
+            |mut trigger: Trigger<Pointer<Click>>,
+             tool: Res<Tool>,
+             mut selected_biome: ResMut<SelectedBiome>|
+             -> Result<()> {
+                trigger.propagate(false);
+                if *tool == Tool::Terraform {
+                    *selected_biome = selected_biome.next();
+                }
+
+                Ok(())
+            },
+        ),
+        children![
+            (
+                Node {
+                    width: Val::Px(32.0),
+                    height: Val::Px(32.0),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                Icon("icons/terraform.png"),
+            ),
+            (
+                BiomeSwatch,
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(12.0),
+                    height: Val::Px(12.0),
+                    right: Val::Px(6.0),
+                    bottom: Val::Px(6.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                BorderRadius::all(Val::Percent(50.0)),
+                BorderColor(Color::from(WHITE)),
+                BackgroundColor(BIOMES[0].colour),
+            )
+        ],
+    )
+}
+
 fn particle_tool(color: ParticleColour, new: bool) -> impl Bundle {
     (
         Tool::Particle(color),
@@ -284,8 +346,8 @@ fn update_toolbar_on_colour_change(
                 EaseFunction::CubicInOut,
                 Duration::from_secs_f32(0.2),
                 WidthLens {
-                    start: (*prev_num as f32 + 2.0) * TOOL_SIZE,
-                    end: (params.num_colours as f32 + 2.0) * TOOL_SIZE,
+                    start: (*prev_num as f32 + 3.0) * TOOL_SIZE,
+                    end: (params.num_colours as f32 + 3.0) * TOOL_SIZE,
                 },
             )));
     }
@@ -304,6 +366,17 @@ fn update_toolbar_on_colour_change(
     }
 }
 
+#[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
+fn update_biome_swatch(
+    selected_biome: Res<SelectedBiome>,
+    mut swatches: Query<&mut BackgroundColor, With<BiomeSwatch>>,
+) {
+    // Erasing shows an empty swatch
+    swatches.iter_mut().for_each(|mut swatch| {
+        swatch.0 = selected_biome.colour();
+    });
+}
+
 #[cfg_attr(feature = "hot_reload", bevy_simple_subsecond_system::hot)]
 fn update_camera(
     follow_particle: Option<Res<FollowParticle>>,